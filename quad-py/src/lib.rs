@@ -1,7 +1,7 @@
 use ndarray::Array1;
 use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
-use quad::constants::{FnVec, Myf64};
+use quad::constants::FnVec;
 use quad::errors::*;
 use quad::qag::Qag;
 use std::sync::Arc;
@@ -17,6 +17,7 @@ fn lambda_eval(ob: &Py<PyAny>, z: f64) -> Array1<f64> {
 }
 
 #[pyfunction]
+#[allow(clippy::too_many_arguments)]
 fn qag(
     py: Python,
     ob: Py<PyAny>,
@@ -29,56 +30,19 @@ fn qag(
     points: Option<Vec<f64>>,
     more_info: Option<bool>,
 ) -> PyResult<QagsResult> {
-    let pointss = {
-        if points.is_some() {
-            points.unwrap()
-        } else {
-            [0.0; 0].to_vec()
-        }
-    };
-    let limitt = {
-        if limit.is_some() {
-            limit.unwrap()
-        } else {
-            50
-        }
-    };
-    let keyy = {
-        if key.is_some() {
-            key.unwrap()
-        } else {
-            2
-        }
-    };
-    let epsabss = {
-        if epsabs.is_some() {
-            epsabs.unwrap()
-        } else {
-            1.49e-8
-        }
-    };
-    let epsrell = {
-        if epsrel.is_some() {
-            epsrel.unwrap()
-        } else {
-            1.49e-8
-        }
-    };
-    let more_infoo = {
-        if more_info.is_some() {
-            more_info.unwrap()
-        } else {
-            false
-        }
-    };
+    let pointss = points.unwrap_or_default();
+    let limitt = limit.unwrap_or(50);
+    let keyy = key.unwrap_or(2);
+    let epsabss = epsabs.unwrap_or(1.49e-8);
+    let epsrell = epsrel.unwrap_or(1.49e-8);
+    let more_infoo = more_info.unwrap_or(false);
 
-    let qag = Qag {
-        key: keyy,
-        limit: limitt,
-        points: pointss,
-        number_of_thread: 1,
-        more_info: more_infoo,
-    };
+    let qag = Qag::builder()
+        .key(keyy)
+        .limit(limitt)
+        .points(pointss)
+        .more_info(more_infoo)
+        .build();
 
     let f = |x: f64| lambda_eval(&ob, x);
 
@@ -87,54 +51,55 @@ fn qag(
     };
 
     py.allow_threads(|| {
-        let res = qag.integrate(&fun, a, b, epsabss, epsrell);
-        if res.is_err() {
-            match res.unwrap_err() {
-                QagError::Invalid => {
-                    return Err(PyErr::new::<PyException, _>(INVALID_ERROR_MESSAGE))
-                }
-                QagError::MaxIteration => {
-                    return Err(PyErr::new::<PyException, _>(MAX_ITERATION_ERROR_MESSAGE))
-                }
-                QagError::BadTolerance => {
-                    return Err(PyErr::new::<PyException, _>(BAD_TOLERANCE_ERROR_MESSAGE))
-                }
-                QagError::BadFunction => {
-                    return Err(PyErr::new::<PyException, _>(BAD_FUNCTION_ERROR_MESSAGE))
-                }
-                QagError::Diverge => {
-                    return Err(PyErr::new::<PyException, _>(DIVERGE_ERROR_MESSAGE))
-                }
+        let res = match qag.integrate(&fun, a, b, epsabss, epsrell) {
+            Ok(res) => res,
+            Err(QagError::Invalid) => {
+                return Err(PyErr::new::<PyException, _>(INVALID_ERROR_MESSAGE))
             }
-        }
-        let res = res.unwrap();
-        let (result, abserr, more_inf) = (res.result, res.abserr, res.more_info);
-        if more_inf.is_none() {
-            Ok(QagsResult {
-                result: result.to_vec(),
-                abserr,
-                more_info: None,
-            })
-        } else {
-            let mut more_inf_py: Vec<(f64, f64, f64, Vec<f64>)> = vec![];
-            let more_inf_unwrapped = more_inf.unwrap();
-            let (mut hash, mut heap) = (more_inf_unwrapped.hash, more_inf_unwrapped.heap);
-            let (neval, last) = (more_inf_unwrapped.neval, more_inf_unwrapped.last);
-            for _k in 0..heap.len() {
-                let old_interval = heap.pop().unwrap();
-                let ((x, y), old_err) = (old_interval.interval, old_interval.err);
-                let old_res = hash.remove(&(Myf64 { x }, Myf64 { x: y })).unwrap();
-                more_inf_py.push((x, y, old_err, old_res.to_vec()));
+            Err(QagError::MaxIteration) => {
+                return Err(PyErr::new::<PyException, _>(MAX_ITERATION_ERROR_MESSAGE))
             }
-            Ok(QagsResult {
-                result: result.to_vec(),
-                abserr,
-                more_info: Some((neval, last, more_inf_py)),
-            })
-        }
+            Err(QagError::BadTolerance) => {
+                return Err(PyErr::new::<PyException, _>(BAD_TOLERANCE_ERROR_MESSAGE))
+            }
+            Err(QagError::BadFunction) => {
+                return Err(PyErr::new::<PyException, _>(BAD_FUNCTION_ERROR_MESSAGE))
+            }
+            Err(QagError::Diverge) => {
+                return Err(PyErr::new::<PyException, _>(DIVERGE_ERROR_MESSAGE))
+            }
+            Err(error @ QagError::MissingInterval { .. }) => {
+                return Err(PyErr::new::<PyException, _>(error.to_string()))
+            }
+            Err(
+                error @ (QagError::Panicked(_)
+                | QagError::NotMonotone { .. }
+                | QagError::EmptyIntegrand
+                | QagError::BadSplit { .. }
+                | QagError::Decode
+                | QagError::InvalidBatchSize
+                | QagError::InvalidWeights
+                | QagError::InvalidReparam),
+            ) => return Err(PyErr::new::<PyException, _>(error.to_string())),
+        };
+        let more_inf_py = res.more_info.as_ref().map(|more_info| {
+            let subintervals: Vec<SubIntervalPy> = res
+                .subintervals()
+                .map(|sub| (sub.a, sub.b, sub.abserr, sub.result))
+                .collect();
+            (more_info.neval, more_info.last, subintervals)
+        });
+        Ok(QagsResult {
+            result: res.result.to_vec(),
+            abserr: res.abserr,
+            more_info: more_inf_py,
+        })
     })
 }
 
+/// Sub-interval data exposed to Python: `(a, b, abserr, result)`.
+type SubIntervalPy = (f64, f64, f64, Vec<f64>);
+
 #[pyclass]
 struct QagsResult {
     #[pyo3(get, set)]
@@ -142,7 +107,7 @@ struct QagsResult {
     #[pyo3(get, set)]
     pub abserr: f64,
     #[pyo3(get, set)]
-    pub more_info: Option<(i32, usize, Vec<(f64, f64, f64, Vec<f64>)>)>,
+    pub more_info: Option<(usize, usize, Vec<SubIntervalPy>)>,
 }
 
 #[pymodule]
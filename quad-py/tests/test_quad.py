@@ -87,12 +87,12 @@ class MyTestCase(unittest.TestCase):
         res2 = quad.qag(f, c, a, epsabs, epsrel, key, limit)
 
         assert (
-            res1.result[0] - correct_result[0] < epsabs
-            and res1.result[1] - correct_result[1] < epsabs
+            abs(res1.result[0] - correct_result[0]) < epsabs
+            and abs(res1.result[1] - correct_result[1]) < epsabs
         )
         assert (
-            res2.result[0] - correct_result[0] < epsabs
-            and res2.result[1] - correct_result[1] < epsabs
+            abs(res2.result[0] - correct_result[0]) < epsabs
+            and abs(res2.result[1] - correct_result[1]) < epsabs
         )
 
     def test_double_infinite(self):
@@ -153,6 +153,24 @@ class MyTestCase(unittest.TestCase):
 
         assert sub_interval == sub_interval_expected
 
+    def test_more_info_twice(self):
+        a = 0.0
+        b = 1.0
+        limit = 10000
+        epsabs = 1.0e-10
+        epsrel = 0.0
+        key = 2
+
+        f = lambda x: (math.sqrt(x), math.cos(x))
+
+        res = quad.qag(f, a, b, epsabs, epsrel, key, limit, more_info=True)
+        first = res.more_info
+        second = res.more_info
+        assert first == second
+        assert len(first[2]) == first[1]
+        lefts = [interval[0] for interval in first[2]]
+        assert lefts == sorted(lefts)
+
 
 if __name__ == "__main__":
     unittest.main()
@@ -3,6 +3,8 @@ use criterion::Criterion;
 use criterion::{criterion_group, criterion_main};
 use ndarray::{array, Array1};
 use quad::constants::FnVec;
+use quad::qag::{AutoBatch, Backend, Qag};
+use quad::qag_workspace::QagWorkspace;
 use quad::*;
 use rgsl::*;
 use std::sync::Arc;
@@ -206,8 +208,172 @@ fn key(c: &mut Criterion) {
     group.finish();
 }
 
+fn workspace(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Workspace");
+    group.sample_size(10);
+    let number_of_integrals = 1000000;
+    let qag = Qag::default();
+    let f = FnVec {
+        components: Arc::new(|x: f64| array![x.cos()]),
+    };
+    group.bench_function("Without_workspace", |b| {
+        b.iter(|| {
+            for k in 0..number_of_integrals {
+                let _res = qag.integrate(&f, 0.0, 1.0 + k as f64 * 1.0e-6, 1.0e-8, 0.0);
+            }
+        });
+    });
+    group.bench_function("With_workspace", |b| {
+        let mut ws = QagWorkspace::new();
+        b.iter(|| {
+            for k in 0..number_of_integrals {
+                let _res = qag.integrate_with_workspace(
+                    &mut ws,
+                    &f,
+                    0.0,
+                    1.0 + k as f64 * 1.0e-6,
+                    1.0e-8,
+                    0.0,
+                );
+            }
+        });
+    });
+    group.finish();
+}
+
+fn auto_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Auto_batch");
+    group.sample_size(10);
+    let builder = Qag::builder().limit(1000000).number_of_thread(NUM_THREADS);
+    let qag = builder.clone().build();
+    let qag_auto = builder.clone().auto_batch(AutoBatch::default()).build();
+    for z in [0, 4] {
+        let f = FnVec {
+            components: Arc::new(move |x: f64| {
+                if z > 0 {
+                    thread::sleep(time::Duration::from_nanos(10_u64.pow(z)));
+                }
+                array![x.cos()]
+            }),
+        };
+        group.bench_with_input(BenchmarkId::new("Fixed", z), &z, |b, _| {
+            b.iter(|| qag.integrate(&f, 0.0, 10000.0, 1.0e-10, 0.0));
+        });
+        group.bench_with_input(BenchmarkId::new("Auto", z), &z, |b, _| {
+            b.iter(|| qag_auto.integrate(&f, 0.0, 10000.0, 1.0e-10, 0.0));
+        });
+        for batch_size in [16, 1024] {
+            let qag_batch = builder.clone().batch_size(batch_size).build();
+            let id = format!("Batch_size_{}", batch_size);
+            group.bench_with_input(BenchmarkId::new(id, z), &z, |b, _| {
+                b.iter(|| qag_batch.integrate(&f, 0.0, 10000.0, 1.0e-10, 0.0));
+            });
+        }
+    }
+    group.finish();
+}
+
+fn errors_only(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Errors_only");
+    let qag = Qag::builder().limit(1000000).more_info(true).build();
+    let qag_errors_only = Qag::builder().limit(1000000).errors_only(true).build();
+    let f = FnVec {
+        components: Arc::new(|x: f64| array![x.cos(), x.sin()]),
+    };
+    group.bench_function("More_info", |b| {
+        b.iter(|| qag.integrate(&f, 0.0, 10000.0, 1.0e-10, 0.0));
+    });
+    group.bench_function("Errors_only", |b| {
+        b.iter(|| qag_errors_only.integrate(&f, 0.0, 10000.0, 1.0e-10, 0.0));
+    });
+    group.finish();
+}
+
+fn backend(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Backend");
+    group.sample_size(10);
+    let builder = Qag::builder().limit(1000000).number_of_thread(NUM_THREADS);
+    let qag = builder.clone().build();
+    let qag_std = builder.backend(Backend::StdThreads).build();
+    for z in [0, 4] {
+        let f = FnVec {
+            components: Arc::new(move |x: f64| {
+                if z > 0 {
+                    thread::sleep(time::Duration::from_nanos(10_u64.pow(z)));
+                }
+                array![x.sin() / x.sqrt()]
+            }),
+        };
+        group.bench_with_input(BenchmarkId::new("Rayon", z), &z, |b, _| {
+            b.iter(|| qag.integrate(&f, 0.0, 1000.0, 1.0e-10, 0.0));
+        });
+        group.bench_with_input(BenchmarkId::new("StdThreads", z), &z, |b, _| {
+            b.iter(|| qag_std.integrate(&f, 0.0, 1000.0, 1.0e-10, 0.0));
+        });
+    }
+    group.finish();
+}
+
+#[cfg(feature = "smallvec")]
+fn smallvec(c: &mut Criterion) {
+    use quad::qag_smallvec::{smallvec, SmallArray};
+    let mut group = c.benchmark_group("Smallvec");
+    let qag = Qag::builder().limit(1000000).build();
+    let f = FnVec {
+        components: Arc::new(|x: f64| array![x.cos(), x.sin(), x.cos() * x, x.sin() * x]),
+    };
+    let f_small = |x: f64| -> SmallArray { smallvec![x.cos(), x.sin(), x.cos() * x, x.sin() * x] };
+    group.bench_function("Array1", |b| {
+        b.iter(|| qag.integrate(&f, 0.0, 1000.0, 1.0e-10, 0.0));
+    });
+    group.bench_function("SmallVec", |b| {
+        b.iter(|| qag.integrate_small(f_small, 0.0, 1000.0, 1.0e-10, 0.0));
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "smallvec"))]
+fn smallvec(_c: &mut Criterion) {}
+
+#[cfg(feature = "simd")]
+fn simd(c: &mut Criterion) {
+    use quad::qk61::qk61_quadrature;
+    use quad::qk61_simd::{f64x4, qk61_4vec_quadrature};
+
+    let mut group = c.benchmark_group("Simd");
+    let f = |x: f64| [x.sin(), x.cos(), (-x * x).exp(), 1.0 / (1.0 + x * x)];
+    group.bench_function("Scalar_qk61", |b| {
+        b.iter(|| {
+            qk61_quadrature(
+                |x: f64| {
+                    let y = f(x);
+                    array![y[0], y[1], y[2], y[3]]
+                },
+                0.0,
+                1.0,
+            )
+        });
+    });
+    group.bench_function("Simd_qk61", |b| {
+        b.iter(|| qk61_4vec_quadrature(|x: f64| f64x4::from(f(x)), 0.0, 1.0));
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "simd"))]
+fn simd(_c: &mut Criterion) {}
+
 criterion_group!(benches1, qag_delay);
 criterion_group!(benches2, fn_lenght);
 criterion_group!(benches3, number_of_interval_subdivision);
 criterion_group!(benches4, key);
-criterion_main!(benches1, benches2, benches3, benches4);
+criterion_group!(benches5, workspace);
+criterion_group!(benches6, simd);
+criterion_group!(benches7, auto_batch);
+criterion_group!(benches8, errors_only);
+criterion_group!(benches9, smallvec);
+criterion_group!(benches10, backend);
+criterion_main!(
+    benches1, benches2, benches3, benches4, benches5, benches6, benches7, benches8, benches9,
+    benches10
+);
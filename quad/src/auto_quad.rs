@@ -0,0 +1,301 @@
+use crate::constants::{norm_ar, FnVec, EPMACH};
+use crate::errors::QagError;
+use crate::qag::{LimitBehavior, OutputMap, Qag};
+use crate::qag_integration_result::{MoreInfo, QagIntegrationResult};
+use crate::qag_workspace::QagWorkspace;
+use ndarray::Array1;
+use std::sync::Arc;
+/// Default [stall_ratio](Quad::stall_ratio).
+pub const QUAD_STALL_RATIO: f64 = 0.5;
+/// Number of iterations of [Qag] over which the decay of the error is measured.
+pub const QUAD_STALL_WINDOW: usize = 5;
+/// Largest number of terms of the extrapolated sequence of [integrate](Quad::integrate).
+pub const QUAD_MAX_TERMS: usize = 60;
+
+/// Integrator running [Qag], which switches to the epsilon extrapolation if the error decays
+/// too slowly, as for an integrable singularity at an end of the interval.
+#[derive(Clone)]
+pub struct Quad {
+    /// Integrator used first, and for the pieces of the extrapolated sequence.
+    pub qag: Qag,
+    /// Largest average reduction factor of the error per iteration over the last
+    /// [QUAD_STALL_WINDOW] iterations of [Qag] which counts as a stall.
+    pub stall_ratio: f64,
+}
+
+impl Quad {
+    pub fn new(qag: Qag) -> Self {
+        Self {
+            qag,
+            stall_ratio: QUAD_STALL_RATIO,
+        }
+    }
+
+    /// Adaptive integration of a vector-valued function, escalating to the epsilon
+    /// extrapolation when [Qag] stalls.
+    ///
+    /// [Qag] runs first. If it reaches [limit](Qag::limit) and the error was decaying slower
+    /// than [stall_ratio](Quad::stall_ratio) per iteration, the point p where the sub-intervals
+    /// have been refined the most, an end of the smallest one, is taken as a singularity. The
+    /// integral is then computed as the limit of the integrals over the interval without
+    /// (p - L 2^-k, p + R 2^-k), where L and R are the distances of p from the bounds: every
+    /// term adds two pieces integrated by [Qag], and the sequence, converging geometrically for
+    /// an algebraic singularity, is accelerated by the epsilon algorithm of Wynn. The error is
+    /// the change of the last extrapolations plus the errors of the pieces, and [MoreInfo] is
+    /// not available.
+    ///
+    /// If the extrapolation does not converge either, the outcome of [Qag] is returned. The
+    /// integrand is transformed as in [integrate](Qag::integrate), and the extrapolation is
+    /// performed in the transformed variable, e.g. in u for a [reparam](Qag::reparam).
+    pub fn integrate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let core = |qag: &Qag,
+                    ws: &mut QagWorkspace,
+                    f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+                    a: f64,
+                    b: f64,
+                    epsabs: f64,
+                    epsrel: f64,
+                    _: &OutputMap| {
+            let quad = Quad {
+                qag: qag.clone(),
+                ..self.clone()
+            };
+            let fun = FnVec {
+                components: Arc::new(f),
+            };
+            Ok((quad.qintegrate(ws, &fun, a, b, epsabs, epsrel)?, ()))
+        };
+        let mut ws = QagWorkspace::new();
+        let f = &*fun.components;
+        let map = OutputMap::default();
+        let (res, ()) = self
+            .qag
+            .integrate_pipeline(&mut ws, f, a, b, epsabs, epsrel, &map, &core)?;
+        Ok(res)
+    }
+
+    /// Same as [integrate](Quad::integrate) over the finite interval ('a', 'b'), after the
+    /// transformations of the integrand.
+    fn qintegrate(
+        &self,
+        ws: &mut QagWorkspace,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let qag = Qag {
+            limit_behavior: LimitBehavior::ReturnBest,
+            ..self.qag.clone()
+        };
+        ws.more_info = Some(true);
+        let mut res = qag.qintegrate_with_workspace(ws, fun, a, b, epsabs, epsrel)?;
+        let more_info = if self.qag.more_info {
+            res.more_info.clone()
+        } else {
+            res.more_info.take()
+        };
+        let Some(more_info) = more_info else {
+            return Ok(res);
+        };
+        if res.failure.is_none() || !self.stalled(&more_info) {
+            return self.qag_outcome(res);
+        }
+
+        match self.extrapolate(fun, a, b, epsabs, epsrel, singularity(&more_info, a, b)) {
+            Some(res) => Ok(res),
+            None => self.qag_outcome(res),
+        }
+    }
+
+    /// Result of [Qag] with its own [limit_behavior](Qag::limit_behavior).
+    fn qag_outcome(&self, res: QagIntegrationResult) -> Result<QagIntegrationResult, QagError> {
+        match (&res.failure, self.qag.limit_behavior) {
+            (Some(failure), LimitBehavior::Error) => Err(failure.clone()),
+            _ => Ok(res),
+        }
+    }
+
+    /// Whether the error decayed slower than [stall_ratio](Quad::stall_ratio) per iteration over
+    /// the last [QUAD_STALL_WINDOW] iterations.
+    fn stalled(&self, more_info: &MoreInfo) -> bool {
+        let history = &more_info.history;
+        if history.len() <= QUAD_STALL_WINDOW {
+            return false;
+        }
+        let last = history[history.len() - 1].1;
+        let first = history[history.len() - 1 - QUAD_STALL_WINDOW].1;
+        first > 0.0 && (last / first).powf(1.0 / QUAD_STALL_WINDOW as f64) > self.stall_ratio
+    }
+
+    /// Epsilon extrapolation of the integrals over ('a', 'b') without shrinking neighbourhoods
+    /// of 'p', if it converges.
+    fn extrapolate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        p: f64,
+    ) -> Option<QagIntegrationResult> {
+        let qag = Qag {
+            more_info: false,
+            limit_behavior: LimitBehavior::Error,
+            ..self.qag.clone()
+        };
+        let (left, right) = (p - a, b - p);
+        let mut partial: Option<Array1<f64>> = None;
+        let mut pieces_err = 0.0;
+        let mut sequence: Vec<Array1<f64>> = vec![];
+        let mut extrapolated: Vec<Array1<f64>> = vec![];
+        for k in 0..QUAD_MAX_TERMS as i32 {
+            let (outer, inner) = (0.5_f64.powi(k), 0.5_f64.powi(k + 1));
+            let mut term = partial.clone();
+            for (lo, hi) in [
+                (p - left * outer, p - left * inner),
+                (p + right * inner, p + right * outer),
+            ] {
+                if hi - lo <= EPMACH * lo.abs().max(hi.abs()) {
+                    continue;
+                }
+                let piece = qag.qintegrate(fun, lo, hi, 0.1 * epsabs, epsrel).ok()?;
+                pieces_err += piece.abserr;
+                term = Some(match term {
+                    Some(term) => term + piece.result,
+                    None => piece.result,
+                });
+            }
+            let term = term?;
+            partial = Some(term.clone());
+            sequence.push(term);
+            extrapolated.push(epsilon(&sequence));
+
+            let len = extrapolated.len();
+            if len < 3 {
+                continue;
+            }
+            let result = &extrapolated[len - 1];
+            let abserr = norm_ar(&(result - &extrapolated[len - 2]))
+                + norm_ar(&(result - &extrapolated[len - 3]))
+                + pieces_err;
+            if abserr <= self.qag.tolerance_logic.errbnd(epsabs, epsrel, result) {
+                let mut res = QagIntegrationResult::new(result.clone(), abserr);
+                res.success_kind = Some(
+                    self.qag
+                        .tolerance_logic
+                        .success_kind(epsabs, epsrel, result),
+                );
+                return Some(res);
+            }
+        }
+        None
+    }
+}
+
+/// Point where the sub-intervals of [MoreInfo] have been refined the most: the end of the
+/// smallest one lying on a bound or, if none does, its midpoint.
+fn singularity(more_info: &MoreInfo, a: f64, b: f64) -> f64 {
+    let smallest = more_info
+        .heap
+        .iter()
+        .map(|item| item.interval)
+        .min_by(|x, y| (x.1 - x.0).total_cmp(&(y.1 - y.0)))
+        .unwrap_or((a, b));
+    if smallest.0 == a {
+        a
+    } else if smallest.1 == b {
+        b
+    } else {
+        0.5 * (smallest.0 + smallest.1)
+    }
+}
+
+/// Highest even column of the epsilon table of Wynn built on 'sequence', component-wise.
+fn epsilon(sequence: &[Array1<f64>]) -> Array1<f64> {
+    let n = sequence[0].len();
+    Array1::from_iter((0..n).map(|i| {
+        let mut previous = vec![0.0; sequence.len() + 1];
+        let mut current: Vec<f64> = sequence.iter().map(|s| s[i]).collect();
+        let mut best = current[current.len() - 1];
+        let mut column = 0;
+        while current.len() > 1 {
+            let mut next = Vec::with_capacity(current.len() - 1);
+            for j in 0..current.len() - 1 {
+                let delta = current[j + 1] - current[j];
+                if delta.abs() <= EPMACH * current[j + 1].abs() {
+                    return if column % 2 == 0 {
+                        current[j + 1]
+                    } else {
+                        best
+                    };
+                }
+                next.push(previous[j + 1] + 1.0 / delta);
+            }
+            previous = current;
+            current = next;
+            column += 1;
+            if column % 2 == 0 {
+                best = current[current.len() - 1];
+            }
+        }
+        best
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::epsilon;
+    use crate::auto_quad::Quad;
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use ndarray::{array, Array1};
+    use std::sync::Arc;
+
+    #[test]
+    fn wynn() {
+        // Partial sums of the series of ln(2).
+        let sequence: Vec<Array1<f64>> = (1..=12)
+            .map(|n| {
+                let sum: f64 = (1..=n)
+                    .map(|k| if k % 2 == 1 { 1.0 } else { -1.0 } / k as f64)
+                    .sum();
+                array![sum]
+            })
+            .collect();
+        assert!((sequence[11][0] - 2.0_f64.ln()).abs() > 1.0e-2);
+        assert!((epsilon(&sequence)[0] - 2.0_f64.ln()).abs() < 1.0e-8);
+    }
+
+    #[test]
+    fn inverse_sqrt() {
+        let qag = Qag::default();
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![1.0 / x.sqrt(), x.cos() / x.sqrt()]),
+        };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::MaxIteration);
+
+        let quad = Quad::new(qag.clone());
+        let res = quad.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        assert!((res.result[0] - 2.0).abs() < 1.0e-10);
+        assert!(res.abserr <= 1.0e-10);
+
+        // Smooth integrands converge within Qag.
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x.exp()]),
+        };
+        let res = quad.integrate(&g, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let res_qag = qag.integrate(&g, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        assert_eq!(res.result, res_qag.result);
+    }
+}
@@ -1,7 +1,9 @@
 #[cfg(doc)]
 use crate::errors::QagError;
+#[cfg(doc)]
 use crate::qag::Qag;
 
+use crate::qag::GaussKronrodRule;
 use ndarray::Array1;
 use std::cmp::Ordering;
 use std::hash;
@@ -19,6 +21,12 @@ pub struct FnVec<'a> {
 pub const EPMACH: f64 = f64::EPSILON;
 /// Smallest positive normal `f64` value.
 pub const UFLOW: f64 = f64::MIN_POSITIVE;
+/// Ratio between the roundoff error of a Gauss-Kronrod rule and EPMACH times the integral of
+/// the absolute value of the integrand.
+pub const ROUNDOFF_FACTOR: f64 = 50.0;
+/// Ratio |centre| / half-length of a sub-interval above which the abscissae of the
+/// Gauss-Kronrod rules are computed relative to its bounds, see [abscissae](crate::qk::abscissae).
+pub const ABSCISSA_SHIFT_RATIO: f64 = 1.0e3;
 /// Parameter of [iroff1_flag].
 pub const IROFF_PARAMETER1: f64 = 0.00001;
 /// Parameter of [iroff1_flag].
@@ -31,13 +39,28 @@ pub const IROFF2_THRESHOLD: i32 = 20;
 pub const BAD_FUNCTION_PARAMETER1: f64 = 100.0;
 /// Parameter of [bad_function_flag].
 pub const BAD_FUNCTION_PARAMETER2: f64 = 1000.0;
+/// Number of function evaluations of [integrate](Qag::integrate) with the Gauss-Kronrod rule
+/// 'key' and 'subintervals' sub-intervals.
+///
+/// With the maximum number of sub-intervals [limit](Qag::limit) it is the worst-case cost of an
+/// integration.
+pub fn evals_for(key: GaussKronrodRule, subintervals: usize) -> usize {
+    if key == GaussKronrodRule::G7K15 {
+        subintervals.saturating_mul(30).saturating_add(15)
+    } else {
+        subintervals
+            .saturating_mul(2)
+            .saturating_sub(1)
+            .saturating_mul(key.points())
+    }
+}
 /// Norm of an [Array1].
 pub fn norm_ar(ar: &Array1<f64>) -> f64 {
     ar.iter().map(|x| x.powi(2)).sum::<f64>().sqrt()
 }
 /// Transform the list of additional points in case of semi-infinite or infinite interval.
 pub fn points_transformed(mut points: Vec<f64>, a: f64, b: f64) -> Vec<f64> {
-    points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    points.sort_by(f64::total_cmp);
     let mut points_transformed = vec![0.0; 0];
     for point in &points {
         points_transformed.push(if b == f64::INFINITY && a.is_finite() {
@@ -64,7 +87,7 @@ pub fn iroff1_flag(
             return false;
         }
     }
-    return true;
+    true
 }
 /// Condition to return a [BadFunction](QagError::BadFunction) .
 pub fn bad_function_flag(x: f64, y: f64) -> bool {
@@ -81,11 +104,21 @@ pub fn bad_function_flag(x: f64, y: f64) -> bool {
 pub struct HeapItem {
     pub interval: (f64, f64),
     pub err: f64,
+    /// Number of bisections that led from an initial interval to this one.
+    pub depth: u32,
 }
 
 impl HeapItem {
     pub fn new(interval: (f64, f64), err: f64) -> Self {
-        Self { interval, err }
+        Self::new_with_depth(interval, err, 0)
+    }
+
+    pub fn new_with_depth(interval: (f64, f64), err: f64, depth: u32) -> Self {
+        Self {
+            interval,
+            err,
+            depth,
+        }
     }
 }
 
@@ -99,7 +132,7 @@ impl PartialEq for HeapItem {
 
 impl Ord for HeapItem {
     fn cmp(&self, other: &Self) -> Ordering {
-        (self.err).partial_cmp(&other.err).unwrap()
+        self.err.total_cmp(&other.err)
     }
 }
 
@@ -0,0 +1,67 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use ndarray::array;
+pub use num_complex::Complex;
+use std::sync::Arc;
+
+impl Qag {
+    /// Integration of a complex function along a contour segment.
+    ///
+    /// The contour is parametrized as z(t), t in (0,1), and 'path' returns (z(t), z'(t)). The
+    /// integral of f(z(t)) z'(t) dt is then computed by [integrate](Qag::integrate) as a
+    /// two-components real integrand (real and imaginary part).
+    ///
+    /// Returns the complex result and the absolute error.
+    pub fn contour_integrate<F, P>(
+        &self,
+        f: F,
+        path: P,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(Complex<f64>, f64), QagError>
+    where
+        F: Fn(Complex<f64>) -> Complex<f64> + Send + Sync,
+        P: Fn(f64) -> (Complex<f64>, Complex<f64>) + Send + Sync,
+    {
+        let fun = FnVec {
+            components: Arc::new(|t: f64| {
+                let (z, dz) = path(t);
+                let res = f(z) * dz;
+                array![res.re, res.im]
+            }),
+        };
+        let res = self.integrate(&fun, 0.0, 1.0, epsabs, epsrel)?;
+        Ok((Complex::new(res.result[0], res.result[1]), res.abserr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contour::Complex;
+    use crate::qag::Qag;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn unit_circle() {
+        let epsabs = 1.0e-10;
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let circle = |t: f64| {
+            let z = Complex::new(0.0, 2.0 * PI * t).exp();
+            (z, Complex::new(0.0, 2.0 * PI) * z)
+        };
+
+        let (res, _) = qag
+            .contour_integrate(|z: Complex<f64>| 1.0 / z, circle, epsabs, 0.0)
+            .unwrap();
+        assert!((res - Complex::new(0.0, 2.0 * PI)).norm() < epsabs);
+
+        let (res, _) = qag
+            .contour_integrate(|z: Complex<f64>| z * z, circle, epsabs, 0.0)
+            .unwrap();
+        assert!(res.norm() < epsabs);
+    }
+}
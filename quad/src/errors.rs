@@ -7,24 +7,76 @@ use std::fmt;
 pub enum QagError {
     Invalid,
     MaxIteration,
+    /// Roundoff prevents the requested tolerance from being achieved: either the accumulated
+    /// roundoff exceeds the error estimate, or bisections stopped improving the result or the
+    /// error too many times (see [roundoff_warmup](Qag::roundoff_warmup)).
     BadTolerance,
     BadFunction,
     Diverge,
+    /// Internal bookkeeping error: the interval ('a', 'b') popped from the heap has no cached
+    /// result.
+    MissingInterval {
+        a: f64,
+        b: f64,
+    },
+    /// A panic caught by [try_integrate](Qag::try_integrate), e.g. raised by the integrand or by
+    /// components of inconsistent lengths, with its message.
+    Panicked(String),
+    /// The integrand of [MonotoneQuad](crate::monotone_quad::MonotoneQuad) is not monotone: its
+    /// value at 'x' does not lie between the ones at the ends of the bisected sub-interval.
+    NotMonotone {
+        x: f64,
+    },
+    /// The integrand returned a vector with no components, so there is nothing to integrate. It
+    /// is an invalid input, reported apart from [Invalid](QagError::Invalid), which refers to the
+    /// tolerances.
+    EmptyIntegrand,
+    /// The point 'x' returned by [split_fn](Qag::split_fn) does not lie strictly inside the
+    /// sub-interval ('a', 'b') to bisect.
+    BadSplit {
+        a: f64,
+        b: f64,
+        x: f64,
+    },
+    /// The bounds lie outside the domain of [reparam](Qag::reparam), e.g. a <= 0 for
+    /// [Log](crate::qag::Reparam::Log).
+    InvalidReparam,
+    /// The [norm_weights](Qag::norm_weights) are not one per component, or are not all positive
+    /// and finite.
+    InvalidWeights,
+    /// The [batch_size](Qag::batch_size) is 0.
+    InvalidBatchSize,
+    /// The bytes passed to [from_bytes](crate::qag_integration_result::MoreInfo::from_bytes)
+    /// are not a valid serialization.
+    Decode,
 }
 
 impl fmt::Display for QagError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let error_message: &str;
         match self {
-            QagError::Invalid => error_message = INVALID_ERROR_MESSAGE,
-            QagError::MaxIteration => error_message = MAX_ITERATION_ERROR_MESSAGE,
-            QagError::BadTolerance => error_message = BAD_TOLERANCE_ERROR_MESSAGE,
-            QagError::BadFunction => error_message = BAD_FUNCTION_ERROR_MESSAGE,
-            QagError::Diverge => error_message = DIVERGE_ERROR_MESSAGE,
+            QagError::Invalid => write!(f, "{}", INVALID_ERROR_MESSAGE),
+            QagError::MaxIteration => write!(f, "{}", MAX_ITERATION_ERROR_MESSAGE),
+            QagError::BadTolerance => write!(f, "{}", BAD_TOLERANCE_ERROR_MESSAGE),
+            QagError::BadFunction => write!(f, "{}", BAD_FUNCTION_ERROR_MESSAGE),
+            QagError::Diverge => write!(f, "{}", DIVERGE_ERROR_MESSAGE),
+            QagError::MissingInterval { a, b } => {
+                write!(f, "{} ({}, {})", MISSING_INTERVAL_ERROR_MESSAGE, a, b)
+            }
+            QagError::Panicked(message) => write!(f, "{} {}", PANICKED_ERROR_MESSAGE, message),
+            QagError::NotMonotone { x } => write!(f, "{} {}", NOT_MONOTONE_ERROR_MESSAGE, x),
+            QagError::EmptyIntegrand => write!(f, "{}", EMPTY_INTEGRAND_ERROR_MESSAGE),
+            QagError::BadSplit { a, b, x } => {
+                write!(f, "{} {} ({}, {})", BAD_SPLIT_ERROR_MESSAGE, x, a, b)
+            }
+            QagError::InvalidReparam => write!(f, "{}", INVALID_REPARAM_ERROR_MESSAGE),
+            QagError::InvalidWeights => write!(f, "{}", INVALID_WEIGHTS_ERROR_MESSAGE),
+            QagError::InvalidBatchSize => write!(f, "{}", INVALID_BATCH_SIZE_ERROR_MESSAGE),
+            QagError::Decode => write!(f, "{}", DECODE_ERROR_MESSAGE),
         }
-        write!(f, "{}", error_message)
     }
 }
+
+impl std::error::Error for QagError {}
 /// Error message about reaching the max iteration [limit](Qag::limit).
 pub const MAX_ITERATION_ERROR_MESSAGE: &str =
     "Maximum number of subdivisions allowed has been achieved. One can allow more subdivisions by \
@@ -47,3 +99,59 @@ pub const BAD_FUNCTION_ERROR_MESSAGE: &str =
 /// Error message about probably divergent integrand.
 pub const DIVERGE_ERROR_MESSAGE: &str = "The integral is probably divergent, or slowly convergent.\
     It must be noted that divergence can occur with any other value of ResultState.";
+/// Error message about an interval missing from the cache of the sub-interval results.
+pub const MISSING_INTERVAL_ERROR_MESSAGE: &str =
+    "Internal error: no cached result for the sub-interval selected for bisection. This is a bug, \
+    please report it together with the integrand, the bounds and the additional points used. \
+    Missing interval:";
+/// Error message about a panic raised during the integration.
+pub const PANICKED_ERROR_MESSAGE: &str = "The integration panicked:";
+/// Error message about an integrand assumed monotone which is not.
+pub const NOT_MONOTONE_ERROR_MESSAGE: &str =
+    "The integrand is not monotone on the integration interval, as assumed: the monotonicity is \
+    violated at x =";
+/// Error message about an integrand with no components.
+pub const EMPTY_INTEGRAND_ERROR_MESSAGE: &str =
+    "The input is invalid, because the integrand returned an empty vector: it must have at \
+    least one component.";
+/// Error message about a split point outside the sub-interval to bisect.
+pub const BAD_SPLIT_ERROR_MESSAGE: &str =
+    "The input is invalid, because split_fn returned a point which does not lie strictly inside \
+    the sub-interval to bisect. Split point and sub-interval:";
+/// Error message about bounds outside the domain of the reparametrization.
+pub const INVALID_REPARAM_ERROR_MESSAGE: &str =
+    "The input is invalid, because the bounds lie outside the domain of the reparametrization, \
+    e.g. a <= 0 for Reparam::Log.";
+/// Error message about invalid weights of the norm.
+pub const INVALID_WEIGHTS_ERROR_MESSAGE: &str =
+    "The input is invalid, because norm_weights must contain one positive and finite weight per \
+    component of the integrand.";
+/// Error message about a batch size of 0.
+pub const INVALID_BATCH_SIZE_ERROR_MESSAGE: &str =
+    "The input is invalid, because batch_size must be at least 1.";
+/// Error message about bytes which are not a valid serialization of MoreInfo.
+pub const DECODE_ERROR_MESSAGE: &str =
+    "The bytes are not a valid serialization of MoreInfo: they are truncated, have a wrong \
+    header or do not match the lengths in the header.";
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{QagError, EMPTY_INTEGRAND_ERROR_MESSAGE, MISSING_INTERVAL_ERROR_MESSAGE};
+
+    #[test]
+    fn missing_interval_message() {
+        let error = QagError::MissingInterval { a: 0.25, b: 0.5 };
+        let message = error.to_string();
+        assert!(message.starts_with(MISSING_INTERVAL_ERROR_MESSAGE));
+        assert!(message.ends_with("(0.25, 0.5)"));
+    }
+
+    #[test]
+    fn empty_integrand_message() {
+        assert!(!EMPTY_INTEGRAND_ERROR_MESSAGE.contains("  "));
+        assert_eq!(
+            QagError::EmptyIntegrand.to_string(),
+            EMPTY_INTEGRAND_ERROR_MESSAGE
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use crate::constants::{norm_ar, EPMACH};
+use crate::errors::QagError;
+use crate::patterson_quad::solve;
+use ndarray::Array1;
+use std::sync::Mutex;
+/// Number of points of the nested Genz-Keister rules.
+pub const GENZ_KEISTER_POINTS: [usize; 5] = [1, 3, 9, 19, 35];
+
+/// Non-adaptive integrator with the nested Genz-Keister rules of 1, 3, 9, 19 and 35 points, for
+/// integrals over the real line with the Gaussian weight exp(-x^2).
+///
+/// The rules extend the Gauss-Hermite rule of 3 points as the Gauss-Kronrod-Patterson rules of
+/// [PattersonQuad](crate::patterson_quad::PattersonQuad) extend the Gauss-Legendre one: every
+/// rule contains all the abscissae of the previous one and integrates exactly the polynomials
+/// of degree 1, 5, 15, 29 and 51 respectively. Being nested, they are the 1-D building blocks of
+/// sparse-grid cubature. The integrand f is the function multiplying the weight, so
+/// ∫ f(x) exp(-x^2) dx is computed.
+///
+/// The rules are computed once, at the first use.
+#[derive(Clone, Debug)]
+pub struct GenzKeisterQuad {
+    /// Maximum number of points of the last rule, at most 35.
+    pub max_points: usize,
+}
+
+/// Result of [integrate](GenzKeisterQuad::integrate).
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct GenzKeisterResult {
+    pub result: Array1<f64>,
+    /// Norm of the difference between the results of the last two rules.
+    pub abserr: f64,
+    /// Number of evaluations of the integrand, the number of points of the last rule.
+    pub neval: usize,
+}
+
+/// Nodes and weights of a rule: the nodes are the ones of the previous rule followed by the new
+/// ones, the weights refer to all of them in the same order.
+struct GenzKeisterRule {
+    nodes: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+/// Rules computed so far, shared by all the integrations.
+static RULES: Mutex<Vec<GenzKeisterRule>> = Mutex::new(Vec::new());
+
+impl GenzKeisterQuad {
+    /// Integration of a vector-valued function times exp(-x^2) over the real line.
+    ///
+    /// The rules of 1 and 3 points are always used, then points are added until the norm of the
+    /// difference between the last two results satisfies the tolerance. If it does not with
+    /// [max_points](GenzKeisterQuad::max_points), [MaxIteration](QagError::MaxIteration) is
+    /// returned.
+    pub fn integrate<F>(
+        &self,
+        f: F,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<GenzKeisterResult, QagError>
+    where
+        F: Fn(f64) -> Vec<f64>,
+    {
+        if self.max_points < 3 || (epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH)) {
+            return Err(QagError::Invalid);
+        }
+
+        let mut values: Vec<Array1<f64>> = vec![];
+        let mut previous: Option<Array1<f64>> = None;
+        for (level, &points) in GENZ_KEISTER_POINTS.iter().enumerate() {
+            if points > self.max_points {
+                break;
+            }
+            let (nodes, weights) = genz_keister_rule(level);
+            for &x in &nodes[values.len()..] {
+                values.push(Array1::from(f(x)));
+            }
+            let mut result = Array1::zeros(values[0].len());
+            for (value, &w) in values.iter().zip(&weights) {
+                result.scaled_add(w, value);
+            }
+            if let Some(previous) = previous {
+                let abserr = norm_ar(&(&result - &previous));
+                if abserr <= epsabs.max(epsrel * norm_ar(&result)) {
+                    return Ok(GenzKeisterResult {
+                        result,
+                        abserr,
+                        neval: values.len(),
+                    });
+                }
+            }
+            previous = Some(result);
+        }
+        Err(QagError::MaxIteration)
+    }
+}
+
+/// Nodes and weights of the Genz-Keister rule of [GENZ_KEISTER_POINTS]\[level\] points, for the
+/// weight exp(-x^2), computing and caching the missing rules.
+///
+/// The nodes of the previous rule come first, followed by the new ones.
+pub fn genz_keister_rule(level: usize) -> (Vec<f64>, Vec<f64>) {
+    assert!(level < GENZ_KEISTER_POINTS.len());
+    let mut rules = RULES.lock().unwrap();
+    while rules.len() <= level {
+        let nodes = match rules.last() {
+            None => vec![0.0],
+            Some(last) => {
+                let mut nodes = last.nodes.clone();
+                nodes.extend(extension(
+                    &last.nodes,
+                    GENZ_KEISTER_POINTS[rules.len()] - last.nodes.len(),
+                ));
+                nodes
+            }
+        };
+        let weights = interpolatory_weights(&nodes);
+        rules.push(GenzKeisterRule { nodes, weights });
+    }
+    (rules[level].nodes.clone(), rules[level].weights.clone())
+}
+
+/// Hermite polynomials h_0(x), ..., h_n(x), orthonormal with the weight exp(-x^2).
+fn hermite(x: f64, n: usize) -> Vec<f64> {
+    let mut h = vec![std::f64::consts::PI.powf(-0.25)];
+    h.push(2.0_f64.sqrt() * x * h[0]);
+    for k in 1..n {
+        let k = k as f64;
+        let next =
+            (2.0 / (k + 1.0)).sqrt() * x * h[h.len() - 1] - (k / (k + 1.0)).sqrt() * h[h.len() - 2];
+        h.push(next);
+    }
+    h.truncate(n + 1);
+    h
+}
+
+/// Gauss-Hermite rule of 'n' points, used to compute the integrals of polynomials times
+/// exp(-x^2).
+pub fn gauss_hermite(n: usize) -> Vec<(f64, f64)> {
+    let mut rule = vec![(0.0, 0.0); n];
+    for i in 0..n - n / 2 {
+        let mut z = match i {
+            0 => {
+                let m = (2 * n + 1) as f64;
+                m.sqrt() - 1.85575 * m.powf(-1.0 / 6.0)
+            }
+            1 => {
+                let z = rule[0].0;
+                z - 1.14 * (n as f64).powf(0.426) / z
+            }
+            2 => 1.86 * rule[1].0 - 0.86 * rule[0].0,
+            3 => 1.91 * rule[2].0 - 0.91 * rule[1].0,
+            _ => 2.0 * rule[i - 1].0 - rule[i - 2].0,
+        };
+        let mut dh = 1.0;
+        for _ in 0..100 {
+            let h = hermite(z, n);
+            dh = (2.0 * n as f64).sqrt() * h[n - 1];
+            let dz = h[n] / dh;
+            z -= dz;
+            if dz.abs() <= EPMACH * z.abs().max(1.0) {
+                break;
+            }
+        }
+        let w = 2.0 / (dh * dh);
+        rule[i] = (z, w);
+        rule[n - 1 - i] = (-z, w);
+    }
+    rule
+}
+
+/// The 'm' nodes extending the 'nodes' of a rule of n points, the roots of the polynomial of
+/// degree m orthogonal to all lower degrees with the weight prod_i (x - nodes_i) exp(-x^2).
+///
+/// It panics if the roots are not all real and distinct from the nodes, i.e. if the extension
+/// does not exist.
+fn extension(nodes: &[f64], m: usize) -> Vec<f64> {
+    // The integrands have degree at most n + 2m - 1.
+    let gauss = gauss_hermite((nodes.len() + 2 * m) / 2 + 1);
+    let quadrature: Vec<_> = gauss
+        .iter()
+        .map(|&(x, w)| {
+            (
+                w * nodes.iter().map(|&node| x - node).product::<f64>(),
+                hermite(x, m),
+            )
+        })
+        .collect();
+    // The polynomial is the sum of c_j h_j, with c_m = 1.
+    let matrix = (0..m)
+        .map(|k| {
+            (0..m)
+                .map(|j| quadrature.iter().map(|(w, h)| w * h[j] * h[k]).sum())
+                .collect()
+        })
+        .collect();
+    let rhs = (0..m)
+        .map(|k| -quadrature.iter().map(|(w, h)| w * h[m] * h[k]).sum::<f64>())
+        .collect();
+    let mut c = solve(matrix, rhs);
+    c.push(1.0);
+    let polynomial = |x: f64| -> f64 { hermite(x, m).iter().zip(&c).map(|(h, c)| h * c).sum() };
+
+    // The roots lie within the Gauss-Hermite nodes of the same degree, and the polynomial is
+    // even, as the nodes include the origin, so the positive roots are bracketed by scanning a
+    // fine grid and mirrored.
+    let bound = gauss_hermite(m + nodes.len()).first().unwrap().0 + 1.0;
+    let steps = 1 << 15;
+    let grid = |i: usize| bound * i as f64 / steps as f64;
+    let mut roots = vec![];
+    for i in 0..steps {
+        let (mut lo, mut hi) = (grid(i), grid(i + 1));
+        let sign_lo = polynomial(lo).signum();
+        if sign_lo == polynomial(hi).signum() {
+            continue;
+        }
+        while hi - lo > 2.0 * EPMACH * lo.abs().max(hi.abs()) {
+            let mid = 0.5 * (lo + hi);
+            if mid <= lo || mid >= hi {
+                break;
+            }
+            if polynomial(mid).signum() == sign_lo {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let root = 0.5 * (lo + hi);
+        roots.extend([root, -root]);
+    }
+    assert_eq!(roots.len(), m, "the extension does not exist");
+    roots
+}
+
+/// Weights of the interpolatory rule with 'nodes', the integrals of the Lagrange polynomials
+/// with the weight exp(-x^2), computed with the Gauss-Hermite rule of as many points.
+fn interpolatory_weights(nodes: &[f64]) -> Vec<f64> {
+    let gauss = gauss_hermite(nodes.len());
+    (0..nodes.len())
+        .map(|i| {
+            gauss
+                .iter()
+                .map(|&(x, w)| {
+                    w * nodes
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != i)
+                        .map(|(_, &node)| (x - node) / (nodes[i] - node))
+                        .product::<f64>()
+                })
+                .sum()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gauss_hermite, genz_keister_rule, GENZ_KEISTER_POINTS};
+    use crate::errors::QagError;
+    use crate::genz_keister::GenzKeisterQuad;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn rules() {
+        let (nodes, _) = genz_keister_rule(2);
+        for x in [
+            0.0,
+            0.5240335474869576,
+            1.224744871391589,
+            2.023230191100516,
+            2.959210779063838,
+        ] {
+            assert!(nodes.iter().any(|&y| (y - x).abs() < 1.0e-14));
+            assert!(nodes.iter().any(|&y| (y + x).abs() < 1.0e-14));
+        }
+
+        for (level, &points) in GENZ_KEISTER_POINTS.iter().enumerate() {
+            let (nodes, weights) = genz_keister_rule(level);
+            assert_eq!(nodes.len(), points);
+            let gauss = gauss_hermite(points);
+            // Exact up to degree 1, 5, 15, 29 and 51, as the Gauss-Hermite rule of as many
+            // points up to degree 2 points - 1.
+            let degree = [1, 5, 15, 29, 51][level];
+            let mut moment = PI.sqrt();
+            for k in (0..=degree).step_by(2) {
+                let sum: f64 = nodes
+                    .iter()
+                    .zip(&weights)
+                    .map(|(&x, &w)| w * x.powi(k))
+                    .sum();
+                assert!((sum - moment).abs() < 1.0e-12 * moment);
+                if (k as usize) < 2 * points {
+                    let sum_gauss: f64 = gauss.iter().map(|&(x, w)| w * x.powi(k)).sum();
+                    assert!((sum_gauss - moment).abs() < 1.0e-12 * moment);
+                }
+                moment *= (k + 1) as f64 / 2.0;
+            }
+        }
+    }
+
+    #[test]
+    fn cos() {
+        let quad = GenzKeisterQuad { max_points: 35 };
+        // The integral of cos(a x) exp(-x^2) is sqrt(pi) exp(-a^2 / 4).
+        let res = quad
+            .integrate(|x: f64| vec![x.cos(), (2.0 * x).cos()], 1.0e-12, 0.0)
+            .unwrap();
+        assert!((res.result[0] - PI.sqrt() * (-0.25_f64).exp()).abs() < 1.0e-13);
+        assert!((res.result[1] - PI.sqrt() * (-1.0_f64).exp()).abs() < 1.0e-13);
+        assert!(res.abserr <= 1.0e-12);
+        assert!(GENZ_KEISTER_POINTS.contains(&res.neval));
+
+        let res = quad.integrate(|x: f64| vec![(10.0 * x).cos()], 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::MaxIteration);
+        let res = GenzKeisterQuad { max_points: 1 }.integrate(|x: f64| vec![x], 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
@@ -1,8 +1,53 @@
 //! Adaptive integration of a vector-valued function.
+pub mod auto_quad;
 pub mod constants;
+pub mod contour;
 pub mod errors;
+pub mod genz_keister;
+pub mod monotone_quad;
+pub mod patterson_quad;
+#[cfg(feature = "profiling")]
+pub mod profiling;
 pub mod qag;
+#[cfg(feature = "async")]
+pub mod qag_async;
+pub mod qag_basis;
+pub mod qag_bessel;
+pub mod qag_best_effort;
+pub mod qag_best_key;
+pub mod qag_builder;
+pub mod qag_decades;
+pub mod qag_density;
+pub mod qag_digits;
+pub mod qag_gauss;
+pub mod qag_graded;
+pub mod qag_gradient;
 pub mod qag_integration_result;
+pub mod qag_into;
+pub mod qag_jumps;
+pub mod qag_max;
+pub mod qag_mesh;
+pub mod qag_moments;
+pub mod qag_mut;
+pub mod qag_periodic;
+pub mod qag_progress;
+#[cfg(any(test, feature = "testing"))]
+pub mod qag_reference;
+pub mod qag_reliability;
+pub mod qag_resabs;
+#[cfg(feature = "smallvec")]
+pub mod qag_smallvec;
+pub mod qag_square;
+pub mod qag_support;
+pub mod qag_symmetry;
+pub mod qag_tail;
+pub mod qag_try;
+pub mod qag_weighted;
+pub mod qag_workspace;
+pub mod qags;
+pub mod qawc;
+pub mod qawo;
+pub mod qaws;
 pub mod qk;
 pub mod qk15;
 pub mod qk21;
@@ -10,13 +55,22 @@ pub mod qk31;
 pub mod qk41;
 pub mod qk51;
 pub mod qk61;
+#[cfg(feature = "simd")]
+pub mod qk61_simd;
+pub mod quad_integrator_result;
+pub mod rules;
 pub mod semi_infinite_function;
+#[cfg(any(test, feature = "testing"))]
+pub mod test_functions;
+#[cfg(feature = "viz")]
+pub mod viz;
 
 use crate::constants::FnVec;
 use crate::errors::QagError;
 use crate::qag::Qag;
 use crate::qag_integration_result::QagIntegrationResult;
 
+#[allow(clippy::too_many_arguments)]
 pub fn integrate(
     f: &FnVec,
     a: f64,
@@ -30,11 +84,12 @@ pub fn integrate(
     more_info: bool,
 ) -> Result<QagIntegrationResult, QagError> {
     let qag = Qag {
-        key,
+        key: key.into(),
         limit,
         points,
         number_of_thread,
         more_info,
+        ..Default::default()
     };
-    qag.integrate(&f, a, b, epsabs, epsrel)
+    qag.integrate(f, a, b, epsabs, epsrel)
 }
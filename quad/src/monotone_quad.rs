@@ -0,0 +1,177 @@
+use crate::constants::{norm_ar, FnVec, HeapItem, Myf64, EPMACH};
+use crate::errors::QagError;
+use ndarray::{Array1, Zip};
+use std::collections::{BinaryHeap, HashMap};
+/// Adaptive integrator for integrands whose components are monotone on the integration
+/// interval, with a verified error bound.
+///
+/// Over a sub-interval (x, y) a monotone f_k lies between f_k(x) and f_k(y), so its integral is
+/// bracketed by the left and right Riemann sums. The sub-interval with the widest bracket is
+/// bisected until the half-width of the total bracket satisfies the requested tolerance: unlike
+/// the Gauss-Kronrod error estimates, this bound is rigorous, up to the rounding of the sums.
+/// The convergence is only linear in the number of evaluations, so it is meant for moderate
+/// tolerances.
+#[derive(Clone, Debug)]
+pub struct MonotoneQuad {
+    /// Maximum number of subdivision allowed.
+    pub limit: usize,
+}
+
+/// Result of [integrate](MonotoneQuad::integrate).
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct MonotoneResult {
+    /// Trapezoidal estimate, the midpoint of the bracket (lower, upper).
+    pub result: Array1<f64>,
+    /// Rigorous lower bound of every component of the integral.
+    pub lower: Array1<f64>,
+    /// Rigorous upper bound of every component of the integral.
+    pub upper: Array1<f64>,
+    /// Euclidean norm of the half-width of the bracket, a bound of the absolute error.
+    pub abserr: f64,
+    /// Number of evaluations of the integrand.
+    pub neval: usize,
+}
+
+impl MonotoneQuad {
+    /// Integration of a vector-valued function over the finite interval ('a', 'b'), assuming
+    /// every component is monotone.
+    ///
+    /// The assumption is checked on every sampled abscissa: if a value does not lie between
+    /// the ones at the ends of its sub-interval, [NotMonotone](QagError::NotMonotone) is
+    /// returned. Infinite bounds are rejected with [Invalid](QagError::Invalid), as the values at
+    /// the ends are needed. If b < a the bounds are swapped and the result is negated.
+    pub fn integrate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<MonotoneResult, QagError> {
+        if b < a {
+            let res = self.integrate(fun, b, a, epsabs, epsrel)?;
+            return Ok(MonotoneResult {
+                result: -res.result,
+                lower: -res.upper,
+                upper: -res.lower,
+                ..res
+            });
+        }
+        if !(a.is_finite() && b.is_finite())
+            || (epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH))
+        {
+            return Err(QagError::Invalid);
+        }
+
+        let f = &fun.components;
+        let mut values = HashMap::new();
+        values.insert(Myf64 { x: a }, f(a));
+        values.insert(Myf64 { x: b }, f(b));
+        let mut neval = 2;
+
+        let bracket = |x: f64, y: f64, fx: &Array1<f64>, fy: &Array1<f64>| {
+            let h = y - x;
+            ((fx + fy) * (0.5 * h), (fy - fx).mapv(|d| 0.5 * h * d.abs()))
+        };
+        let (mut result, mut half_width) =
+            bracket(a, b, &values[&Myf64 { x: a }], &values[&Myf64 { x: b }]);
+        let mut heap = BinaryHeap::new();
+        heap.push(HeapItem::new((a, b), norm_ar(&half_width)));
+
+        let mut last = 1;
+        loop {
+            let abserr = norm_ar(&half_width);
+            if abserr <= epsabs.max(epsrel * norm_ar(&result)) {
+                break;
+            }
+            if last >= self.limit {
+                return Err(QagError::MaxIteration);
+            }
+            let (x, y) = match heap.pop() {
+                Some(item) => item.interval,
+                None => break,
+            };
+            let m = 0.5 * (x + y);
+            if m <= x || m >= y {
+                return Err(QagError::BadTolerance);
+            }
+            let fm = f(m);
+            neval += 1;
+            let (fx, fy) = (&values[&Myf64 { x }], &values[&Myf64 { x: y }]);
+            let mut monotone = fm.len() == fx.len();
+            Zip::from(&fm).and(fx).and(fy).for_each(|&vm, &vx, &vy| {
+                monotone &= vx.min(vy) <= vm && vm <= vx.max(vy);
+            });
+            if !monotone {
+                return Err(QagError::NotMonotone { x: m });
+            }
+
+            let (old_result, old_half_width) = bracket(x, y, fx, fy);
+            let (left_result, left_half_width) = bracket(x, m, fx, &fm);
+            let (right_result, right_half_width) = bracket(m, y, &fm, fy);
+            result = result - old_result + &left_result + &right_result;
+            half_width = half_width - old_half_width + &left_half_width + &right_half_width;
+            heap.push(HeapItem::new((x, m), norm_ar(&left_half_width)));
+            heap.push(HeapItem::new((m, y), norm_ar(&right_half_width)));
+            values.insert(Myf64 { x: m }, fm);
+            last += 1;
+        }
+
+        // Sum again over the final sub-intervals, avoiding the drift of the running sums.
+        let n = result.len();
+        let (mut result, mut half_width) = (Array1::zeros(n), Array1::zeros(n));
+        for item in heap.iter() {
+            let (x, y) = item.interval;
+            let (res, width) = bracket(x, y, &values[&Myf64 { x }], &values[&Myf64 { x: y }]);
+            result += &res;
+            half_width += &width;
+        }
+        Ok(MonotoneResult {
+            lower: &result - &half_width,
+            upper: &result + &half_width,
+            abserr: norm_ar(&half_width),
+            result,
+            neval,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::monotone_quad::MonotoneQuad;
+    use ndarray::array;
+    use std::f64::consts::E;
+    use std::sync::Arc;
+
+    #[test]
+    fn exp() {
+        let quad = MonotoneQuad { limit: 1_000_000 };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.exp(), -x.exp()]),
+        };
+        let correct_result = E - 1.0;
+
+        let res = quad.integrate(&f, 0.0, 1.0, 0.0, 1.0e-4).unwrap();
+        assert!(res.lower[0] <= correct_result && correct_result <= res.upper[0]);
+        assert!(res.lower[1] <= -correct_result && -correct_result <= res.upper[1]);
+        assert!(res.abserr <= 1.0e-4 * 2.0_f64.sqrt() * correct_result);
+        assert!((res.result[0] - correct_result).abs() <= res.abserr);
+
+        let res_reversed = quad.integrate(&f, 1.0, 0.0, 0.0, 1.0e-4).unwrap();
+        assert_eq!(res_reversed.result, -&res.result);
+        assert_eq!(res_reversed.lower, -&res.upper);
+
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x.sin()]),
+        };
+        let res = quad.integrate(&g, 0.0, 6.0, 0.0, 1.0e-4);
+        assert!(matches!(res, Err(QagError::NotMonotone { .. })));
+        let res = quad.integrate(&f, 0.0, f64::INFINITY, 0.0, 1.0e-4);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+        let res = MonotoneQuad { limit: 10 }.integrate(&f, 0.0, 1.0, 0.0, 1.0e-4);
+        assert_eq!(res.unwrap_err(), QagError::MaxIteration);
+    }
+}
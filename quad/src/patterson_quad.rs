@@ -0,0 +1,320 @@
+use crate::constants::{norm_ar, FnVec, EPMACH};
+use crate::errors::QagError;
+use ndarray::Array1;
+use std::sync::Mutex;
+/// Largest number of points of the Gauss-Kronrod-Patterson rules.
+pub const PATTERSON_MAX_POINTS: usize = 63;
+
+/// Non-adaptive integrator with the nested Gauss-Kronrod-Patterson rules of 3, 7, 15, 31 and 63
+/// points.
+///
+/// Instead of bisecting the interval as [Qag](crate::qag::Qag), the same interval is integrated
+/// with rules of increasing order: every rule contains all the abscissae of the previous one,
+/// so no evaluation is wasted and a rule of 2n + 1 points costs only n + 1 new evaluations. For
+/// smooth integrands this p-refinement converges much faster than the bisection, while for
+/// integrands with singularities or peaks it fails, as every rule is a polynomial
+/// approximation over the whole interval.
+///
+/// The rules are computed once, at the first use. The next one, of 127 points, is not
+/// available, as its nodes cannot be computed accurately in double precision.
+#[derive(Clone, Debug)]
+pub struct PattersonQuad {
+    /// Maximum number of points of the last rule, at most [PATTERSON_MAX_POINTS].
+    pub max_points: usize,
+}
+
+/// Result of [integrate](PattersonQuad::integrate).
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct PattersonResult {
+    pub result: Array1<f64>,
+    /// Norm of the difference between the results of the last two rules.
+    pub abserr: f64,
+    /// Number of evaluations of the integrand, the number of points of the last rule.
+    pub neval: usize,
+}
+
+/// Nodes and weights of a rule: the nodes are the ones of the previous rule followed by the new
+/// ones, the weights refer to all of them in the same order.
+struct PattersonRule {
+    nodes: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+/// Rules computed so far, shared by all the integrations.
+static RULES: Mutex<Vec<PattersonRule>> = Mutex::new(Vec::new());
+
+impl PattersonQuad {
+    /// Integration of a vector-valued function over the finite interval ('a', 'b').
+    ///
+    /// The rules of 3 and 7 points are always used, then points are added until the norm of the
+    /// difference between the last two results satisfies the tolerance. If it does not with
+    /// [max_points](PattersonQuad::max_points), [MaxIteration](QagError::MaxIteration) is
+    /// returned. Infinite bounds are rejected with [Invalid](QagError::Invalid). If b < a the
+    /// bounds are swapped and the result is negated.
+    pub fn integrate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<PattersonResult, QagError> {
+        if b < a {
+            let res = self.integrate(fun, b, a, epsabs, epsrel)?;
+            return Ok(PattersonResult {
+                result: -res.result,
+                ..res
+            });
+        }
+        if !(a.is_finite() && b.is_finite())
+            || self.max_points < 7
+            || (epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH))
+        {
+            return Err(QagError::Invalid);
+        }
+
+        let f = &fun.components;
+        let (center, half_length) = (0.5 * (a + b), 0.5 * (b - a));
+        let mut values: Vec<Array1<f64>> = vec![];
+        let mut previous: Option<Array1<f64>> = None;
+        for level in 1.. {
+            if (1 << (level + 1)) - 1 > self.max_points.min(PATTERSON_MAX_POINTS) {
+                return Err(QagError::MaxIteration);
+            }
+            let (nodes, weights) = rule(level);
+            for &x in &nodes[values.len()..] {
+                values.push(f(center + half_length * x));
+            }
+            let mut result = Array1::zeros(values[0].len());
+            for (value, &w) in values.iter().zip(&weights) {
+                result.scaled_add(w * half_length, value);
+            }
+            if let Some(previous) = previous {
+                let abserr = norm_ar(&(&result - &previous));
+                if abserr <= epsabs.max(epsrel * norm_ar(&result)) {
+                    return Ok(PattersonResult {
+                        result,
+                        abserr,
+                        neval: values.len(),
+                    });
+                }
+            }
+            previous = Some(result);
+        }
+        unreachable!()
+    }
+}
+
+/// Nodes and weights on (-1, 1) of the rule of 2^(level + 1) - 1 points, computing and caching
+/// the missing rules.
+fn rule(level: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut rules = RULES.lock().unwrap();
+    while rules.len() <= level {
+        let nodes = match rules.last() {
+            None => vec![0.0],
+            Some(last) => {
+                let mut nodes = last.nodes.clone();
+                nodes.extend(extension(&last.nodes));
+                nodes
+            }
+        };
+        let weights = interpolatory_weights(&nodes);
+        rules.push(PattersonRule { nodes, weights });
+    }
+    (rules[level].nodes.clone(), rules[level].weights.clone())
+}
+
+/// Legendre polynomials P_0(x), ..., P_n(x).
+fn legendre(x: f64, n: usize) -> Vec<f64> {
+    let mut p = vec![1.0, x];
+    for k in 1..n {
+        let k = k as f64;
+        let next = ((2.0 * k + 1.0) * x * p[p.len() - 1] - k * p[p.len() - 2]) / (k + 1.0);
+        p.push(next);
+    }
+    p.truncate(n + 1);
+    p
+}
+
+/// Gauss-Legendre rule of 'n' points, used to compute the integrals of polynomials.
+pub(crate) fn gauss_legendre(n: usize) -> Vec<(f64, f64)> {
+    (0..n)
+        .map(|i| {
+            let mut x = (std::f64::consts::PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos();
+            let mut dp = 1.0;
+            for _ in 0..100 {
+                let p = legendre(x, n);
+                dp = n as f64 * (x * p[n] - p[n - 1]) / (x * x - 1.0);
+                let dx = p[n] / dp;
+                x -= dx;
+                if dx.abs() <= EPMACH {
+                    break;
+                }
+            }
+            (x, 2.0 / ((1.0 - x * x) * dp * dp))
+        })
+        .collect()
+}
+
+/// Solution of the linear system 'matrix' x = 'rhs' by Gaussian elimination with partial
+/// pivoting.
+pub(crate) fn solve(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Vec<f64> {
+    let n = rhs.len();
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| matrix[i][col].abs().total_cmp(&matrix[j][col].abs()))
+            .unwrap();
+        matrix.swap(col, pivot);
+        rhs.swap(col, pivot);
+        for row in col + 1..n {
+            let factor = matrix[row][col] / matrix[col][col];
+            let (upper, lower) = matrix.split_at_mut(row);
+            for (x, y) in lower[0][col..].iter_mut().zip(&upper[col][col..]) {
+                *x -= factor * y;
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| matrix[row][k] * x[k]).sum();
+        x[row] = (rhs[row] - sum) / matrix[row][row];
+    }
+    x
+}
+
+/// The n + 1 nodes extending the 'nodes' of a rule of n points, the roots of the polynomial of
+/// degree n + 1 orthogonal to all lower degrees with the weight prod_i (x - nodes_i).
+fn extension(nodes: &[f64]) -> Vec<f64> {
+    let m = nodes.len() + 1;
+    // The integrands have degree at most n + 2m.
+    let quadrature: Vec<_> = gauss_legendre((nodes.len() + 2 * m) / 2 + 1)
+        .into_iter()
+        .map(|(x, w)| {
+            (
+                w * nodes.iter().map(|&node| x - node).product::<f64>(),
+                legendre(x, m),
+            )
+        })
+        .collect();
+    // The polynomial is the sum of c_j P_j, with c_m = 1.
+    let matrix = (0..m)
+        .map(|k| {
+            (0..m)
+                .map(|j| quadrature.iter().map(|(w, p)| w * p[j] * p[k]).sum())
+                .collect()
+        })
+        .collect();
+    let rhs = (0..m)
+        .map(|k| -quadrature.iter().map(|(w, p)| w * p[m] * p[k]).sum::<f64>())
+        .collect();
+    let mut c = solve(matrix, rhs);
+    c.push(1.0);
+    let polynomial = |x: f64| -> f64 { legendre(x, m).iter().zip(&c).map(|(p, c)| p * c).sum() };
+
+    // The new nodes interlace the old ones.
+    let mut edges = nodes.to_vec();
+    edges.sort_by(f64::total_cmp);
+    edges.insert(0, -1.0);
+    edges.push(1.0);
+    edges
+        .windows(2)
+        .map(|edge| {
+            let (mut lo, mut hi) = (edge[0], edge[1]);
+            let sign_lo = polynomial(lo).signum();
+            while hi - lo > 2.0 * EPMACH * lo.abs().max(hi.abs()) {
+                let mid = 0.5 * (lo + hi);
+                if mid <= lo || mid >= hi {
+                    break;
+                }
+                if polynomial(mid).signum() == sign_lo {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            0.5 * (lo + hi)
+        })
+        .collect()
+}
+
+/// Weights of the interpolatory rule with 'nodes', integrating exactly P_0, ..., P_(n-1).
+fn interpolatory_weights(nodes: &[f64]) -> Vec<f64> {
+    let n = nodes.len();
+    let p: Vec<_> = nodes.iter().map(|&x| legendre(x, n - 1)).collect();
+    let matrix = (0..n).map(|k| (0..n).map(|i| p[i][k]).collect()).collect();
+    let mut rhs = vec![0.0; n];
+    rhs[0] = 2.0;
+    solve(matrix, rhs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rule;
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::patterson_quad::PattersonQuad;
+    use ndarray::array;
+    use std::f64::consts::E;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn rules() {
+        let (nodes, weights) = rule(2);
+        let expected = [
+            (0.0, 0.4509165386584741),
+            (0.7745966692414834, 0.2684880898683334),
+            (0.4342437493468026, 0.4013974147759622),
+            (0.9604912687080203, 0.1046562260264673),
+        ];
+        for (x, w) in expected {
+            let i = nodes.iter().position(|&y| (y - x).abs() < 1.0e-15).unwrap();
+            assert!((weights[i] - w).abs() < 1.0e-15);
+        }
+        for level in 1..=5 {
+            let (nodes, weights) = rule(level);
+            assert_eq!(nodes.len(), (1 << (level + 1)) - 1);
+            assert!(weights.iter().all(|&w| w > 0.0));
+            // Exact up to degree 3 2^level - 1.
+            for degree in (0..3 << level).step_by(2) {
+                let sum: f64 = nodes
+                    .iter()
+                    .zip(&weights)
+                    .map(|(&x, &w)| w * x.powi(degree))
+                    .sum();
+                assert!((sum - 2.0 / (degree as f64 + 1.0)).abs() < 1.0e-13);
+            }
+        }
+    }
+
+    #[test]
+    fn exp() {
+        let quad = PattersonQuad { max_points: 63 };
+        let evaluations = AtomicUsize::new(0);
+        let f = FnVec {
+            components: Arc::new(|x: f64| {
+                evaluations.fetch_add(1, Ordering::Relaxed);
+                array![x.exp()]
+            }),
+        };
+
+        let res = quad.integrate(&f, 0.0, 1.0, 1.0e-13, 0.0).unwrap();
+        assert!((res.result[0] - (E - 1.0)).abs() < 1.0e-14);
+        assert!(res.abserr <= 1.0e-13);
+        assert_eq!(res.neval, 15);
+        assert_eq!(evaluations.load(Ordering::Relaxed), 15);
+
+        let res_reversed = quad.integrate(&f, 1.0, 0.0, 1.0e-13, 0.0).unwrap();
+        assert_eq!(res_reversed.result, -&res.result);
+
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x.abs().sqrt()]),
+        };
+        let res = quad.integrate(&g, -1.0, 1.0, 1.0e-13, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::MaxIteration);
+        let res = quad.integrate(&f, 0.0, f64::INFINITY, 1.0e-13, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+/// Number of evaluations of the integrands performed by the Gauss-Kronrod rules of the whole
+/// process, across nested and parallel integrations.
+///
+/// It is incremented by every rule application (e.g. by 21 for a 10-21 points rule), so that
+/// it also counts the evaluations of the integrations which return an error.
+pub static EVAL_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Add 'evaluations' to [EVAL_COUNT].
+pub(crate) fn count_evaluations(evaluations: usize) {
+    EVAL_COUNT.fetch_add(evaluations as u64, Ordering::Relaxed);
+}
+
+/// Current value of [EVAL_COUNT].
+pub fn eval_count() -> u64 {
+    EVAL_COUNT.load(Ordering::Relaxed)
+}
+
+/// Set [EVAL_COUNT] to zero, returning its previous value.
+pub fn reset_eval_count() -> u64 {
+    EVAL_COUNT.swap(0, Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::profiling::{eval_count, reset_eval_count};
+    use crate::qag::Qag;
+    use crate::qk21::qk21_quadrature;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn eval_count_grows() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 4,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.powf(-0.5)]),
+        };
+
+        // Other tests may run concurrently, so only lower bounds can be checked.
+        let start = eval_count();
+        let _ = qk21_quadrature(|x: f64| array![x], 0.0, 1.0);
+        assert!(eval_count() - start >= 21);
+
+        let start = eval_count();
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let neval = res.more_info.unwrap().neval as u64;
+        assert!(eval_count() - start >= neval);
+
+        assert!(reset_eval_count() >= start + neval);
+    }
+}
@@ -5,33 +5,36 @@ use ::rayon::prelude::*;
 
 use crate::constants::*;
 use crate::errors::QagError;
-use crate::qag_integration_result::QagIntegrationResult;
+use crate::qag_integration_result::{Difficulty, QagIntegrationResult, RefineEvent, SuccessKind};
+use crate::qag_progress::ProgressMsg;
+use crate::qag_workspace::QagWorkspace;
 use crate::qk15::qk15_quadrature;
 use crate::qk21::qk21_quadrature;
 use crate::qk31::qk31_quadrature;
 use crate::qk41::qk41_quadrature;
 use crate::qk51::qk51_quadrature;
 use crate::qk61::qk61_quadrature;
+use crate::semi_infinite_function::infinite_integrand;
+#[cfg(doc)]
 use crate::semi_infinite_function::{double_infinite_function, semi_infinite_function};
-use ndarray::Array1;
+use ndarray::{Array1, ScalarOperand};
+use num_traits::Float;
 use std::collections::{BinaryHeap, HashMap};
-use std::sync::Arc;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 /// Struct with the primary function 'integrate' as method.
 #[derive(Clone)]
 pub struct Qag {
-    /// Correspond to the Gauss-Kronrod rule used.
-    ///
-    /// The following are the correspondence between i32 and Gauss-Kronrod rule:
-    /// - 1 -> 7-15 points
-    /// - 2 -> 10-21 points
-    /// - 3 -> 15-31 points
-    /// - 4 -> 20-41 points
-    /// - 5 -> 25-51 points
-    /// - 6 -> 30-61 points
-    pub key: i32,
+    /// Gauss-Kronrod rule used.
+    pub key: GaussKronrodRule,
     /// Maximum number of subdivision allowed.
     pub limit: usize,
     /// List of additional breakpoints.
+    ///
+    /// The points outside the integration interval are ignored and reported in
+    /// [ignored_points](QagIntegrationResult::ignored_points).
     pub points: Vec<f64>,
     /// Number of thread used.
     ///
@@ -43,6 +46,446 @@ pub struct Qag {
     /// If more_info is set to true [integrate](Qag::integrate) will return a [QagIntegrationResult]
     /// containing [MoreInfo].
     pub more_info: bool,
+    /// Behaviour when the maximum number of subdivision [limit](Qag::limit) is reached.
+    pub limit_behavior: LimitBehavior,
+    /// Number of sub-intervals after which an increase of the error upon bisection starts to be
+    /// counted as a sign of roundoff.
+    ///
+    /// When this counter exceeds [IROFF2_THRESHOLD] the integration gives up with
+    /// [BadTolerance](QagError::BadTolerance). Increase it for integrands whose first
+    /// sub-intervals are atypically noisy, decrease it to give up earlier.
+    pub roundoff_warmup: usize,
+    /// If set to true the returned [QagIntegrationResult] contains the
+    /// [refinement_trace](QagIntegrationResult::refinement_trace), the sequence of the bisected
+    /// intervals in processing order.
+    pub record_refinement_trace: bool,
+    /// Maximum number of bisections leading from an initial interval to any of its
+    /// sub-intervals.
+    ///
+    /// Once a sub-interval reaches this depth its error estimate is accepted and it is no longer
+    /// subdivided, while the others keep being refined. Capped sub-intervals still contribute to
+    /// the total error, and only the performed bisections count towards [limit](Qag::limit). If
+    /// only capped sub-intervals are left before achieving the requested tolerance, the
+    /// integration stops as if [limit](Qag::limit) were reached. If None, the depth is not
+    /// limited.
+    pub max_depth: Option<u32>,
+    /// If set, the number of sub-intervals bisected in parallel at each iteration is tuned
+    /// automatically, see [AutoBatch], starting from [batch_size](Qag::batch_size). Otherwise
+    /// [batch_size](Qag::batch_size) sub-intervals at most are bisected at once.
+    pub auto_batch: Option<AutoBatch>,
+    /// Maximum number of sub-intervals bisected in parallel at each iteration, 128 in the Python
+    /// bindings.
+    ///
+    /// Larger batches keep more threads busy on cheap integrands, while smaller ones avoid
+    /// bisections beyond the requested tolerance on expensive integrands. It has to be at least
+    /// 1, otherwise [integrate](Qag::integrate) returns
+    /// [InvalidBatchSize](QagError::InvalidBatchSize).
+    pub batch_size: usize,
+    /// List of points where the integrand has a removable singularity, e.g. a 0/0 form such as
+    /// sin(x)/x at 0.
+    ///
+    /// The integrand is never evaluated exactly at these points: the average of the values at
+    /// a small symmetric offset is used instead.
+    pub removable_singularities: Vec<f64>,
+    /// Point where a sub-interval (a, b) is bisected, which has to lie strictly inside it,
+    /// otherwise [integrate](Qag::integrate) returns [BadSplit](QagError::BadSplit).
+    ///
+    /// If None, the midpoint is used. For infinite ranges it receives the sub-intervals of the
+    /// transformed variable.
+    pub split_fn: Option<SplitFn>,
+    /// How 'epsabs' and 'epsrel' are combined in the convergence test.
+    pub tolerance_logic: ToleranceLogic,
+    /// If set to true the returned [QagIntegrationResult] contains the
+    /// [interval_errors](QagIntegrationResult::interval_errors), the final sub-intervals with
+    /// their error estimates.
+    ///
+    /// It is a lightweight alternative to [more_info](Qag::more_info), which moves the whole
+    /// sub-interval cache into the result.
+    pub errors_only: bool,
+    /// Integration variable u = forward(x) in which the adaptive subdivision is performed.
+    ///
+    /// For [Linear](Reparam::Linear) the integration is performed in x, otherwise the additional
+    /// points and the bounds are mapped to u and f(x(u)) dx/du is integrated, so that the
+    /// information in [MoreInfo] refers to u.
+    pub reparam: Reparam,
+    /// Weights w_k of the components in the norm sqrt(sum_k w_k r_k^2) used by the convergence
+    /// test, where r is the result or the error.
+    ///
+    /// They scale components carrying different units to a common reference: the integration is
+    /// performed on sqrt(w_k) f_k and only the result is scaled back, so 'epsabs', the returned
+    /// 'abserr' and the information in [MoreInfo] refer to the weighted components. Every weight
+    /// must be positive and finite, with one weight per component, otherwise
+    /// [integrate](Qag::integrate) returns [InvalidWeights](QagError::InvalidWeights). If None,
+    /// all the weights are 1.
+    pub norm_weights: Option<Vec<f64>>,
+    /// Implementation of the threads bisecting the sub-intervals in parallel.
+    pub backend: Backend,
+    /// If set to true the returned [QagIntegrationResult] contains the
+    /// [point_values](QagIntegrationResult::point_values), the table of the evaluations of the
+    /// integrand sorted by abscissa.
+    pub record_point_values: bool,
+}
+
+/// The defaults are the same as in the Python bindings: the 10-21 points rule, a limit of 50
+/// sub-intervals, no points, a single thread and no [MoreInfo]. The other options are disabled,
+/// and 128 sub-intervals at most are bisected at once.
+impl Default for Qag {
+    fn default() -> Self {
+        Self {
+            key: GaussKronrodRule::G10K21,
+            limit: 50,
+            points: vec![0.0; 0],
+            number_of_thread: 1,
+            more_info: false,
+            limit_behavior: LimitBehavior::Error,
+            roundoff_warmup: 10,
+            record_refinement_trace: false,
+            max_depth: None,
+            auto_batch: None,
+            batch_size: 128,
+            removable_singularities: vec![0.0; 0],
+            split_fn: None,
+            tolerance_logic: ToleranceLogic::Or,
+            errors_only: false,
+            reparam: Reparam::Linear,
+            norm_weights: None,
+            backend: Backend::Rayon,
+            record_point_values: false,
+        }
+    }
+}
+
+/// Combination of the tolerances 'epsabs' and 'epsrel' required by [integrate](Qag::integrate).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToleranceLogic {
+    /// Converge when abserr <= max(epsabs, epsrel * |result|), i.e. either tolerance suffices.
+    Or,
+    /// Converge when abserr <= min(epsabs, epsrel * |result|), i.e. both tolerances are
+    /// required. A non-positive tolerance is not requested, so only the other one applies.
+    And,
+}
+
+impl ToleranceLogic {
+    /// Error bound that the estimated error has to satisfy.
+    pub fn errbnd(&self, epsabs: f64, epsrel: f64, result: &Array1<f64>) -> f64 {
+        let relbnd = epsrel * norm_ar(result);
+        match self {
+            ToleranceLogic::Or => epsabs.max(relbnd),
+            ToleranceLogic::And if epsabs <= 0.0 => relbnd,
+            ToleranceLogic::And if epsrel <= 0.0 => epsabs,
+            ToleranceLogic::And => epsabs.min(relbnd),
+        }
+    }
+
+    /// Tolerance which determined the error bound [errbnd](ToleranceLogic::errbnd).
+    pub fn success_kind(&self, epsabs: f64, epsrel: f64, result: &Array1<f64>) -> SuccessKind {
+        if self.errbnd(epsabs, epsrel, result) == epsabs {
+            SuccessKind::Absolute
+        } else {
+            SuccessKind::Relative
+        }
+    }
+}
+
+/// Stopping criterion on the current result, used by [qintegrate_until](Qag::qintegrate_until).
+pub(crate) type StopFn<'a> = &'a dyn Fn(&Array1<f64>) -> bool;
+
+/// Values of an integrand, as evaluated by the Gauss-Kronrod rules of
+/// [qk_quadrature](crate::qk::qk_quadrature) and transformed before the integration by
+/// [integrate](Qag::integrate).
+pub trait Values<T = f64>: Sized {
+    /// Number of components.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Values with 'n' components equal to zero.
+    fn zeros(n: usize) -> Self;
+
+    fn to_vec(&self) -> Vec<T>;
+
+    /// Component 'k' of the values.
+    fn component(&self, k: usize) -> T;
+
+    /// Mutable reference to the component 'k' of the values.
+    fn component_mut(&mut self, k: usize) -> &mut T;
+
+    /// Values multiplied by 'factor'.
+    fn scaled(self, factor: T) -> Self;
+
+    /// Values divided by 'divisor'.
+    fn divided(self, divisor: T) -> Self;
+
+    /// Values multiplied component-wise by 'weights'.
+    fn weighted(self, weights: &Array1<T>) -> Self;
+
+    /// Component-wise mean of the values and 'other'.
+    fn mean(self, other: Self) -> Self;
+}
+
+impl<T: Float + ScalarOperand> Values<T> for Array1<T> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn zeros(n: usize) -> Self {
+        Array1::zeros(n)
+    }
+
+    fn to_vec(&self) -> Vec<T> {
+        self.to_vec()
+    }
+
+    fn component(&self, k: usize) -> T {
+        self[k]
+    }
+
+    fn component_mut(&mut self, k: usize) -> &mut T {
+        &mut self[k]
+    }
+
+    fn scaled(self, factor: T) -> Self {
+        self * factor
+    }
+
+    fn divided(self, divisor: T) -> Self {
+        self / divisor
+    }
+
+    fn weighted(self, weights: &Array1<T>) -> Self {
+        self * weights
+    }
+
+    fn mean(self, other: Self) -> Self {
+        (self + other) * T::from(0.5).unwrap()
+    }
+}
+
+/// Map from the result of the integrand transformed by
+/// [integrate_pipeline](Qag::integrate_pipeline) to the one of the original integrand.
+#[derive(Clone, Default)]
+pub(crate) struct OutputMap {
+    /// Whether the bounds have been swapped.
+    pub reversed: bool,
+    /// Square roots of the [norm_weights](Qag::norm_weights) multiplying the integrand.
+    pub scale: Option<Array1<f64>>,
+}
+
+impl OutputMap {
+    /// Integral of the original integrand, given the one of the transformed integrand.
+    pub fn apply(&self, result: &Array1<f64>) -> Array1<f64> {
+        let mut result = self.apply_abs(result);
+        if self.reversed {
+            result.mapv_inplace(|x| -x);
+        }
+        result
+    }
+
+    /// Same as [apply](OutputMap::apply) for a quantity which does not change sign when the
+    /// bounds are swapped, e.g. the integral of |f|.
+    pub fn apply_abs(&self, result: &Array1<f64>) -> Array1<f64> {
+        match &self.scale {
+            Some(scale) => result / scale,
+            None => result.clone(),
+        }
+    }
+}
+
+/// Integration over a finite interval of the integrand transformed by
+/// [integrate_pipeline](Qag::integrate_pipeline), returning the result and its own output.
+pub(crate) type PipelineCore<'c, V, T> = dyn Fn(
+        &Qag,
+        &mut QagWorkspace,
+        &(dyn Fn(f64) -> V + Sync),
+        f64,
+        f64,
+        f64,
+        f64,
+        &OutputMap,
+    ) -> Result<(QagIntegrationResult, T), QagError>
+    + 'c;
+
+/// Type of [split_fn](Qag::split_fn).
+pub type SplitFn = Arc<dyn Fn(f64, f64) -> f64 + Send + Sync>;
+
+/// Type of the functions of [Custom](Reparam::Custom).
+pub type ReparamFn = Arc<dyn Fn(f64) -> f64 + Send + Sync>;
+
+/// Monotone reparametrization u = forward(x) of the integration variable, see
+/// [reparam](Qag::reparam).
+///
+/// It spreads the sub-intervals according to the scale of the integrand, e.g. a logarithmic
+/// one for integrands with features near 0 over many orders of magnitude.
+#[derive(Clone)]
+pub enum Reparam {
+    /// The integration is performed in x.
+    Linear,
+    /// The integration is performed in u = ln(x), which requires 0 < a, b, otherwise
+    /// [integrate](Qag::integrate) returns [InvalidReparam](QagError::InvalidReparam).
+    Log,
+    /// The integration is performed in u = forward(x), with x = inverse(u) and
+    /// jacobian(u) = dx/du.
+    Custom {
+        forward: ReparamFn,
+        inverse: ReparamFn,
+        jacobian: ReparamFn,
+    },
+}
+
+/// Gauss-Kronrod rule of [key](Qag::key), named by the number of points of the Gauss rule and
+/// of its Kronrod extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GaussKronrodRule {
+    G7K15,
+    G10K21,
+    G15K31,
+    G20K41,
+    G25K51,
+    G30K61,
+}
+
+impl GaussKronrodRule {
+    /// All the rules, from the lowest to the highest order.
+    pub const ALL: [GaussKronrodRule; 6] = [
+        GaussKronrodRule::G7K15,
+        GaussKronrodRule::G10K21,
+        GaussKronrodRule::G15K31,
+        GaussKronrodRule::G20K41,
+        GaussKronrodRule::G25K51,
+        GaussKronrodRule::G30K61,
+    ];
+
+    /// Number of points of the Kronrod rule, i.e. of evaluations over a sub-interval.
+    pub fn points(self) -> usize {
+        match self {
+            GaussKronrodRule::G7K15 => 15,
+            GaussKronrodRule::G10K21 => 21,
+            GaussKronrodRule::G15K31 => 31,
+            GaussKronrodRule::G20K41 => 41,
+            GaussKronrodRule::G25K51 => 51,
+            GaussKronrodRule::G30K61 => 61,
+        }
+    }
+
+    /// Integral of 'f' over ('a', 'b') with the Kronrod rule, its error estimate and its roundoff
+    /// error.
+    pub fn quadrature<F>(self, f: F, a: f64, b: f64) -> (Array1<f64>, f64, f64)
+    where
+        F: Fn(f64) -> Array1<f64>,
+    {
+        match self {
+            GaussKronrodRule::G7K15 => qk15_quadrature(f, a, b),
+            GaussKronrodRule::G10K21 => qk21_quadrature(f, a, b),
+            GaussKronrodRule::G15K31 => qk31_quadrature(f, a, b),
+            GaussKronrodRule::G20K41 => qk41_quadrature(f, a, b),
+            GaussKronrodRule::G25K51 => qk51_quadrature(f, a, b),
+            GaussKronrodRule::G30K61 => qk61_quadrature(f, a, b),
+        }
+    }
+}
+
+/// The rule with the integer key of QUADPACK, from 1 (7-15 points) to 6 (30-61 points), as
+/// used by the Python bindings. Keys below 1 and above 6 are clamped.
+impl From<i32> for GaussKronrodRule {
+    fn from(key: i32) -> Self {
+        GaussKronrodRule::ALL[key.clamp(1, 6) as usize - 1]
+    }
+}
+
+/// The integer key of QUADPACK of the rule, from 1 to 6.
+impl From<GaussKronrodRule> for i32 {
+    fn from(key: GaussKronrodRule) -> Self {
+        GaussKronrodRule::ALL
+            .iter()
+            .position(|&k| k == key)
+            .unwrap() as i32
+            + 1
+    }
+}
+
+/// Behaviour of [integrate](Qag::integrate) when [limit](Qag::limit) is reached without
+/// achieving the requested tolerance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LimitBehavior {
+    /// Return a [MaxIteration](QagError::MaxIteration) error.
+    Error,
+    /// Return the best estimate obtained so far, with
+    /// [failure](QagIntegrationResult::failure) set to [MaxIteration](QagError::MaxIteration).
+    ReturnBest,
+}
+
+/// Threads used by [integrate](Qag::integrate) to bisect the sub-intervals in parallel.
+///
+/// Both backends bisect the same sub-intervals and combine the results in the same order, so
+/// the result is the same.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    /// A Rayon thread-pool, built once per [QagWorkspace] and reused by the next integrations.
+    Rayon,
+    /// Scoped threads of the standard library, spawned for every batch of sub-intervals, which
+    /// share them through an atomic counter. The spawning costs a few microseconds per batch,
+    /// negligible for expensive integrands. If [number_of_thread](Qag::number_of_thread) is 0,
+    /// the available parallelism is used.
+    StdThreads,
+}
+
+/// Self-tuning of the number of sub-intervals bisected in parallel at each iteration of
+/// [integrate](Qag::integrate).
+///
+/// The wall-time of every batch of bisections is measured, and the next batch is sized so that
+/// its evaluation takes about [target](AutoBatch::target): cheap integrands get large batches,
+/// keeping the synchronization overhead negligible, while expensive ones get small batches,
+/// avoiding bisections beyond the requested tolerance. The first batch has
+/// [batch_size](Qag::batch_size) sub-intervals at most.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoBatch {
+    /// Wall-time targeted by each batch of bisections.
+    pub target: Duration,
+    /// Upper bound of the batch size.
+    pub max_batch: usize,
+}
+
+impl Default for AutoBatch {
+    fn default() -> Self {
+        Self {
+            target: Duration::from_millis(1),
+            max_batch: 1 << 16,
+        }
+    }
+}
+
+impl AutoBatch {
+    /// Batch size following a batch of 'processed' sub-intervals bisected in 'elapsed' time.
+    pub fn next_batch_size(&self, elapsed: Duration, processed: usize) -> usize {
+        let per_interval = elapsed.as_secs_f64() / processed.max(1) as f64;
+        if per_interval <= 0.0 {
+            return self.max_batch.max(1);
+        }
+        let batch = self.target.as_secs_f64() / per_interval;
+        (batch.min(self.max_batch as f64) as usize).max(1)
+    }
+}
+
+/// Tolerances of [integrate_opts](Qag::integrate_opts).
+///
+/// The default ones are the same as in the Python bindings, 1.49e-8 for both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IntegrateOpts {
+    /// Absolute tolerance.
+    pub epsabs: f64,
+    /// Relative tolerance.
+    pub epsrel: f64,
+}
+
+impl Default for IntegrateOpts {
+    fn default() -> Self {
+        Self {
+            epsabs: 1.49e-8,
+            epsrel: 1.49e-8,
+        }
+    }
 }
 
 impl Qag {
@@ -55,6 +498,13 @@ impl Qag {
     /// present, are transformed using [points_transformed]. After that [qintegrate](Qag::qintegrate)
     /// is called using (0,1) or (1,-1) as new interval for the semi-infinite and infinite case
     /// respectively.
+    ///
+    /// With 'epsabs' <= 0, if the result is so close to zero that 'epsrel' * |result| is below the
+    /// roundoff, the relative tolerance is applied to the integral of |f| instead.
+    ///
+    /// If b < a the bounds are swapped and the result is negated. The information in [MoreInfo]
+    /// refers to the swapped interval. If a == b, also infinite, the result is zero without any
+    /// bisection, with as many components as f(a), and [MoreInfo] has no sub-intervals.
     pub fn integrate(
         &self,
         fun: &FnVec,
@@ -63,39 +513,269 @@ impl Qag {
         epsabs: f64,
         epsrel: f64,
     ) -> Result<QagIntegrationResult, QagError> {
-        let f = &fun.components;
-        if b == f64::INFINITY && a.is_finite()
-            || a == f64::NEG_INFINITY && b.is_finite()
-            || a == f64::NEG_INFINITY && b == f64::INFINITY
-        {
-            let points = points_transformed(self.points.clone(), a, b);
+        self.integrate_with_workspace(&mut QagWorkspace::new(), fun, a, b, epsabs, epsrel)
+    }
+
+    /// Same as [integrate](Qag::integrate), with the bounds given as a range 'a..=b'.
+    pub fn integrate_range(
+        &self,
+        fun: &FnVec,
+        range: RangeInclusive<f64>,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let (a, b) = range.into_inner();
+        self.integrate(fun, a, b, epsabs, epsrel)
+    }
+
+    /// Same as [integrate](Qag::integrate), with the bounds given as a tuple (a, b) and the
+    /// tolerances as the named fields of [IntegrateOpts].
+    pub fn integrate_opts(
+        &self,
+        fun: &FnVec,
+        bounds: (f64, f64),
+        opts: IntegrateOpts,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let (a, b) = bounds;
+        self.integrate(fun, a, b, opts.epsabs, opts.epsrel)
+    }
+
+    /// Same as [integrate](Qag::integrate), returning [MoreInfo] if 'more_info' is true
+    /// regardless of [more_info](Qag::more_info), for a single call.
+    pub fn integrate_with_info(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        more_info: bool,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let mut ws = QagWorkspace::new();
+        ws.more_info = Some(more_info);
+        self.integrate_with_workspace(&mut ws, fun, a, b, epsabs, epsrel)
+    }
+
+    /// Same as [integrate](Qag::integrate), but reusing the containers of a [QagWorkspace].
+    ///
+    /// The workspace is cleared at the beginning of each call, so the same one can be used for
+    /// many integrations in a row, amortizing the allocations and the thread-pool creation.
+    pub fn integrate_with_workspace(
+        &self,
+        ws: &mut QagWorkspace,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        if a == b {
+            let n = (fun.components)(a).len();
+            if n == 0 {
+                return Err(QagError::EmptyIntegrand);
+            }
+            let result = Array1::zeros(n);
+            let mut res = if ws.more_info.unwrap_or(self.more_info) {
+                QagIntegrationResult::new_more_info(
+                    result,
+                    0.0,
+                    0,
+                    0,
+                    HashMap::new(),
+                    BinaryHeap::new(),
+                )
+            } else {
+                QagIntegrationResult::new(result, 0.0)
+            };
+            res.success_kind = Some(
+                self.tolerance_logic
+                    .success_kind(epsabs, epsrel, &res.result),
+            );
+            return Ok(res);
+        }
+
+        let core = |qag: &Qag,
+                    ws: &mut QagWorkspace,
+                    f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+                    a: f64,
+                    b: f64,
+                    epsabs: f64,
+                    epsrel: f64,
+                    _: &OutputMap| {
+            let fun = FnVec {
+                components: Arc::new(f),
+            };
+            Ok((
+                qag.qintegrate_with_workspace(ws, &fun, a, b, epsabs, epsrel)?,
+                (),
+            ))
+        };
+        let map = OutputMap::default();
+        let f = &*fun.components;
+        let (res, ()) = self.integrate_pipeline(ws, f, a, b, epsabs, epsrel, &map, &core)?;
+        Ok(res)
+    }
+
+    /// Transformations of the integrand shared by [integrate](Qag::integrate) and the other
+    /// integrators, before 'core' integrates over a finite interval.
+    ///
+    /// In order, the bounds are swapped if b < a, the evaluations are recorded for
+    /// [record_point_values](Qag::record_point_values), the [points](Qag::points) outside the
+    /// interval are ignored, the [removable_singularities](Qag::removable_singularities) are
+    /// averaged out, the integrand is weighted by the [norm_weights](Qag::norm_weights), the
+    /// [reparam](Qag::reparam) is applied and infinite ranges are transformed. Only the result is
+    /// mapped back to the original integrand, while 'core' receives the [OutputMap] for its own
+    /// output.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn integrate_pipeline<V: Values, T>(
+        &self,
+        ws: &mut QagWorkspace,
+        f: &(dyn Fn(f64) -> V + Sync),
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        map: &OutputMap,
+        core: &PipelineCore<V, T>,
+    ) -> Result<(QagIntegrationResult, T), QagError> {
+        if b < a {
+            let map = OutputMap {
+                reversed: !map.reversed,
+                ..map.clone()
+            };
+            let (mut res, output) =
+                self.integrate_pipeline(ws, f, b, a, epsabs, epsrel, &map, core)?;
+            res.result.mapv_inplace(|x| -x);
+            return Ok((res, output));
+        }
+
+        if self.record_point_values {
+            let values = Mutex::new(vec![]);
+            let f_recorded = |x: f64| {
+                let value = f(x);
+                values.lock().unwrap().push((x, value.to_vec()));
+                value
+            };
+            let qag = Qag {
+                record_point_values: false,
+                ..self.clone()
+            };
+            let (mut res, output) =
+                qag.integrate_pipeline(ws, &f_recorded, a, b, epsabs, epsrel, map, core)?;
+            let mut values = values.into_inner().unwrap();
+            values.sort_by(|x, y| x.0.total_cmp(&y.0));
+            values.dedup_by(|x, y| x.0 == y.0);
+            res.point_values = Some(values);
+            return Ok((res, output));
+        }
+
+        if self.points.iter().any(|p| !(a..=b).contains(p)) {
+            let (points, ignored_points) = self.points.iter().partition(|p| (a..=b).contains(*p));
             let qag = Qag {
-                key: self.key,
-                limit: self.limit,
                 points,
-                number_of_thread: self.number_of_thread,
-                more_info: self.more_info,
+                ..self.clone()
             };
+            let (mut res, output) =
+                qag.integrate_pipeline(ws, f, a, b, epsabs, epsrel, map, core)?;
+            res.ignored_points = ignored_points;
+            return Ok((res, output));
+        }
 
-            if b == f64::INFINITY && a.is_finite() {
-                let f2 = FnVec {
-                    components: Arc::new(|x: f64| semi_infinite_function(&**f, x, a, b)),
-                };
-                return qag.qintegrate(&f2, 0.0, 1.0, epsabs, epsrel);
-            } else if a == f64::NEG_INFINITY && b.is_finite() {
-                let f2 = FnVec {
-                    components: Arc::new(|x: f64| semi_infinite_function(&**f, x, b, a)),
-                };
-                return qag.qintegrate(&f2, 0.0, 1.0, epsabs, epsrel);
-            } else if a == f64::NEG_INFINITY && b == f64::INFINITY {
-                let f2 = FnVec {
-                    components: Arc::new(|x: f64| double_infinite_function(&**f, x)),
-                };
-                return qag.qintegrate(&f2, -1.0, 1.0, epsabs, epsrel);
+        if !self.removable_singularities.is_empty() {
+            let singularities = &self.removable_singularities;
+            let f_removed = |x: f64| {
+                if singularities.contains(&x) {
+                    let h = EPMACH.sqrt() * x.abs().max(1.0);
+                    f(x - h).mean(f(x + h))
+                } else {
+                    f(x)
+                }
+            };
+            let qag = Qag {
+                removable_singularities: vec![0.0; 0],
+                ..self.clone()
+            };
+            return qag.integrate_pipeline(ws, &f_removed, a, b, epsabs, epsrel, map, core);
+        }
+
+        if let Some(weights) = &self.norm_weights {
+            let probe = match (a.is_finite(), b.is_finite()) {
+                (true, true) => 0.5 * (a + b),
+                (true, false) => a + 1.0,
+                (false, true) => b - 1.0,
+                (false, false) => 0.0,
+            };
+            if f(probe).len() != weights.len()
+                || weights.iter().any(|&w| !w.is_finite() || w <= 0.0)
+            {
+                return Err(QagError::InvalidWeights);
+            }
+            let scale = Array1::from_iter(weights.iter().map(|w| w.sqrt()));
+            let f_weighted = |x: f64| f(x).weighted(&scale);
+            let qag = Qag {
+                norm_weights: None,
+                ..self.clone()
+            };
+            let map = OutputMap {
+                scale: Some(scale.clone()),
+                ..map.clone()
+            };
+            let (mut res, output) =
+                qag.integrate_pipeline(ws, &f_weighted, a, b, epsabs, epsrel, &map, core)?;
+            res.result /= &scale;
+            return Ok((res, output));
+        }
+
+        let maps: Option<(ReparamFn, ReparamFn, ReparamFn)> = match &self.reparam {
+            Reparam::Linear => None,
+            Reparam::Log if a <= 0.0 => return Err(QagError::InvalidReparam),
+            Reparam::Log => Some((Arc::new(f64::ln), Arc::new(f64::exp), Arc::new(f64::exp))),
+            Reparam::Custom {
+                forward,
+                inverse,
+                jacobian,
+            } => Some((forward.clone(), inverse.clone(), jacobian.clone())),
+        };
+        if let Some((forward, inverse, jacobian)) = maps {
+            let f_reparam = |u: f64| f(inverse(u)).scaled(jacobian(u));
+            let qag = Qag {
+                points: self.points.iter().map(|&x| forward(x)).collect(),
+                reparam: Reparam::Linear,
+                ..self.clone()
+            };
+            let (a, b) = (forward(a), forward(b));
+            return qag.integrate_pipeline(ws, &f_reparam, a, b, epsabs, epsrel, map, core);
+        }
+
+        if let Some((f2, a2, b2)) = infinite_integrand(f, a, b) {
+            let qag = Qag {
+                points: points_transformed(self.points.clone(), a, b),
+                ..self.clone()
             };
+            return core(&qag, ws, &f2, a2, b2, epsabs, epsrel, map);
         }
 
-        self.qintegrate(&fun, a, b, epsabs, epsrel)
+        core(self, ws, f, a, b, epsabs, epsrel, map)
+    }
+
+    /// Error bound of the [tolerance_logic](Qag::tolerance_logic).
+    ///
+    /// With 'epsabs' <= 0, if the relative bound is below the accumulated roundoff 'rounderr',
+    /// so that it cannot be achieved (e.g. for an integral vanishing by symmetry), it is
+    /// promoted to 'epsrel' * 'resabs', the integral of |f| estimated on the initial intervals.
+    fn errbnd(
+        &self,
+        epsabs: f64,
+        epsrel: f64,
+        result: &Array1<f64>,
+        rounderr: f64,
+        resabs: f64,
+    ) -> f64 {
+        let errbnd = self.tolerance_logic.errbnd(epsabs, epsrel, result);
+        if epsabs <= 0.0 && epsrel * norm_ar(result) < rounderr {
+            return errbnd.max(epsrel * resabs);
+        }
+        errbnd
     }
 
     /// Adaptive integration of a vector-valued function.
@@ -110,24 +790,109 @@ impl Qag {
         epsabs: f64,
         epsrel: f64,
     ) -> Result<QagIntegrationResult, QagError> {
+        self.qintegrate_with_workspace(&mut QagWorkspace::new(), fun, a, b, epsabs, epsrel)
+    }
+
+    /// Same as [qintegrate](Qag::qintegrate), but reusing the containers of a [QagWorkspace].
+    ///
+    /// This function is not intended to be called directly.
+    /// Use [integrate_with_workspace](Qag::integrate_with_workspace) instead.
+    pub fn qintegrate_with_workspace(
+        &self,
+        ws: &mut QagWorkspace,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        self.qintegrate_until(ws, fun, a, b, epsabs, epsrel, None)
+    }
+
+    /// Same as [qintegrate_with_workspace](Qag::qintegrate_with_workspace), also stopping as
+    /// soon as 'stop' is true for the current result.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn qintegrate_until(
+        &self,
+        ws: &mut QagWorkspace,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        stop: Option<StopFn>,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let f = &fun.components;
+        let n: usize = f(0.5 * (a + b)).len();
+        let rule = |x: f64, y: f64| self.key.quadrature(&**f, x, y);
+        self.qintegrate_rule(ws, n, &rule, a, b, epsabs, epsrel, stop)
+    }
+
+    /// Adaptive integration of an 'n'-components function whose Gauss-Kronrod estimates over a
+    /// sub-interval are given by 'rule', applying the rule of [key](Qag::key).
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn qintegrate_rule<R>(
+        &self,
+        ws: &mut QagWorkspace,
+        n: usize,
+        rule: &R,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        stop: Option<StopFn>,
+    ) -> Result<QagIntegrationResult, QagError>
+    where
+        R: Fn(f64, f64) -> (Array1<f64>, f64, f64) + Sync,
+    {
+        if self.batch_size == 0 {
+            return Err(QagError::InvalidBatchSize);
+        }
         if epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH) {
             return Err(QagError::Invalid);
         }
+        if n == 0 {
+            return Err(QagError::EmptyIntegrand);
+        }
 
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(self.number_of_thread)
-            .build()
-            .unwrap();
+        ws.clear();
+        let more_info = ws.more_info.unwrap_or(self.more_info);
+        let pool = match self.backend {
+            Backend::Rayon => {
+                ws.set_pool(self.number_of_thread);
+                ws.pool.as_ref().and_then(|(_, pool)| pool.as_ref())
+            }
+            Backend::StdThreads => None,
+        };
+        let threads = match self.number_of_thread {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let interrupt = ws.interrupt.clone();
+        let progress = ws.progress.clone();
+        let report = |last: usize, abserr: f64| {
+            if let Some(tx) = progress.as_ref() {
+                // A dropped receiver does not stop the integration.
+                let _ = tx.send(ProgressMsg { last, abserr });
+            }
+        };
+        let stopped = |result: &Array1<f64>| {
+            stop.map_or(false, |stop| stop(result))
+                || interrupt
+                    .as_ref()
+                    .map_or(false, |flag| flag.load(Ordering::Relaxed))
+        };
 
-        let mut initial_intervals = vec![];
-        let mut points = self.points.clone();
-        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let initial_intervals = &mut ws.initial_intervals;
+        let points = &mut ws.points;
+        points.extend_from_slice(&self.points);
+        points.sort_by(f64::total_cmp);
 
         if points.is_empty() {
             initial_intervals.push((a, b));
         } else {
             let mut prev = a;
-            for p in points {
+            for &p in points.iter() {
                 if p > a && p < b {
                     initial_intervals.push((prev, p));
                     prev = p;
@@ -136,67 +901,76 @@ impl Qag {
             initial_intervals.push((prev, b));
         }
 
-        let f = &fun.components;
-        let n: usize = f(0.0).len();
-        let mut neval = 0;
         let mut last = 1;
-        let mut interval_cache = HashMap::new();
-        let mut heap = BinaryHeap::new();
+        let interval_cache = &mut ws.interval_cache;
+        let heap = &mut ws.heap;
+        let capped = &mut ws.capped;
         let mut result = Array1::<f64>::zeros(n);
         let mut abserr = 0.0;
         let mut rounderr = 0.0;
         let mut iroff1 = 0;
         let mut iroff2 = 0;
-        let mut keyf = self.key;
-        if self.key <= 0 {
-            keyf = 1;
-        }
-        if self.key >= 7 {
-            keyf = 6;
-        }
-
-        for comp in initial_intervals {
-            let (result_temp, abserr_temp, rounderr_temp) = match keyf {
-                1 => qk15_quadrature(&**f, comp.0, comp.1),
-                2 => qk21_quadrature(&**f, comp.0, comp.1),
-                3 => qk31_quadrature(&**f, comp.0, comp.1),
-                4 => qk41_quadrature(&**f, comp.0, comp.1),
-                5 => qk51_quadrature(&**f, comp.0, comp.1),
-                6 => qk61_quadrature(&**f, comp.0, comp.1),
-                _ => (Array1::<f64>::from_vec(vec![0.0; f(0.0).len()]), 0.0, 0.0),
-            };
+
+        for &comp in initial_intervals.iter() {
+            let (result_temp, abserr_temp, rounderr_temp) = rule(comp.0, comp.1);
             result += &(Array1::<f64>::from(result_temp.clone()));
             abserr += abserr_temp;
             rounderr += rounderr_temp;
             heap.push(HeapItem::new((comp.0, comp.1), abserr_temp));
             interval_cache.insert((Myf64 { x: comp.0 }, Myf64 { x: comp.1 }), result_temp);
         }
+        let mut peak_intervals = heap.len();
 
-        let mut errbnd = epsabs.max(epsrel * norm_ar(&result));
+        let resabs = rounderr / (ROUNDOFF_FACTOR * EPMACH);
+        let mut errbnd = self.errbnd(epsabs, epsrel, &result, rounderr, resabs);
+        let mut history = vec![];
+        if more_info {
+            history.push((last, abserr));
+        }
+        report(last, abserr + rounderr);
 
-        if abserr + rounderr <= errbnd {
-            if keyf != 1 {
-                neval = (10 * keyf + 1) * (2 * last as i32 - 1);
-            }
-            if keyf == 1 {
-                neval = 30 * last as i32 + 15;
-            }
-            abserr = abserr + rounderr;
-            if self.more_info {
-                return Ok(QagIntegrationResult::new_more_info(
+        if abserr + rounderr <= errbnd || stopped(&result) {
+            let neval = evals_for(self.key, last);
+            let truncation_err = abserr;
+            abserr += rounderr;
+            let difficulty =
+                Difficulty::classify(heap, initial_intervals, norm_ar(&result) / resabs);
+            let mut res = if more_info {
+                QagIntegrationResult::new_more_info(
                     result,
                     abserr,
                     neval,
                     last,
-                    interval_cache,
-                    heap,
-                ));
+                    std::mem::take(interval_cache),
+                    std::mem::take(heap),
+                )
             } else {
-                return Ok(QagIntegrationResult::new(result, abserr));
+                QagIntegrationResult::new(result, abserr)
+            };
+            res.truncation_err = truncation_err;
+            res.roundoff_err = rounderr;
+            res.peak_intervals = peak_intervals;
+            res.difficulty = Some(difficulty);
+            if let Some(more_info) = res.more_info.as_mut() {
+                more_info.history = history;
+            }
+            res.success_kind = Some(
+                self.tolerance_logic
+                    .success_kind(epsabs, epsrel, &res.result),
+            );
+            if self.record_refinement_trace {
+                res.refinement_trace = Some(Vec::new());
             }
+            if self.errors_only {
+                res.interval_errors = Some(match res.more_info.as_ref() {
+                    Some(more_info) => interval_errors(&more_info.heap),
+                    None => interval_errors(heap),
+                });
+            }
+            return Ok(res);
         }
 
-        if self.limit == 1 {
+        if self.limit == 1 && self.limit_behavior == LimitBehavior::Error {
             return Err(QagError::MaxIteration);
         }
 
@@ -204,135 +978,132 @@ impl Qag {
             return Err(QagError::BadTolerance);
         }
 
+        let mut trace: Option<Vec<RefineEvent>> = if self.record_refinement_trace {
+            Some(Vec::new())
+        } else {
+            None
+        };
+
+        let bisect = |comp: &(f64, f64, u32, f64)| {
+            let (a1, b1) = (comp.0, comp.3);
+            let (a2, b2) = (comp.3, comp.1);
+            let (result1, abserr1, rounderr1) = rule(a1, b1);
+            let (result2, abserr2, rounderr2) = rule(a2, b2);
+            (
+                (a1, b1, result1, abserr1, rounderr1),
+                (a2, b2, result2, abserr2, rounderr2),
+            )
+        };
+
+        let mut exhausted = false;
+        let mut batch_size = self.batch_size;
+
         while last < self.limit {
-            let mut to_process = vec![];
+            let to_process = &mut ws.to_process;
+            to_process.clear();
             let mut err_sum = 0.0;
             let mut old_result = Array1::<f64>::zeros(n);
             let max_new_divison = self.limit - last;
 
-            while to_process.len() < 128.min(max_new_divison) && heap.len() != 0 {
-                let old_interval = heap.pop().unwrap();
+            while to_process.len() < batch_size.min(max_new_divison) {
+                let Some(old_interval) = heap.pop() else {
+                    break;
+                };
+                if matches!(self.max_depth, Some(max_depth) if old_interval.depth >= max_depth) {
+                    capped.push(old_interval);
+                    continue;
+                }
                 let ((x, y), old_err) = (old_interval.interval, old_interval.err);
+                let depth = old_interval.depth;
                 if bad_function_flag(x, y) {
                     return Err(QagError::BadFunction);
                 }
-                let old_res = interval_cache
-                    .remove(&(Myf64 { x }, Myf64 { x: y }))
-                    .unwrap();
+                let old_res = match interval_cache.remove(&(Myf64 { x }, Myf64 { x: y })) {
+                    Some(old_res) => old_res,
+                    None => return Err(QagError::MissingInterval { a: x, b: y }),
+                };
                 err_sum += old_err;
                 old_result += &Array1::<f64>::from(old_res);
-                to_process.push((x, y));
+                let split = match &self.split_fn {
+                    Some(split_fn) => split_fn(x, y),
+                    None => 0.5 * (x + y),
+                };
+                if !(split > x && split < y) {
+                    return Err(QagError::BadSplit {
+                        a: x,
+                        b: y,
+                        x: split,
+                    });
+                }
+                to_process.push((x, y, depth, split));
+                if let Some(trace) = trace.as_mut() {
+                    trace.push(RefineEvent::new((x, y), old_err));
+                }
                 if err_sum > abserr - errbnd / 8.0 {
                     break;
                 }
             }
 
+            if to_process.is_empty() {
+                exhausted = true;
+                break;
+            }
+
             last += to_process.len();
 
-            let new_result: (Vec<_>, Vec<_>) = pool.install(|| {
-                to_process
-                    .par_iter()
-                    .map(|comp| {
-                        let mut result1 = Array1::<f64>::from_elem(1, 0.0);
-                        let mut abserr1 = 0.0;
-                        let mut rounderr1 = 0.0;
-
-                        let mut result2 = Array1::<f64>::from_elem(1, 0.0);
-                        let mut abserr2 = 0.0;
-                        let mut rounderr2 = 0.0;
-
-                        let a1 = comp.0;
-                        let b1 = 0.5 * (comp.0 + comp.1);
-                        let a2 = b1;
-                        let b2 = comp.1;
-
-                        match keyf {
-                            1 => {
-                                (result1, abserr1, rounderr1) = qk15_quadrature(&**f, a1, b1);
-                                (result2, abserr2, rounderr2) = qk15_quadrature(&**f, a2, b2);
-                            }
-                            2 => {
-                                (result1, abserr1, rounderr1) = qk21_quadrature(&**f, a1, b1);
-                                (result2, abserr2, rounderr2) = qk21_quadrature(&**f, a2, b2);
-                            }
-                            3 => {
-                                (result1, abserr1, rounderr1) = qk31_quadrature(&**f, a1, b1);
-                                (result2, abserr2, rounderr2) = qk31_quadrature(&**f, a2, b2);
-                            }
-                            4 => {
-                                (result1, abserr1, rounderr1) = qk41_quadrature(&**f, a1, b1);
-                                (result2, abserr2, rounderr2) = qk41_quadrature(&**f, a2, b2);
-                            }
-                            5 => {
-                                (result1, abserr1, rounderr1) = qk51_quadrature(&**f, a1, b1);
-                                (result2, abserr2, rounderr2) = qk51_quadrature(&**f, a2, b2);
-                            }
-                            6 => {
-                                (result1, abserr1, rounderr1) = qk61_quadrature(&**f, a1, b1);
-                                (result2, abserr2, rounderr2) = qk61_quadrature(&**f, a2, b2);
-                            }
-                            _ => (),
-                        }
-                        (
-                            (a1, b1, result1, abserr1, rounderr1),
-                            (a2, b2, result2, abserr2, rounderr2),
-                        )
-                    })
-                    .collect()
-            });
+            let start = self.auto_batch.map(|_| Instant::now());
+            let new_result: (Vec<_>, Vec<_>) = match (self.backend, pool) {
+                (Backend::Rayon, Some(pool)) => {
+                    pool.install(|| to_process.par_iter().map(bisect).collect())
+                }
+                (Backend::StdThreads, _) if threads > 1 => {
+                    scoped_map(to_process, &bisect, threads).into_iter().unzip()
+                }
+                _ => to_process.iter().map(bisect).collect(),
+            };
+            if let (Some(auto_batch), Some(start)) = (self.auto_batch, start) {
+                batch_size = auto_batch.next_batch_size(start.elapsed(), to_process.len());
+            }
 
             let mut new_res = Array1::<f64>::zeros(n);
             let mut new_abserr = 0.0;
 
-            for k in 0..new_result.0.len() {
-                new_res += &(Array1::<f64>::from(new_result.0[k].2.clone()));
-                new_res += &(Array1::<f64>::from(new_result.1[k].2.clone()));
-                new_abserr += new_result.0[k].3 + new_result.1[k].3;
-                rounderr += new_result.0[k].4 + new_result.1[k].4;
-                interval_cache.insert(
-                    (
-                        Myf64 {
-                            x: new_result.0[k].0,
-                        },
-                        Myf64 {
-                            x: new_result.0[k].1,
-                        },
-                    ),
-                    new_result.0[k].2.clone(),
-                );
-                interval_cache.insert(
-                    (
-                        Myf64 {
-                            x: new_result.1[k].0,
-                        },
-                        Myf64 {
-                            x: new_result.1[k].1,
-                        },
-                    ),
-                    new_result.1[k].2.clone(),
-                );
-                heap.push(HeapItem::new(
-                    (new_result.0[k].0, new_result.0[k].1),
-                    new_result.0[k].3,
-                ));
-                heap.push(HeapItem::new(
-                    (new_result.1[k].0, new_result.1[k].1),
-                    new_result.1[k].3,
-                ));
+            if let Some(trace) = trace.as_mut() {
+                let start = trace.len() - new_result.0.len();
+                for (k, event) in trace[start..].iter_mut().enumerate() {
+                    event.children_err = (new_result.0[k].3, new_result.1[k].3);
+                }
+            }
+
+            let children = new_result.0.into_iter().zip(new_result.1);
+            for (comp, (left, right)) in to_process.iter().zip(children) {
+                new_res += &left.2;
+                new_res += &right.2;
+                new_abserr += left.3 + right.3;
+                rounderr += left.4 + right.4;
+                for (x, y, res, err, _) in [left, right] {
+                    interval_cache.insert((Myf64 { x }, Myf64 { x: y }), res);
+                    heap.push(HeapItem::new_with_depth((x, y), err, comp.2 + 1));
+                }
             }
+            peak_intervals = peak_intervals.max(heap.len() + capped.len());
             if iroff1_flag(&old_result, &new_res, new_abserr, err_sum) {
                 iroff1 += 1;
             }
-            if last > 10 && new_abserr > err_sum {
+            if last > self.roundoff_warmup && new_abserr > err_sum {
                 iroff2 += 1;
             }
             result += &new_res;
             result -= &old_result;
             abserr += new_abserr - err_sum;
+            if more_info {
+                history.push((last, abserr));
+            }
+            report(last, abserr + rounderr);
 
-            errbnd = epsabs.max(epsrel * norm_ar(&result));
+            errbnd = self.errbnd(epsabs, epsrel, &result, rounderr, resabs);
 
-            if abserr <= errbnd / 8.0 {
+            if abserr <= errbnd / 8.0 || stopped(&result) {
                 break;
             }
             if abserr < rounderr || iroff1 >= IROFF1_THRESHOLD || iroff2 >= IROFF2_THRESHOLD {
@@ -340,41 +1111,128 @@ impl Qag {
             }
         }
 
-        if abserr > errbnd / 8.0 && last >= self.limit {
-            return Err(QagError::MaxIteration);
-        }
+        heap.extend(capped.drain(..));
 
-        if keyf != 1 {
-            neval = (10 * keyf + 1) * (2 * last as i32 - 1);
-        }
-        if keyf == 1 {
-            neval = 30 * last as i32 + 15;
+        let mut failure = None;
+        if abserr > errbnd / 8.0 && (last >= self.limit || exhausted) {
+            match self.limit_behavior {
+                LimitBehavior::Error => return Err(QagError::MaxIteration),
+                LimitBehavior::ReturnBest => failure = Some(QagError::MaxIteration),
+            }
         }
 
-        abserr = abserr + rounderr;
+        let neval = evals_for(self.key, last);
+
+        let truncation_err = abserr;
+        abserr += rounderr;
 
-        if self.more_info {
-            return Ok(QagIntegrationResult::new_more_info(
+        let difficulty = Difficulty::classify(heap, initial_intervals, norm_ar(&result) / resabs);
+        let mut res = if more_info {
+            QagIntegrationResult::new_more_info(
                 result,
                 abserr,
                 neval,
                 last,
-                interval_cache,
-                heap,
-            ));
+                std::mem::take(interval_cache),
+                std::mem::take(heap),
+            )
         } else {
-            return Ok(QagIntegrationResult::new(result, abserr));
+            QagIntegrationResult::new(result, abserr)
+        };
+        res.truncation_err = truncation_err;
+        res.roundoff_err = rounderr;
+        res.peak_intervals = peak_intervals;
+        res.difficulty = Some(difficulty);
+        if let Some(more_info) = res.more_info.as_mut() {
+            more_info.history = history;
+        }
+        if failure.is_none() {
+            res.success_kind = Some(
+                self.tolerance_logic
+                    .success_kind(epsabs, epsrel, &res.result),
+            );
+        }
+        res.failure = failure;
+        res.refinement_trace = trace;
+        if self.errors_only {
+            res.interval_errors = Some(match res.more_info.as_ref() {
+                Some(more_info) => interval_errors(&more_info.heap),
+                None => interval_errors(heap),
+            });
         }
+        Ok(res)
     }
 }
 
+/// 'f' applied to every item on up to 'threads' scoped threads, in the order of 'items'.
+///
+/// The threads take the next item from a shared counter until none is left, and a panic of 'f'
+/// is propagated to the caller.
+fn scoped_map<T, U, F>(items: &[T], f: &F, threads: usize) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let mut indexed: Vec<(usize, U)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads.min(items.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return done;
+                        };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| std::panic::resume_unwind(err))
+            })
+            .collect()
+    });
+    indexed.sort_by_key(|(i, _)| *i);
+    indexed.into_iter().map(|(_, u)| u).collect()
+}
+
+/// Sub-intervals (left, right, err) of the heap, sorted by their left end.
+fn interval_errors(heap: &BinaryHeap<HeapItem>) -> Vec<(f64, f64, f64)> {
+    let mut interval_errors: Vec<(f64, f64, f64)> = heap
+        .iter()
+        .map(|item| (item.interval.0, item.interval.1, item.err))
+        .collect();
+    interval_errors.sort_by(|a, b| a.0.total_cmp(&b.0));
+    interval_errors
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::constants::{FnVec, Myf64};
+    use crate::auto_quad::Quad;
+    use crate::constants::{evals_for, norm_ar, FnVec, Myf64};
     use crate::errors::QagError;
-    use crate::qag::Qag;
-    use ndarray::array;
+    use crate::qag::{
+        AutoBatch, Backend, GaussKronrodRule, IntegrateOpts, LimitBehavior, Qag, Reparam,
+        ToleranceLogic,
+    };
+    use crate::qag_integration_result::{Difficulty, MoreInfo, QagIntegrationResult, SuccessKind};
+    use crate::qag_workspace::QagWorkspace;
+    use crate::qags::Qags;
+    use crate::test_functions::{
+        assert_transform_case, assert_transform_output, battery, transform_cases, TestFunction,
+    };
+    use ndarray::{array, Array1};
+    use std::collections::{BinaryHeap, HashMap};
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
+    use std::time::Duration;
 
     #[test]
     fn max_iteration1() {
@@ -383,14 +1241,14 @@ mod tests {
         let epsrel = 0.0;
         let epsabs = 1.0e-2;
         let limit = 1;
-        let key = 6;
+        let key = GaussKronrodRule::G30K61;
 
         let qag = Qag {
             key,
             limit,
-            points: vec![0.0; 0],
             number_of_thread: 8,
             more_info: true,
+            ..Default::default()
         };
 
         let f = FnVec {
@@ -408,14 +1266,14 @@ mod tests {
         let epsrel = 0.0;
         let epsabs = 1.0e-2;
         let limit = 30;
-        let key = 6;
+        let key = GaussKronrodRule::G30K61;
 
         let qag = Qag {
             key,
             limit,
-            points: vec![0.0; 0],
             number_of_thread: 8,
             more_info: true,
+            ..Default::default()
         };
 
         let f = FnVec {
@@ -434,14 +1292,14 @@ mod tests {
         let epsrel = 1.0e-30;
         let epsabs = 0.0;
         let limit = 30;
-        let key = 6;
+        let key = GaussKronrodRule::G30K61;
 
         let qag = Qag {
             key,
             limit,
-            points: vec![0.0; 0],
             number_of_thread: 8,
             more_info: true,
+            ..Default::default()
         };
 
         let f = FnVec {
@@ -462,13 +1320,13 @@ mod tests {
         let limit = 10000;
         let correct_result = [1.0 - 10000.0_f64.cos(), 10000.0_f64.sin()];
 
-        for key in 1..7 {
+        for key in GaussKronrodRule::ALL {
             let qag = Qag {
                 key,
                 limit,
-                points: vec![0.0; 0],
                 number_of_thread: 8,
                 more_info: true,
+                ..Default::default()
             };
 
             let f = FnVec {
@@ -490,15 +1348,15 @@ mod tests {
         let epsrel = 0.0;
         let epsabs = 1.0e-12;
         let limit = 10000;
-        let key = 6;
+        let key = GaussKronrodRule::G30K61;
         let correct_result = [0.4, 0.6];
 
         let qag = Qag {
             key,
             limit,
-            points: vec![0.0; 0],
             number_of_thread: 8,
             more_info: true,
+            ..Default::default()
         };
 
         let f = FnVec {
@@ -514,12 +1372,12 @@ mod tests {
         let res2 = qag.integrate(&f, c, a, epsabs, epsrel).unwrap();
 
         assert!(
-            res1.result[0] - correct_result[0] < epsabs
-                && res1.result[1] - correct_result[1] < epsabs
+            (res1.result[0] - correct_result[0]).abs() < epsabs
+                && (res1.result[1] - correct_result[1]).abs() < epsabs
         );
         assert!(
-            res2.result[0] - correct_result[0] < epsabs
-                && res2.result[1] - correct_result[1] < epsabs
+            (res2.result[0] - correct_result[0]).abs() < epsabs
+                && (res2.result[1] - correct_result[1]).abs() < epsabs
         );
     }
     #[test]
@@ -529,15 +1387,15 @@ mod tests {
         let epsrel = 0.0;
         let epsabs = 1.0e-10;
         let limit = 10000;
-        let key = 6;
-        let correct_result = [1.2879903316984565533522585284072106913, 1.5974];
+        let key = GaussKronrodRule::G30K61;
+        let correct_result = [1.2879903316984564, 1.5974];
 
         let qag = Qag {
             key,
             limit,
-            points: vec![0.0; 0],
             number_of_thread: 8,
             more_info: true,
+            ..Default::default()
         };
 
         let f = FnVec {
@@ -562,7 +1420,7 @@ mod tests {
         let epsrel = 0.0;
         let epsabs = 1.0;
         let limit = 10000;
-        let key = 6;
+        let key = GaussKronrodRule::G30K61;
         let points = vec![0.0, 0.2, 0.4, 0.6, 0.8, 1.0];
 
         let qag = Qag {
@@ -571,6 +1429,7 @@ mod tests {
             points: points.clone(),
             number_of_thread: 8,
             more_info: true,
+            ..Default::default()
         };
         let f = FnVec {
             components: Arc::new(|x: f64| array![x.cos(), x.sin()]),
@@ -579,8 +1438,1371 @@ mod tests {
         let mut res_hash = res.more_info.unwrap().hash.clone();
         assert_eq!(res_hash.len(), qag.points.len() - 1);
         for k in 0..points.len() - 1 {
-            res_hash.remove(&((Myf64 { x: points[k] }, Myf64 { x: points[k + 1] })));
+            res_hash.remove(&(Myf64 { x: points[k] }, Myf64 { x: points[k + 1] }));
         }
         assert_eq!(res_hash.len(), 0);
     }
+    #[test]
+    fn points_default_limit() {
+        // Only the bisections count towards the limit, not the initial sub-intervals.
+        let qag = Qag {
+            points: (1..=10).map(|k| k as f64 / 11.0).collect(),
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5)]),
+        };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-6, 0.0).unwrap();
+        assert!(res.failure.is_none());
+        assert!((res.result[0] - 2.0).abs() < 1.0e-6);
+        let more_info = res.more_info.unwrap();
+        assert!(more_info.last < qag.limit);
+        assert!(more_info.heap.len() > qag.limit);
+    }
+    #[test]
+    fn ignored_points() {
+        let qag = Qag {
+            limit: 10000,
+            points: vec![5.0],
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.cos(), x.sin()]),
+        };
+
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let res_no_points = Qag {
+            points: vec![0.0; 0],
+            ..qag.clone()
+        }
+        .integrate(&f, 0.0, 1.0, 1.0e-10, 0.0)
+        .unwrap();
+        assert_eq!(res.ignored_points, vec![5.0]);
+        assert_eq!(res.result, res_no_points.result);
+        assert!(res_no_points.ignored_points.is_empty());
+
+        let qag = Qag {
+            points: vec![0.5, 5.0, -1.0, 1.0],
+            ..qag
+        };
+        let res = qag.integrate(&f, 1.0, 0.0, 1.0e-10, 0.0).unwrap();
+        assert_eq!(res.ignored_points, vec![5.0, -1.0]);
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![(-x).exp()]),
+        };
+        let res = qag.integrate(&g, 0.0, f64::INFINITY, 1.0e-10, 0.0).unwrap();
+        assert_eq!(res.ignored_points, vec![-1.0]);
+    }
+    #[test]
+    fn mesh() {
+        let a = 0.0;
+        let b = 1.0;
+        let epsrel = 0.0;
+        let epsabs = 1.0e-12;
+        let limit = 10000;
+        let key = GaussKronrodRule::G30K61;
+        let points = vec![0.6, 0.2, 0.4, 0.8];
+
+        let qag = Qag {
+            key,
+            limit,
+            points,
+            number_of_thread: 8,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sqrt(), (30.0 * x).sin()]),
+        };
+        let res = qag.integrate(&f, a, b, epsabs, epsrel).unwrap();
+        let more_info = res.more_info.unwrap();
+        let mesh = more_info.mesh();
+        // The sub-intervals are contiguous, and the points are nodes of the refined mesh.
+        assert!(more_info.heap.len() > 5);
+        assert_eq!(mesh.len(), more_info.heap.len() + 1);
+        assert!(mesh.windows(2).all(|w| w[0] < w[1]));
+        for x in [0.0, 0.2, 0.4, 0.6, 0.8, 1.0] {
+            assert!(mesh.contains(&x));
+        }
+    }
+    #[test]
+    fn limit_return_best() {
+        let a = 0.0;
+        let b = 1000000.0;
+        let epsrel = 0.0;
+        let epsabs = 1.0e-2;
+        let limit = 30;
+        let key = GaussKronrodRule::G30K61;
+
+        let qag = Qag {
+            key,
+            limit,
+            number_of_thread: 8,
+            more_info: true,
+            limit_behavior: LimitBehavior::ReturnBest,
+            ..Default::default()
+        };
+
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.cos()]),
+        };
+        let res = qag.integrate(&f, a, b, epsabs, epsrel).unwrap();
+
+        assert_eq!(res.failure, Some(QagError::MaxIteration));
+        assert_eq!(res.result.len(), 2);
+        assert!(res.abserr > epsabs);
+        assert_eq!(res.more_info.unwrap().last, limit);
+    }
+    #[test]
+    fn limit_terminates() {
+        // Oscillating enough that none of the limits is sufficient.
+        let f = |calls: &AtomicUsize, x: f64| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            array![x.sin()]
+        };
+        for backend in [Backend::Rayon, Backend::StdThreads] {
+            for number_of_thread in [1, 8] {
+                for limit in [1, 2, 7, 300] {
+                    let qag = Qag {
+                        limit,
+                        number_of_thread,
+                        more_info: true,
+                        limit_behavior: LimitBehavior::ReturnBest,
+                        backend,
+                        ..Default::default()
+                    };
+                    let calls = AtomicUsize::new(0);
+                    let fun = FnVec {
+                        components: Arc::new(|x: f64| f(&calls, x)),
+                    };
+                    let res = qag.integrate(&fun, 0.0, 1.0e6, 1.0e-10, 0.0).unwrap();
+                    drop(fun);
+                    let more_info = res.more_info.unwrap();
+
+                    assert_eq!(res.failure, Some(QagError::MaxIteration));
+                    assert_eq!(more_info.last, limit);
+                    // One more evaluation probes the number of components.
+                    assert_eq!(more_info.neval + 1, calls.into_inner());
+                }
+            }
+        }
+    }
+    #[test]
+    fn workspace() {
+        let a = 0.0;
+        let epsrel = 0.0;
+        let epsabs = 1.0e-10;
+        let limit = 10000;
+        let key = GaussKronrodRule::G10K21;
+
+        let qag = Qag {
+            key,
+            limit,
+            number_of_thread: 2,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.cos()]),
+        };
+
+        let mut ws = QagWorkspace::new();
+        for k in 1..20 {
+            let b = k as f64;
+            let res_ws = qag
+                .integrate_with_workspace(&mut ws, &f, a, b, epsabs, epsrel)
+                .unwrap();
+            let res = qag.integrate(&f, a, b, epsabs, epsrel).unwrap();
+            assert_eq!(res_ws.result, res.result);
+            assert_eq!(res_ws.abserr, res.abserr);
+        }
+    }
+    #[test]
+    fn success_kind() {
+        let a = 0.0;
+        let b = 1.0;
+        let qag = Qag {
+            limit: 10000,
+            limit_behavior: LimitBehavior::ReturnBest,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![1.0e6 * x.exp()]),
+        };
+
+        let res_abs = qag.integrate(&f, a, b, 1.0e-2, 1.0e-12).unwrap();
+        let res_rel = qag.integrate(&f, a, b, 1.0e-12, 1.0e-6).unwrap();
+        let qag_limit = Qag {
+            limit: 1,
+            ..qag.clone()
+        };
+        let res_limit = qag_limit.integrate(&f, a, 100.0, 1.0e-12, 0.0).unwrap();
+
+        assert_eq!(res_abs.success_kind, Some(SuccessKind::Absolute));
+        assert_eq!(res_rel.success_kind, Some(SuccessKind::Relative));
+        assert_eq!(res_limit.success_kind, None);
+
+        assert_eq!(res_abs.failure, None);
+        assert_eq!(res_limit.failure, Some(QagError::MaxIteration));
+    }
+    #[test]
+    fn refinement_trace() {
+        let a = 0.0;
+        let b = 1.0;
+        let qag = Qag {
+            key: GaussKronrodRule::G7K15,
+            limit: 10000,
+            more_info: true,
+            record_refinement_trace: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5)]),
+        };
+
+        let res = qag.integrate(&f, a, b, 1.0e-10, 0.0).unwrap();
+        let trace = res.refinement_trace.unwrap();
+        let last = res.more_info.unwrap().last;
+
+        assert_eq!(trace.len(), last - 1);
+        assert_eq!(trace[0].interval, (a, b));
+        for event in &trace {
+            assert!(event.interval.0 < event.interval.1);
+            assert!(event.children_err.0 > 0.0 && event.children_err.1 > 0.0);
+        }
+        // The singularity at 0 dominates the error, so the leftmost interval is always refined.
+        for event in &trace[1..] {
+            assert_eq!(event.interval.0, a);
+        }
+
+        let qag_off = Qag {
+            record_refinement_trace: false,
+            ..qag
+        };
+        let res_off = qag_off.integrate(&f, a, b, 1.0e-10, 0.0).unwrap();
+        assert!(res_off.refinement_trace.is_none());
+    }
+    #[test]
+    fn reversed_bounds() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), (-x * x).exp()]),
+        };
+
+        for (a, b) in [
+            (0.0, 1.0),
+            (0.0, f64::INFINITY),
+            (f64::NEG_INFINITY, f64::INFINITY),
+        ] {
+            let res = qag.integrate(&f, a, b, 1.0e-10, 0.0);
+            let res_reversed = qag.integrate(&f, b, a, 1.0e-10, 0.0);
+            match (res, res_reversed) {
+                (Ok(res), Ok(res_reversed)) => {
+                    assert_eq!(res_reversed.result, -res.result);
+                    assert_eq!(res_reversed.abserr, res.abserr);
+                }
+                (Err(err), Err(err_reversed)) => assert_eq!(err, err_reversed),
+                _ => panic!("reversed bounds changed the outcome"),
+            }
+        }
+
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x]),
+        };
+        let res = qag.integrate(&f, 1.0, 0.0, 1.0e-10, 0.0).unwrap();
+        assert!((res.result[0] + 0.5).abs() < 1.0e-15);
+        assert!(res.abserr >= 0.0);
+    }
+    #[test]
+    fn empty_interval() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.cos(), 1.0]),
+        };
+
+        for a in [3.0, f64::INFINITY, f64::NEG_INFINITY] {
+            let res = qag.integrate(&f, a, a, 1.0e-10, 0.0).unwrap();
+            assert_eq!(res.result, array![0.0, 0.0, 0.0]);
+            assert_eq!(res.abserr, 0.0);
+            let more_info = res.more_info.unwrap();
+            assert_eq!((more_info.last, more_info.neval), (0, 0));
+            assert!(more_info.heap.is_empty());
+        }
+    }
+    #[test]
+    fn range() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.cos()]),
+        };
+
+        let res = qag.integrate(&f, 0.0, 2.0, 1.0e-10, 0.0).unwrap();
+        let res_range = qag.integrate_range(&f, 0.0..=2.0, 1.0e-10, 0.0).unwrap();
+        let res_reversed = qag.integrate_range(&f, 2.0..=0.0, 1.0e-10, 0.0).unwrap();
+
+        assert_eq!(res_range.result, res.result);
+        assert_eq!(res_reversed.result, -&res.result);
+
+        let opts = IntegrateOpts {
+            epsabs: 1.0e-10,
+            epsrel: 0.0,
+        };
+        let res_opts = qag.integrate_opts(&f, (0.0, 2.0), opts).unwrap();
+        assert_eq!(res_opts.result, res.result);
+        let res_default = qag
+            .integrate_opts(&f, (0.0, 2.0), IntegrateOpts::default())
+            .unwrap();
+        let res_positional = qag.integrate(&f, 0.0, 2.0, 1.49e-8, 1.49e-8).unwrap();
+        assert_eq!(res_default.result, res_positional.result);
+    }
+    #[test]
+    fn max_depth() {
+        let (a, b) = (0.0, 1.0);
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            limit_behavior: LimitBehavior::ReturnBest,
+            max_depth: Some(8),
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(x - 0.3).abs().powf(-0.5) + x.cos()]),
+        };
+        let correct_result = 2.0 * 0.3_f64.sqrt() + 2.0 * 0.7_f64.sqrt() + 1.0_f64.sin();
+
+        let res = qag.integrate(&f, a, b, 1.0e-12, 0.0).unwrap();
+        let more_info = res.more_info.unwrap();
+
+        assert_eq!(res.failure, Some(QagError::MaxIteration));
+        assert!(more_info.last < qag.limit);
+        assert!(more_info.heap.iter().all(|item| item.depth <= 8));
+        assert!(more_info.heap.iter().any(|item| item.depth == 8));
+        assert_eq!(more_info.heap.len(), more_info.hash.len());
+        assert!((res.result[0] - correct_result).abs() <= res.abserr);
+
+        let qag_error = Qag {
+            limit_behavior: LimitBehavior::Error,
+            ..qag.clone()
+        };
+        let res = qag_error.integrate(&f, a, b, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::MaxIteration);
+
+        let qag_no_cap = Qag {
+            max_depth: None,
+            ..qag
+        };
+        let res = qag_no_cap.integrate(&f, a, b, 1.0e-6, 0.0).unwrap();
+        assert!(res.failure.is_none());
+    }
+    #[test]
+    fn error_breakdown() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.powf(-0.5)]),
+        };
+
+        for (epsabs, b) in [(1.0e-1, 1.0), (1.0e-8, 1.0), (1.0e-8, 1.0e2)] {
+            let res = qag.integrate(&f, 0.0, b, epsabs, 0.0).unwrap();
+            assert!(res.truncation_err > 0.0);
+            assert!(res.roundoff_err > 0.0);
+            assert_eq!(res.abserr, res.truncation_err + res.roundoff_err);
+        }
+    }
+    #[test]
+    fn auto_batch() {
+        let auto_batch = AutoBatch::default();
+        let cheap = auto_batch.next_batch_size(Duration::from_micros(10), 100);
+        let expensive = auto_batch.next_batch_size(Duration::from_millis(10), 10);
+        assert_eq!(cheap, 10000);
+        assert_eq!(expensive, 1);
+        assert_eq!(auto_batch.next_batch_size(Duration::ZERO, 10), 1 << 16);
+
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 4,
+            auto_batch: Some(auto_batch),
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), (10.0 * x).sin()]),
+        };
+        let correct_result = [2.0, (1.0 - 10.0_f64.cos()) / 10.0];
+
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        for (res, correct_result) in res.result.iter().zip(correct_result) {
+            assert!((res - correct_result).abs() < 1.0e-10);
+        }
+    }
+    #[test]
+    fn batch_size() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 4,
+            more_info: true,
+            batch_size: 1,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), (10.0 * x).sin()]),
+        };
+        let correct_result = [2.0, (1.0 - 10.0_f64.cos()) / 10.0];
+
+        let mut last = vec![];
+        for batch_size in [1, 16, 1024] {
+            let qag = Qag {
+                batch_size,
+                ..qag.clone()
+            };
+            let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+            for (res, correct_result) in res.result.iter().zip(correct_result) {
+                assert!((res - correct_result).abs() < 1.0e-10);
+            }
+            last.push(res.more_info.unwrap().last);
+        }
+        assert!(last[0] <= last[1] && last[1] <= last[2]);
+
+        let qag = Qag {
+            batch_size: 0,
+            ..qag
+        };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::InvalidBatchSize);
+    }
+    #[test]
+    fn observed_order() {
+        let mut more_info = MoreInfo::new(0, 8, HashMap::new(), BinaryHeap::new());
+        more_info.history = vec![(1, 1.0), (2, 0.25), (4, 0.0625), (8, 0.015625)];
+        assert!((more_info.observed_order().unwrap() - 2.0).abs() < 1.0e-12);
+        more_info.history.truncate(2);
+        assert_eq!(more_info.observed_order(), None);
+
+        let qag = Qag {
+            key: GaussKronrodRule::G7K15,
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(100.0 * x).cos()]),
+        };
+        let res = qag.integrate(&f, 0.0, 10.0, 1.0e-10, 0.0).unwrap();
+        let more_info = res.more_info.unwrap();
+
+        assert_eq!(more_info.history[0].0, 1);
+        assert_eq!(more_info.history.last().unwrap().0, more_info.last);
+        assert!(more_info.observed_order().unwrap() > 0.0);
+    }
+    #[test]
+    fn removable_singularities() {
+        let qag = Qag {
+            key: GaussKronrodRule::G30K61,
+            limit: 10000,
+            removable_singularities: vec![0.0],
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin() / x]),
+        };
+        let correct_result = 1.892166140734366;
+
+        let res = qag.integrate(&f, -1.0, 1.0, 1.0e-12, 0.0).unwrap();
+        assert!((res.result[0] - correct_result).abs() < 1.0e-12);
+
+        let qag_undeclared = Qag {
+            removable_singularities: vec![0.0; 0],
+            ..qag
+        };
+        let res_undeclared = qag_undeclared.integrate(&f, -1.0, 1.0, 1.0e-12, 0.0);
+        assert!(res_undeclared.map_or(true, |res| res.result[0].is_nan()));
+    }
+    #[test]
+    fn split_fn() {
+        let kink = 1.0 / 3.0;
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(move |x: f64| array![(x - kink).abs()]),
+        };
+        let correct_result = 0.5 * (kink * kink + (1.0 - kink) * (1.0 - kink));
+
+        let qag_split = Qag {
+            split_fn: Some(Arc::new(move |a: f64, b: f64| {
+                if a < kink && kink < b {
+                    kink
+                } else {
+                    0.5 * (a + b)
+                }
+            })),
+            ..qag.clone()
+        };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-12, 0.0).unwrap();
+        let res_split = qag_split.integrate(&f, 0.0, 1.0, 1.0e-12, 0.0).unwrap();
+
+        assert!((res_split.result[0] - correct_result).abs() < 1.0e-12);
+        assert_eq!(res_split.more_info.unwrap().last, 2);
+        assert!(res.more_info.unwrap().last > 2);
+
+        let qag_invalid = Qag {
+            split_fn: Some(Arc::new(|a: f64, _b: f64| a)),
+            ..qag
+        };
+        let res_invalid = qag_invalid.integrate(&f, 0.0, 1.0, 1.0e-12, 0.0);
+        assert_eq!(
+            res_invalid.unwrap_err(),
+            QagError::BadSplit {
+                a: 0.0,
+                b: 1.0,
+                x: 0.0
+            }
+        );
+    }
+    #[test]
+    fn more_info_bytes() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), (30.0 * x).sin()]),
+        };
+        let more_info = qag
+            .integrate(&f, 0.0, 1.0, 1.0e-10, 0.0)
+            .unwrap()
+            .more_info
+            .unwrap();
+
+        let bytes = more_info.to_bytes();
+        let decoded = MoreInfo::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.neval, more_info.neval);
+        assert_eq!(decoded.last, more_info.last);
+        assert_eq!(decoded.history, more_info.history);
+        assert_eq!(decoded.hash, more_info.hash);
+        let items = |more_info: &MoreInfo| {
+            let mut items: Vec<_> = more_info
+                .heap
+                .iter()
+                .map(|item| (item.interval, item.err.to_bits(), item.depth))
+                .collect();
+            items.sort_by(|x, y| x.partial_cmp(y).unwrap());
+            items
+        };
+        assert_eq!(items(&decoded), items(&more_info));
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        let truncated = MoreInfo::from_bytes(&bytes[..bytes.len() - 1]);
+        assert_eq!(truncated.unwrap_err(), QagError::Decode);
+        assert_eq!(MoreInfo::from_bytes(b"JSON").unwrap_err(), QagError::Decode);
+        // Huge lengths in the header are rejected before allocating.
+        for field in 2..5 {
+            let mut huge = bytes.clone();
+            huge[4 + 8 * field..12 + 8 * field].copy_from_slice(&u64::MAX.to_le_bytes());
+            assert_eq!(MoreInfo::from_bytes(&huge).unwrap_err(), QagError::Decode);
+        }
+    }
+    #[test]
+    fn empty_and_singleton() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let empty = FnVec {
+            components: Arc::new(|_: f64| Array1::zeros(0)),
+        };
+        for (a, b) in [(0.0, 1.0), (0.0, f64::INFINITY)] {
+            let res = qag.integrate(&empty, a, b, 1.0e-10, 0.0);
+            assert_eq!(res.unwrap_err(), QagError::EmptyIntegrand);
+        }
+
+        // A single component behaves as any other one, e.g. with a vanishing companion.
+        let single = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5) * x.cos()]),
+        };
+        let padded = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5) * x.cos(), 0.0]),
+        };
+        let res = qag.integrate(&single, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let res_padded = qag.integrate(&padded, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        assert_eq!(res.result.len(), 1);
+        assert_eq!(res.result[0], res_padded.result[0]);
+        assert_eq!(res.abserr, res_padded.abserr);
+        assert_eq!(
+            res.more_info.unwrap().neval,
+            res_padded.more_info.unwrap().neval
+        );
+        // Integral of cos(x) / sqrt(x) over (0, 1), by its series.
+        let correct: f64 = (0..10)
+            .map(|k| {
+                let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                let factorial: f64 = (1..=2 * k).map(|j| j as f64).product();
+                sign / (factorial * (2.0 * k as f64 + 0.5))
+            })
+            .sum();
+        assert!((res.result[0] - correct).abs() < 1.0e-10);
+    }
+    #[test]
+    fn integrate_with_info() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sqrt() * (-x).exp(), (-x * x).exp()]),
+        };
+        let res = qag.integrate(&f, 0.0, f64::INFINITY, 1.0e-10, 0.0).unwrap();
+        assert!(res.more_info.is_none());
+
+        let res_info = qag
+            .integrate_with_info(&f, 0.0, f64::INFINITY, 1.0e-10, 0.0, true)
+            .unwrap();
+        assert_eq!(res_info.result, res.result);
+        assert_eq!(res_info.abserr, res.abserr);
+        assert!(res_info.more_info.is_some());
+        assert!(!qag.more_info);
+
+        let qag_info = Qag {
+            more_info: true,
+            ..qag
+        };
+        let res_no_info = qag_info
+            .integrate_with_info(&f, 0.0, f64::INFINITY, 1.0e-10, 0.0, false)
+            .unwrap();
+        assert_eq!(res_no_info.result, res.result);
+        assert!(res_no_info.more_info.is_none());
+    }
+
+    #[test]
+    fn peak_intervals() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        // Increasingly hard integrands need increasingly many sub-intervals.
+        let peaks: Vec<usize> = [0.0, 0.3, 0.6, 0.8]
+            .into_iter()
+            .map(|alpha| {
+                let f = FnVec {
+                    components: Arc::new(move |x: f64| array![x.powf(-alpha)]),
+                };
+                let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+                assert_eq!(res.peak_intervals, res.more_info.unwrap().heap.len());
+                let res_no_info = qag
+                    .integrate_with_info(&f, 0.0, 1.0, 1.0e-10, 0.0, false)
+                    .unwrap();
+                assert_eq!(res_no_info.peak_intervals, res.peak_intervals);
+                res.peak_intervals
+            })
+            .collect();
+        assert_eq!(peaks[0], 1);
+        assert!(peaks.windows(2).all(|w| w[0] < w[1]));
+
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.8)]),
+        };
+        let mut res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        res.compact(0.5);
+        assert_eq!(res.peak_intervals, peaks[3]);
+        assert!(res.more_info.unwrap().heap.len() < res.peak_intervals);
+    }
+
+    #[test]
+    fn sample_density() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), (30.0 * x).sin()]),
+        };
+        let more_info = qag
+            .integrate(&f, 0.0, 1.0, 1.0e-10, 0.0)
+            .unwrap()
+            .more_info
+            .unwrap();
+        let density = more_info.sample_density();
+        assert_eq!(density(-0.5), 0.0);
+        assert_eq!(density(1.5), 0.0);
+        assert!(density(1.0e-6) > density(0.5));
+
+        // The density is constant between the nodes of the mesh.
+        let qag_density = Qag {
+            points: more_info.mesh(),
+            more_info: false,
+            ..qag
+        };
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![density(x)]),
+        };
+        let total = qag_density.integrate(&g, 0.0, 1.0, 1.0e-6, 0.0).unwrap();
+        let neval = more_info.neval as f64;
+        assert!((total.result[0] - neval).abs() < 1.0e-9 * neval);
+    }
+    #[test]
+    fn compact() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), (30.0 * x).sin()]),
+        };
+        let mut res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let len = res.more_info.as_ref().unwrap().heap.len();
+        let err_sum = |res: &QagIntegrationResult| -> f64 {
+            res.more_info
+                .as_ref()
+                .unwrap()
+                .heap
+                .iter()
+                .map(|item| item.err)
+                .sum()
+        };
+        let err = err_sum(&res);
+
+        res.compact(0.1);
+        let more_info = res.more_info.as_ref().unwrap();
+        assert!(more_info.heap.len() < len);
+        assert_eq!(more_info.hash.len(), more_info.heap.len());
+        let mut intervals: Vec<_> = more_info.heap.iter().map(|item| item.interval).collect();
+        intervals.sort_by(|x, y| x.0.total_cmp(&y.0));
+        assert_eq!(intervals[0].0, 0.0);
+        assert_eq!(intervals[intervals.len() - 1].1, 1.0);
+        for w in intervals.windows(2) {
+            assert_eq!(w[0].1, w[1].0);
+        }
+        assert!((err_sum(&res) - err).abs() <= 1.0e-12 * err);
+        let mut total = Array1::<f64>::zeros(2);
+        for result in more_info.hash.values() {
+            total += result;
+        }
+        for k in 0..2 {
+            assert!((total[k] - res.result[k]).abs() < 1.0e-12);
+        }
+        let mut merged = res.clone();
+        merged.compact(0.0);
+        assert_eq!(merged.more_info.unwrap().heap.len(), more_info.heap.len());
+    }
+    #[test]
+    fn subintervals() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), (30.0 * x).sin()]),
+        };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let subintervals: Vec<_> = res.subintervals().collect();
+        assert_eq!(
+            subintervals.len(),
+            res.more_info.as_ref().unwrap().heap.len()
+        );
+        assert_eq!(subintervals[0].a, 0.0);
+        assert_eq!(subintervals[subintervals.len() - 1].b, 1.0);
+        for w in subintervals.windows(2) {
+            assert_eq!(w[0].b, w[1].a);
+        }
+        let mut total = Array1::<f64>::zeros(2);
+        for subinterval in &subintervals {
+            total += &Array1::from(subinterval.result.clone());
+        }
+        for k in 0..2 {
+            assert!((total[k] - res.result[k]).abs() < 1.0e-12);
+        }
+        let max_err = subintervals.iter().map(|s| s.abserr).fold(0.0, f64::max);
+        assert_eq!(
+            max_err,
+            res.more_info.as_ref().unwrap().heap.peek().unwrap().err
+        );
+
+        let qag = Qag {
+            more_info: false,
+            ..qag
+        };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        assert_eq!(res.subintervals().count(), 0);
+    }
+    #[test]
+    fn tolerance_logic() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let qag_and = Qag {
+            tolerance_logic: ToleranceLogic::And,
+            ..qag.clone()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5)]),
+        };
+
+        let res_or = qag.integrate(&f, 0.0, 1.0, 1.0e-3, 1.0e-10).unwrap();
+        let res_and = qag_and.integrate(&f, 0.0, 1.0, 1.0e-3, 1.0e-10).unwrap();
+
+        assert!(res_or.abserr > 2.0e-10);
+        assert!(res_and.abserr <= 2.0e-10);
+        assert!(res_and.more_info.unwrap().last > res_or.more_info.unwrap().last);
+        assert_eq!(res_or.success_kind, Some(SuccessKind::Absolute));
+        assert_eq!(res_and.success_kind, Some(SuccessKind::Relative));
+
+        let res_and_abs = qag_and.integrate(&f, 0.0, 1.0, 1.0e-3, 0.0).unwrap();
+        assert_eq!(res_and_abs.success_kind, Some(SuccessKind::Absolute));
+    }
+    #[test]
+    fn evals_for_neval() {
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5)]),
+        };
+        // The integer keys out of range are clamped.
+        for key in 0..8 {
+            let qag = Qag {
+                key: key.into(),
+                limit: 10000,
+                more_info: true,
+                ..Default::default()
+            };
+            let more_info = qag
+                .integrate(&f, 0.0, 1.0, 1.0e-10, 0.0)
+                .unwrap()
+                .more_info
+                .unwrap();
+            assert_eq!(evals_for(qag.key, more_info.last), more_info.neval);
+            assert_eq!(i32::from(qag.key), key.clamp(1, 6));
+        }
+        let (g7k15, g10k21, g30k61) = (
+            GaussKronrodRule::G7K15,
+            GaussKronrodRule::G10K21,
+            GaussKronrodRule::G30K61,
+        );
+        assert_eq!(evals_for(g10k21, 1), 21);
+        assert_eq!(evals_for(g30k61, 50), 61 * 99);
+        // The limit of the largest tests, and beyond the range of i32.
+        assert_eq!(evals_for(g30k61, 10_000_000), 61 * 19_999_999);
+        assert_eq!(evals_for(g30k61, 100_000_000), 61 * 199_999_999);
+        assert_eq!(evals_for(g7k15, 100_000_000), 3_000_000_015);
+        assert_eq!(evals_for(g30k61, usize::MAX), usize::MAX);
+    }
+    #[test]
+    fn neval_grows() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 4,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), x.ln()]),
+        };
+        let mut previous = (1, 21);
+        for epsabs in [1.0e-4, 1.0e-7, 1.0e-10] {
+            let more_info = qag
+                .integrate(&f, 0.0, 1.0, epsabs, 0.0)
+                .unwrap()
+                .more_info
+                .unwrap();
+            assert!(more_info.last > previous.0);
+            assert!(more_info.neval > previous.1);
+            assert_eq!(more_info.neval, 21 * (2 * more_info.last - 1));
+            previous = (more_info.last, more_info.neval);
+        }
+    }
+    #[test]
+    fn test_functions() {
+        let epsrel = 0.0;
+        let epsabs = 1.0e-8;
+        let limit = 10000;
+
+        for key in GaussKronrodRule::ALL {
+            let qag = Qag {
+                key,
+                limit,
+                number_of_thread: 8,
+                ..Default::default()
+            };
+            for test in battery() {
+                let res = qag
+                    .integrate(&test.f, test.a, test.b, epsabs, epsrel)
+                    .unwrap();
+                assert!(
+                    (res.result[0] - test.correct_result[0]).abs() < epsabs,
+                    "{} with key {:?}",
+                    test.name,
+                    key
+                );
+            }
+        }
+    }
+    #[test]
+    fn serial_parallel_equivalence() {
+        let epsrel = 0.0;
+        let epsabs = 1.0e-10;
+
+        for key in GaussKronrodRule::ALL {
+            let serial = Qag {
+                key,
+                limit: 10000,
+                more_info: true,
+                limit_behavior: LimitBehavior::ReturnBest,
+                ..Default::default()
+            };
+            let parallel = Qag {
+                number_of_thread: 8,
+                ..serial.clone()
+            };
+            for test in battery() {
+                let res = serial.integrate(&test.f, test.a, test.b, epsabs, epsrel);
+                let res_par = parallel.integrate(&test.f, test.a, test.b, epsabs, epsrel);
+                let (res, res_par) = match (res, res_par) {
+                    (Ok(res), Ok(res_par)) => (res, res_par),
+                    (res, res_par) => {
+                        assert_eq!(res.err(), res_par.err(), "{} with key {:?}", test.name, key);
+                        continue;
+                    }
+                };
+                assert_eq!(res.failure, res_par.failure);
+                assert_eq!(res.result.len(), res_par.result.len());
+                for (x, y) in res.result.iter().zip(&res_par.result) {
+                    assert!(
+                        (x - y).abs() < 1.0e-12,
+                        "{} with key {:?}: {} != {}",
+                        test.name,
+                        key,
+                        x,
+                        y
+                    );
+                }
+                assert_eq!(
+                    res.more_info.unwrap().neval,
+                    res_par.more_info.unwrap().neval
+                );
+            }
+        }
+    }
+    #[test]
+    fn std_threads_backend() {
+        let epsrel = 0.0;
+        let epsabs = 1.0e-10;
+
+        for key in GaussKronrodRule::ALL {
+            let rayon = Qag {
+                key,
+                limit: 10000,
+                number_of_thread: 8,
+                more_info: true,
+                limit_behavior: LimitBehavior::ReturnBest,
+                ..Default::default()
+            };
+            for number_of_thread in [0, 8] {
+                let std_threads = Qag {
+                    number_of_thread,
+                    backend: Backend::StdThreads,
+                    ..rayon.clone()
+                };
+                for test in battery() {
+                    let res = rayon.integrate(&test.f, test.a, test.b, epsabs, epsrel);
+                    let res_std = std_threads.integrate(&test.f, test.a, test.b, epsabs, epsrel);
+                    let (res, res_std) = match (res, res_std) {
+                        (Ok(res), Ok(res_std)) => (res, res_std),
+                        (res, res_std) => {
+                            assert_eq!(
+                                res.err(),
+                                res_std.err(),
+                                "{} with key {:?}",
+                                test.name,
+                                key
+                            );
+                            continue;
+                        }
+                    };
+                    assert_eq!(
+                        res.result, res_std.result,
+                        "{} with key {:?}",
+                        test.name, key
+                    );
+                    assert_eq!(res.abserr, res_std.abserr);
+                    assert_eq!(res.failure, res_std.failure);
+                    assert_eq!(
+                        res.more_info.unwrap().neval,
+                        res_std.more_info.unwrap().neval
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn roundoff_warmup() {
+        let a = 0.0;
+        let b = 1.0;
+        let epsrel = 0.0;
+        let epsabs = 1.0e-3;
+        let limit = 5000;
+        let key = GaussKronrodRule::G10K21;
+
+        let qag = Qag {
+            key,
+            limit,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![1.0 / (x - 1.0 / 3.0).powi(2)]),
+        };
+
+        let res = qag.integrate(&f, a, b, epsabs, epsrel);
+        assert_eq!(res.unwrap_err(), QagError::BadTolerance);
+
+        let qag = Qag {
+            roundoff_warmup: 1000,
+            ..qag
+        };
+        let res = qag.integrate(&f, a, b, epsabs, epsrel);
+        assert_eq!(res.unwrap_err(), QagError::BadFunction);
+    }
+    #[test]
+    fn undefined_at_zero() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| {
+                assert!(x != 0.0, "integrand evaluated at 0");
+                array![1.0 / (x * x), 1.0 / (x * x * x)]
+            }),
+        };
+
+        let res = qag.integrate(&f, 1.0, 2.0, 1.0e-12, 0.0).unwrap();
+        assert!((res.result[0] - 0.5).abs() < 1.0e-12);
+        assert!((res.result[1] - 0.375).abs() < 1.0e-12);
+
+        let res = qag.integrate(&f, 1.0, f64::INFINITY, 1.0e-12, 0.0).unwrap();
+        assert!((res.result[0] - 1.0).abs() < 1.0e-12);
+        assert!((res.result[1] - 0.5).abs() < 1.0e-12);
+    }
+    #[test]
+    fn errors_only() {
+        let qag = Qag {
+            limit: 10000,
+            errors_only: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.powf(-0.5)]),
+        };
+
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        assert!(res.more_info.is_none());
+        let interval_errors = res.interval_errors.unwrap();
+        assert_eq!(interval_errors.first().unwrap().0, 0.0);
+        assert_eq!(interval_errors.last().unwrap().1, 1.0);
+        for w in interval_errors.windows(2) {
+            assert_eq!(w[0].1, w[1].0);
+        }
+        let err: f64 = interval_errors.iter().map(|&(_, _, err)| err).sum();
+        assert!((err - res.truncation_err).abs() <= 1.0e-3 * err);
+
+        let qag_more_info = Qag {
+            more_info: true,
+            ..qag.clone()
+        };
+        let res_more_info = qag_more_info.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        assert_eq!(
+            res_more_info.more_info.unwrap().heap.len(),
+            interval_errors.len()
+        );
+        assert_eq!(res_more_info.interval_errors.unwrap(), interval_errors);
+
+        let res = Qag {
+            errors_only: false,
+            ..qag.clone()
+        }
+        .integrate(&f, 0.0, 1.0, 1.0e-10, 0.0)
+        .unwrap();
+        assert!(res.interval_errors.is_none());
+
+        let res = qag.integrate(&f, 1.0, 2.0, 1.0e-6, 0.0).unwrap();
+        assert_eq!(
+            res.interval_errors.unwrap(),
+            vec![(1.0, 2.0, res.truncation_err)]
+        );
+    }
+    #[test]
+    fn reparam() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            limit_behavior: LimitBehavior::ReturnBest,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![1.0 / (1.0 + ((x - 1.0e-2) / 1.0e-3).powi(2))]),
+        };
+        let (a, b) = (0.0, 1.0e12);
+        let correct_result = 0.0030419240010986302;
+
+        let res_linear = qag.integrate(&f, a, b, 1.0e-14, 1.0e-10).unwrap();
+        let res_log = Qag {
+            reparam: Reparam::Log,
+            ..qag.clone()
+        }
+        .integrate(&f, 1.0e-300, b, 1.0e-14, 1.0e-10)
+        .unwrap();
+        assert!(res_log.failure.is_none());
+        assert!((res_log.result[0] - correct_result).abs() < 1.0e-12);
+        // In x the first 10-21 points rule misses the spike and reports convergence.
+        assert!((res_linear.result[0] - correct_result).abs() > 0.5 * correct_result);
+
+        let custom = Reparam::Custom {
+            forward: Arc::new(f64::ln),
+            inverse: Arc::new(f64::exp),
+            jacobian: Arc::new(f64::exp),
+        };
+        let res_custom = Qag {
+            reparam: custom,
+            ..qag.clone()
+        }
+        .integrate(&f, 1.0e-300, b, 1.0e-14, 1.0e-10)
+        .unwrap();
+        assert_eq!(res_custom.result, res_log.result);
+
+        let res = Qag {
+            reparam: Reparam::Log,
+            ..qag
+        }
+        .integrate(&f, a, b, 1.0e-14, 1.0e-10);
+        assert_eq!(res.unwrap_err(), QagError::InvalidReparam);
+    }
+
+    #[test]
+    fn norm_weights() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        // The second component is a narrow peak, negligible in the unweighted norm.
+        let f = FnVec {
+            components: Arc::new(|x: f64| {
+                array![x * x, 1.0e-12 / (1.0e-4 + (x - 0.3) * (x - 0.3))]
+            }),
+        };
+        let correct_result = [1.0 / 3.0, 1.0e-10 * (70.0_f64.atan() + 30.0_f64.atan())];
+        let rel_err = |res: &QagIntegrationResult, k: usize| {
+            (res.result[k] - correct_result[k]).abs() / correct_result[k]
+        };
+
+        let res = qag.integrate(&f, 0.0, 1.0, 0.0, 1.0e-8).unwrap();
+        assert!(rel_err(&res, 0) < 1.0e-8);
+        assert!(rel_err(&res, 1) > 1.0e-3);
+
+        let qag_weighted = Qag {
+            norm_weights: Some(vec![1.0, 1.0e24]),
+            ..qag.clone()
+        };
+        let res = qag_weighted.integrate(&f, 0.0, 1.0, 0.0, 1.0e-8).unwrap();
+        assert!(rel_err(&res, 0) < 1.0e-8);
+        assert!(rel_err(&res, 1) < 1.0e-8);
+        let res_reversed = qag_weighted.integrate(&f, 1.0, 0.0, 0.0, 1.0e-8).unwrap();
+        assert_eq!(res_reversed.result, -res.result);
+
+        let res = Qag {
+            norm_weights: Some(vec![1.0, 1.0, 1.0]),
+            ..qag.clone()
+        }
+        .integrate(&f, 0.0, 1.0, 0.0, 1.0e-8);
+        assert_eq!(res.unwrap_err(), QagError::InvalidWeights);
+        let res = Qag {
+            norm_weights: Some(vec![1.0, 0.0]),
+            ..qag
+        }
+        .integrate(&f, 0.0, 1.0, 0.0, 1.0e-8);
+        assert_eq!(res.unwrap_err(), QagError::InvalidWeights);
+    }
+
+    #[test]
+    fn zero_integral_epsrel() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        // Odd integrands, whose integrals vanish by symmetry.
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin() * (1.0 + x * x).recip(), x.powi(3)]),
+        };
+        let res = qag.integrate(&f, -1.0, 1.0, 0.0, 1.0e-8).unwrap();
+        assert!(res.result.iter().all(|x| x.abs() < 1.0e-14));
+        assert!(res.abserr <= 1.0e-8);
+
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x.powi(3) * (-x * x).exp()]),
+        };
+        let res = qag
+            .integrate(&g, f64::NEG_INFINITY, f64::INFINITY, 0.0, 1.0e-8)
+            .unwrap();
+        assert!(res.result[0].abs() < 1.0e-14);
+
+        // The relative tolerance still applies to non-vanishing integrals.
+        let h = FnVec {
+            components: Arc::new(|x: f64| array![x.exp()]),
+        };
+        let res = qag.integrate(&h, -1.0, 1.0, 0.0, 1.0e-8).unwrap();
+        assert!(res.abserr <= 1.0e-8 * res.result[0]);
+    }
+
+    #[test]
+    fn difficulty() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        for test in battery() {
+            let res = qag
+                .integrate(&test.f, test.a, test.b, 1.0e-10, 0.0)
+                .unwrap();
+            let expected = match test.name {
+                "genz_discontinuous" => Difficulty::Discontinuous,
+                "corner_singularity" | "log_singularity" => Difficulty::EndpointSingular,
+                "oscillatory" => Difficulty::Oscillatory,
+                _ => Difficulty::Smooth,
+            };
+            assert_eq!(res.difficulty, Some(expected), "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn point_values() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 4,
+            more_info: true,
+            record_point_values: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(-x).exp(), 1.0 / (1.0 + x * x)]),
+        };
+
+        for (a, b) in [(0.0, 1.0), (0.0, f64::INFINITY)] {
+            let res = qag.integrate(&f, a, b, 1.0e-10, 0.0).unwrap();
+            let table = res.point_values.unwrap();
+            assert_eq!(table.len(), res.more_info.unwrap().neval);
+            assert!(table.windows(2).all(|w| w[0].0 < w[1].0));
+            for (x, value) in table {
+                assert!(a < x && x < b);
+                assert_eq!(value, (f.components)(x).to_vec());
+            }
+        }
+
+        let res = Qag {
+            record_point_values: false,
+            ..qag
+        }
+        .integrate(&f, 0.0, 1.0, 1.0e-10, 0.0)
+        .unwrap();
+        assert!(res.point_values.is_none());
+    }
+
+    #[test]
+    fn pipeline_entry_points() {
+        type EntryPoint =
+            Box<dyn Fn(&Qag, &TestFunction) -> Result<QagIntegrationResult, QagError>>;
+        let epsrel = 1.0e-8;
+        #[allow(unused_mut)]
+        let mut entry_points: Vec<(&str, EntryPoint)> = vec![
+            (
+                "integrate",
+                Box::new(move |qag, test| qag.integrate(&test.f, test.a, test.b, 0.0, epsrel)),
+            ),
+            (
+                "integrate_with_resabs",
+                Box::new(move |qag, test| {
+                    let (res, _) =
+                        qag.integrate_with_resabs(&test.f, test.a, test.b, 0.0, epsrel)?;
+                    Ok(res)
+                }),
+            ),
+            (
+                "integrate_with_gauss",
+                Box::new(move |qag, test| {
+                    let (res, _) =
+                        qag.integrate_with_gauss(&test.f, test.a, test.b, 0.0, epsrel)?;
+                    Ok(res)
+                }),
+            ),
+            (
+                "integrate_with_reliability",
+                Box::new(move |qag, test| {
+                    let (res, _) =
+                        qag.integrate_with_reliability(&test.f, test.a, test.b, 0.0, epsrel)?;
+                    Ok(res)
+                }),
+            ),
+            (
+                "Qags",
+                Box::new(move |qag, test| {
+                    Qags::new(qag.clone()).integrate(&test.f, test.a, test.b, 0.0, epsrel)
+                }),
+            ),
+            (
+                "Quad",
+                Box::new(move |qag, test| {
+                    Quad::new(qag.clone()).integrate(&test.f, test.a, test.b, 0.0, epsrel)
+                }),
+            ),
+        ];
+        #[cfg(feature = "smallvec")]
+        entry_points.push((
+            "integrate_small",
+            Box::new(move |qag, test| {
+                let f = |x: f64| -> crate::qag_smallvec::SmallArray {
+                    (test.f.components)(x).iter().copied().collect()
+                };
+                qag.integrate_small(f, test.a, test.b, 0.0, epsrel)
+            }),
+        ));
+
+        for (qag, test) in transform_cases() {
+            for (name, integrate) in &entry_points {
+                let res = integrate(&qag, &test)
+                    .unwrap_or_else(|error| panic!("{} on {}: {}", name, test.name, error));
+                assert_transform_case(&qag, &test, &res);
+            }
+
+            // The reference is compared in the norm of the original integrand, regardless of the
+            // norm weights.
+            let reference = test.correct_result.to_vec();
+            let res = qag
+                .integrate_to_reference(&test.f, test.a, test.b, &reference, epsrel)
+                .unwrap();
+            let err = norm_ar(&(&res.result - &test.correct_result));
+            assert!(
+                err <= epsrel * norm_ar(&test.correct_result),
+                "{}",
+                test.name
+            );
+            assert_transform_output(&qag, &test, &res);
+        }
+    }
 }
@@ -0,0 +1,157 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qag_workspace::QagWorkspace;
+use ndarray::Array1;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+impl Qag {
+    /// Same as [integrate](Qag::integrate), for an integrand whose evaluation has to be awaited,
+    /// e.g. a query to a device.
+    ///
+    /// The synchronous integration is replayed with the values evaluated so far: as soon as a
+    /// batch of sub-intervals needs new abscissae it is stopped, the missing values are awaited
+    /// one after the other, and the integration is replayed from the start. Every abscissa is
+    /// evaluated once and the result is the same as [integrate](Qag::integrate), at the price of
+    /// repeating the bookkeeping once per batch, which is negligible for expensive integrands.
+    /// The evaluations are sequential: awaiting the futures of a batch together, e.g. with
+    /// `join_all`, would be needed to overlap them.
+    pub async fn integrate_async<F, Fut>(
+        &self,
+        f: F,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError>
+    where
+        F: Fn(f64) -> Fut,
+        Fut: Future<Output = Vec<f64>>,
+    {
+        let values: Mutex<HashMap<u64, Array1<f64>>> = Mutex::new(HashMap::new());
+        let missing = Mutex::new(vec![]);
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let replay = FnVec {
+            components: Arc::new(|x: f64| {
+                let values = values.lock().unwrap();
+                if let Some(value) = values.get(&x.to_bits()) {
+                    return value.clone();
+                }
+                missing.lock().unwrap().push(x);
+                interrupt.store(true, Ordering::Relaxed);
+                // Placeholder, discarded together with the interrupted integration.
+                Array1::zeros(values.values().next().map_or(0, |value| value.len()))
+            }),
+        };
+        let mut ws = QagWorkspace::new();
+        ws.interrupt = Some(interrupt.clone());
+
+        loop {
+            let res = self.integrate_with_workspace(&mut ws, &replay, a, b, epsabs, epsrel);
+            let missing = std::mem::take(&mut *missing.lock().unwrap());
+            if missing.is_empty() {
+                return res;
+            }
+            for x in missing {
+                if !values.lock().unwrap().contains_key(&x.to_bits()) {
+                    let value = Array1::from(f(x).await);
+                    values.lock().unwrap().insert(x.to_bits(), value);
+                }
+            }
+            interrupt.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    /// Waker unparking the thread blocked in [block_on].
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor, polling 'fut' on the current thread until it is ready.
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = Box::pin(fut);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// Future pending once, as an evaluation handing control back to the executor.
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn same_as_sync() {
+        let qag = Qag {
+            limit: 10000,
+            points: vec![0.3],
+            more_info: true,
+            ..Default::default()
+        };
+        let evaluations = AtomicUsize::new(0);
+        let f = FnVec {
+            components: Arc::new(|x: f64| {
+                evaluations.fetch_add(1, Ordering::Relaxed);
+                array![x.sqrt() * (-x).exp(), (-x).exp()]
+            }),
+        };
+        let f_async = |x: f64| {
+            evaluations.fetch_add(1, Ordering::Relaxed);
+            async move {
+                YieldNow(false).await;
+                vec![x.sqrt() * (-x).exp(), (-x).exp()]
+            }
+        };
+
+        for (a, b) in [(0.0, 2.0), (2.0, 0.0), (0.0, f64::INFINITY)] {
+            evaluations.store(0, Ordering::Relaxed);
+            let res = qag.integrate(&f, a, b, 1.0e-12, 0.0).unwrap();
+            let sync_evaluations = evaluations.swap(0, Ordering::Relaxed);
+            let res_async = block_on(qag.integrate_async(f_async, a, b, 1.0e-12, 0.0)).unwrap();
+            assert_eq!(res_async.result, res.result);
+            assert_eq!(res_async.abserr, res.abserr);
+            assert_eq!(
+                res_async.more_info.unwrap().neval,
+                res.more_info.unwrap().neval
+            );
+            assert_eq!(evaluations.load(Ordering::Relaxed), sync_evaluations);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use ndarray::Array1;
+use std::sync::Arc;
+
+impl Qag {
+    /// Projections of f onto the functions of a basis, the integrals of f(x) phi_j(x) over
+    /// ('a', 'b') for every phi_j in 'basis'.
+    ///
+    /// f and all the basis functions are evaluated at the same abscissae, and the products are
+    /// integrated as a single vector-valued function on one adaptive mesh, refined until all of
+    /// them are resolved. This costs one evaluation of f per abscissa instead of one per basis
+    /// function. As with [try_integrate](Qag::try_integrate), the
+    /// [failure](crate::qag_integration_result::QagIntegrationResult::failure) of an
+    /// integration returning the best estimate is reported as an error.
+    pub fn integrate_against_basis<F>(
+        &self,
+        f: F,
+        basis: &[Box<dyn Fn(f64) -> f64 + Send + Sync>],
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<Vec<f64>, QagError>
+    where
+        F: Fn(f64) -> f64 + Sync + Send,
+    {
+        let products = FnVec {
+            components: Arc::new(|x: f64| {
+                let value = f(x);
+                Array1::from_iter(basis.iter().map(|phi| value * phi(x)))
+            }),
+        };
+        let res = self.integrate(&products, a, b, epsabs, epsrel)?;
+        match res.failure {
+            Some(failure) => Err(failure),
+            None => Ok(res.result.to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qag::Qag;
+    use std::f64::consts::E;
+
+    #[test]
+    fn legendre() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let basis: Vec<Box<dyn Fn(f64) -> f64 + Send + Sync>> = vec![
+            Box::new(|_: f64| 1.0),
+            Box::new(|x: f64| x),
+            Box::new(|x: f64| 0.5 * (3.0 * x * x - 1.0)),
+            Box::new(|x: f64| 0.5 * (5.0 * x * x * x - 3.0 * x)),
+        ];
+        let correct = [E - 1.0 / E, 2.0 / E, E - 7.0 / E, 37.0 / E - 5.0 * E];
+
+        let projections = qag
+            .integrate_against_basis(|x: f64| x.exp(), &basis, -1.0, 1.0, 1.0e-13, 0.0)
+            .unwrap();
+        assert_eq!(projections.len(), basis.len());
+        for (projection, correct) in projections.iter().zip(correct) {
+            assert!((projection - correct).abs() < 1.0e-13);
+        }
+
+        // The polynomials are orthogonal, with norms 2 / (2n + 1).
+        for (n, phi) in basis.iter().enumerate() {
+            let projections = qag
+                .integrate_against_basis(phi, &basis, -1.0, 1.0, 1.0e-13, 0.0)
+                .unwrap();
+            for (m, projection) in projections.into_iter().enumerate() {
+                let correct = if m == n {
+                    2.0 / (2 * n + 1) as f64
+                } else {
+                    0.0
+                };
+                assert!((projection - correct).abs() < 1.0e-13);
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qawo::{weight_moments, OscillatoryWeight};
+use std::f64::consts::{FRAC_PI_4, PI};
+
+/// Bessel function of the first kind of order zero J0(x).
+///
+/// The power series is used for |x| <= 12 and the Hankel asymptotic expansion otherwise, with an
+/// absolute accuracy of about 1e-12.
+pub fn bessel_j0(x: f64) -> f64 {
+    let x = x.abs();
+    if x <= 12.0 {
+        let y = 0.25 * x * x;
+        let mut term = 1.0;
+        let mut sum = 1.0;
+        let mut k = 0.0;
+        loop {
+            k += 1.0;
+            term *= -y / (k * k);
+            sum += term;
+            if k > 2.0 && term.abs() < 1.0e-17 * sum.abs() {
+                return sum;
+            }
+        }
+    }
+
+    let mut term: f64 = 1.0;
+    let mut p = 1.0;
+    let mut q = 0.0;
+    let mut k = 0;
+    loop {
+        k += 1;
+        let next = term * -((2 * k - 1) as f64).powi(2) / (8.0 * k as f64 * x);
+        if next.abs() >= term.abs() || next.abs() < 1.0e-17 {
+            break;
+        }
+        term = next;
+        match k % 4 {
+            0 => p += term,
+            1 => q += term,
+            2 => p -= term,
+            _ => q -= term,
+        }
+    }
+    let chi = x - FRAC_PI_4;
+    (2.0 / (PI * x)).sqrt() * (p * chi.cos() - q * chi.sin())
+}
+
+/// Oscillatory weight J0('omega' x), with J0 computed by [bessel_j0].
+///
+/// Its modified Chebyshev moments have no closed form, and are computed by [weight_moments].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BesselJ0 {
+    pub omega: f64,
+}
+
+impl OscillatoryWeight for BesselJ0 {
+    fn value(&self, x: f64) -> f64 {
+        bessel_j0(self.omega * x)
+    }
+
+    fn omega(&self) -> f64 {
+        self.omega
+    }
+
+    fn moments(&self, center: f64, half_length: f64) -> Vec<f64> {
+        let omega = self.omega;
+        weight_moments(
+            |t| bessel_j0(omega * (center + half_length * t)),
+            omega * half_length,
+        )
+    }
+}
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function against the oscillatory weight
+    /// J0('omega' * x), with J0 computed by [bessel_j0].
+    ///
+    /// The weight is the [BesselJ0] of [integrate_oscillatory](Qag::integrate_oscillatory), so
+    /// on the sub-intervals with many oscillations f is interpolated at the Chebyshev points and
+    /// the number of evaluations does not grow with 'omega'.
+    pub fn integrate_bessel_j0(
+        &self,
+        g: &FnVec,
+        omega: f64,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        self.integrate_oscillatory(&BesselJ0 { omega }, g, a, b, epsabs, epsrel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::{GaussKronrodRule, Qag};
+    use crate::qag_bessel::bessel_j0;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn j0() {
+        let tabulated = [
+            (0.0, 1.0),
+            (0.5, 0.9384698072408129),
+            (5.0, -0.1775967713143383),
+            (12.0, 0.04768931079683353),
+            (20.0, 0.16702466434058315),
+            (100.0, 0.019985850304223122),
+        ];
+        for (x, j0) in tabulated {
+            assert!((bessel_j0(x) - j0).abs() < 1.0e-12);
+            assert_eq!(bessel_j0(-x), bessel_j0(x));
+        }
+    }
+
+    #[test]
+    fn integrate_j0() {
+        let qag = Qag {
+            key: GaussKronrodRule::G30K61,
+            limit: 10000,
+            ..Default::default()
+        };
+        let g = FnVec {
+            components: Arc::new(|_x: f64| array![1.0]),
+        };
+        let correct_result = 0.10670113039567368;
+
+        let res = qag
+            .integrate_bessel_j0(&g, 10.0, 0.0, 1.0, 1.0e-12, 0.0)
+            .unwrap();
+        assert!((res.result[0] - correct_result).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn high_frequency() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![(-x).exp(), x * x]),
+        };
+        let omega = 1000.0;
+
+        let res = qag
+            .integrate_bessel_j0(&g, omega, 0.0, 1.0, 1.0e-10, 0.0)
+            .unwrap();
+        let res_weighted = qag
+            .integrate_weighted(&g, |x: f64| bessel_j0(omega * x), 0.0, 1.0, 1.0e-10, 0.0)
+            .unwrap();
+        for (x, correct) in res.result.iter().zip(&res_weighted.result) {
+            assert!((x - correct).abs() < 1.0e-10);
+        }
+        // The oscillations are integrated through the moments, not resolved by the bisection.
+        let neval = res.more_info.unwrap().neval;
+        assert!(10 * neval < res_weighted.more_info.unwrap().neval);
+    }
+}
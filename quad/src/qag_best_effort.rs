@@ -0,0 +1,104 @@
+use crate::constants::{norm_ar, FnVec, EPMACH};
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+
+/// Relative tolerance of the first integration of
+/// [integrate_best_effort](Qag::integrate_best_effort).
+pub const BEST_EFFORT_EPSREL_START: f64 = 1.0e-6;
+/// Factor applied to the relative tolerance at every step of
+/// [integrate_best_effort](Qag::integrate_best_effort).
+pub const BEST_EFFORT_EPSREL_FACTOR: f64 = 1.0e-2;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function over the finite interval ('a', 'b') as
+    /// accurate as the roundoff allows, without choosing a tolerance.
+    ///
+    /// The integral is computed with epsrel = [BEST_EFFORT_EPSREL_START], then epsrel is
+    /// multiplied by [BEST_EFFORT_EPSREL_FACTOR] at every step, down to 50 * [EPMACH]. Every
+    /// step is warm-started from the mesh of the previous one with
+    /// [integrate_on_mesh](Qag::integrate_on_mesh), so only the new bisections cost. It stops
+    /// when the norm of the change of the result is within 10 * [EPMACH] times its norm, when
+    /// a step fails (e.g. with [BadTolerance](QagError::BadTolerance), as the roundoff
+    /// dominates) or at the smallest epsrel, returning the last successful result.
+    ///
+    /// Together with the result, it returns the achieved relative precision, the larger of
+    /// 'abserr' and the last change, over the norm of the result. Only an error of the first
+    /// step is returned, and infinite bounds are rejected with [Invalid](QagError::Invalid). If
+    /// b < a the bounds are swapped and the result is negated.
+    pub fn integrate_best_effort(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+    ) -> Result<(QagIntegrationResult, f64), QagError> {
+        if b < a {
+            let (mut res, precision) = self.integrate_best_effort(fun, b, a)?;
+            res.result.mapv_inplace(|x| -x);
+            return Ok((res, precision));
+        }
+        if !(a.is_finite() && b.is_finite()) {
+            return Err(QagError::Invalid);
+        }
+        let qag = Qag {
+            more_info: true,
+            ..self.clone()
+        };
+        let min_epsrel = 50.0 * EPMACH;
+        let mut epsrel = BEST_EFFORT_EPSREL_START;
+        let mut res = qag.integrate(fun, a, b, 0.0, epsrel)?;
+        let mut change = f64::INFINITY;
+        while epsrel > min_epsrel {
+            epsrel = (epsrel * BEST_EFFORT_EPSREL_FACTOR).max(min_epsrel);
+            let mesh = res.more_info.as_ref().unwrap().mesh();
+            let next = match qag.integrate_on_mesh(fun, &mesh, true, 0.0, epsrel) {
+                Ok(next) if next.failure.is_none() => next,
+                _ => break,
+            };
+            change = norm_ar(&(&next.result - &res.result));
+            res = next;
+            if change <= 10.0 * EPMACH * norm_ar(&res.result) {
+                break;
+            }
+        }
+
+        let precision = res.abserr.max(change) / norm_ar(&res.result);
+        if !self.more_info {
+            res.more_info = None;
+        }
+        Ok((res, precision))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::{FnVec, EPMACH};
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn best_effort() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.exp(), (10.0 * x).cos()]),
+        };
+        let correct_result = [1.0_f64.exp() - 1.0, 10.0_f64.sin() / 10.0];
+
+        let (res, precision) = qag.integrate_best_effort(&f, 0.0, 1.0).unwrap();
+        assert!(precision < 1.0e-13);
+        assert!(res.more_info.is_none());
+        for (result, correct) in res.result.iter().zip(correct_result) {
+            assert!((result - correct).abs() < 100.0 * EPMACH);
+        }
+
+        let (res_reversed, _) = qag.integrate_best_effort(&f, 1.0, 0.0).unwrap();
+        assert_eq!(res_reversed.result, -&res.result);
+        let res = qag.integrate_best_effort(&f, 0.0, f64::INFINITY);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
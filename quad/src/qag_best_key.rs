@@ -0,0 +1,100 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::{GaussKronrodRule, Qag};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+impl Qag {
+    /// Calibration helper which integrates with every [key](Qag::key) and returns (key, neval)
+    /// of the one that converged with the fewest evaluations of the integrand.
+    ///
+    /// It costs up to 6 times a single [integrate](Qag::integrate), so it is meant to be run
+    /// once on a representative integrand before a long batch of similar ones. Ties are won by
+    /// the rule with fewer points. If no key converged, the error of the last one is returned.
+    pub fn best_key(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(GaussKronrodRule, usize), QagError> {
+        let f = &fun.components;
+        let neval = AtomicUsize::new(0);
+        let counted = FnVec {
+            components: Arc::new(|x: f64| {
+                neval.fetch_add(1, Ordering::Relaxed);
+                f(x)
+            }),
+        };
+
+        let mut best: Option<(GaussKronrodRule, usize)> = None;
+        let mut error = QagError::Invalid;
+        for key in GaussKronrodRule::ALL {
+            let qag = Qag {
+                key,
+                ..self.clone()
+            };
+            neval.store(0, Ordering::Relaxed);
+            match qag.integrate(&counted, a, b, epsabs, epsrel) {
+                Ok(_) => {
+                    let n = neval.load(Ordering::Relaxed);
+                    match best {
+                        Some((_, best_n)) if best_n <= n => {}
+                        _ => best = Some((key, n)),
+                    }
+                }
+                Err(err) => error = err,
+            }
+        }
+        best.ok_or(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::{GaussKronrodRule, Qag};
+    use ndarray::array;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn best_key() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let evaluations = AtomicUsize::new(0);
+        let f = FnVec {
+            components: Arc::new(|x: f64| {
+                evaluations.fetch_add(1, Ordering::Relaxed);
+                array![(10.0 * x).cos() * (-x * x).exp()]
+            }),
+        };
+
+        let (key, neval) = qag.best_key(&f, -3.0, 3.0, 1.0e-12, 0.0).unwrap();
+        let mut nevals = vec![];
+        for k in GaussKronrodRule::ALL {
+            evaluations.store(0, Ordering::Relaxed);
+            let res = Qag {
+                key: k,
+                ..qag.clone()
+            }
+            .integrate(&f, -3.0, 3.0, 1.0e-12, 0.0)
+            .unwrap();
+            assert!(res.abserr <= 1.0e-12);
+            nevals.push(evaluations.load(Ordering::Relaxed));
+        }
+        assert_eq!(neval, *nevals.iter().min().unwrap());
+        assert_eq!(neval, nevals[i32::from(key) as usize - 1]);
+
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5)]),
+        };
+        let qag_short = Qag { limit: 1, ..qag };
+        let res = qag_short.best_key(&g, 0.0, 1.0, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::MaxIteration);
+    }
+}
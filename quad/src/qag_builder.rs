@@ -0,0 +1,203 @@
+use crate::qag::{
+    AutoBatch, Backend, GaussKronrodRule, LimitBehavior, Qag, Reparam, ToleranceLogic,
+};
+use std::sync::Arc;
+
+/// Builder of a [Qag], returned by [builder](Qag::builder).
+///
+/// It starts from the [default](Qag::default) of [Qag], and every setter replaces the field of
+/// the same name.
+#[derive(Clone, Default)]
+pub struct QagBuilder {
+    qag: Qag,
+}
+
+impl QagBuilder {
+    /// Gauss-Kronrod rule, either as a [GaussKronrodRule] or as the integer key from 1 to 6.
+    pub fn key(mut self, key: impl Into<GaussKronrodRule>) -> Self {
+        self.qag.key = key.into();
+        self
+    }
+
+    /// Maximum number of sub-intervals.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.qag.limit = limit;
+        self
+    }
+
+    /// Adds a point where the interval is split from the start.
+    pub fn point(mut self, x: f64) -> Self {
+        self.qag.points.push(x);
+        self
+    }
+
+    /// Replaces the points where the interval is split from the start.
+    pub fn points(mut self, points: Vec<f64>) -> Self {
+        self.qag.points = points;
+        self
+    }
+
+    /// Number of threads, where 0 uses the available parallelism.
+    pub fn number_of_thread(mut self, number_of_thread: usize) -> Self {
+        self.qag.number_of_thread = number_of_thread;
+        self
+    }
+
+    /// Whether [MoreInfo](crate::qag_integration_result::MoreInfo) is returned.
+    pub fn more_info(mut self, more_info: bool) -> Self {
+        self.qag.more_info = more_info;
+        self
+    }
+
+    /// Behaviour when the maximum number of sub-intervals is reached.
+    pub fn limit_behavior(mut self, limit_behavior: LimitBehavior) -> Self {
+        self.qag.limit_behavior = limit_behavior;
+        self
+    }
+
+    /// Number of sub-intervals after which the roundoff is counted.
+    pub fn roundoff_warmup(mut self, roundoff_warmup: usize) -> Self {
+        self.qag.roundoff_warmup = roundoff_warmup;
+        self
+    }
+
+    /// Whether the refinement trace is returned.
+    pub fn record_refinement_trace(mut self, record_refinement_trace: bool) -> Self {
+        self.qag.record_refinement_trace = record_refinement_trace;
+        self
+    }
+
+    /// Maximum number of bisections leading to a sub-interval.
+    pub fn max_depth(mut self, max_depth: u32) -> Self {
+        self.qag.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Automatic tuning of the number of sub-intervals bisected at once.
+    pub fn auto_batch(mut self, auto_batch: AutoBatch) -> Self {
+        self.qag.auto_batch = Some(auto_batch);
+        self
+    }
+
+    /// Maximum number of sub-intervals bisected at once.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.qag.batch_size = batch_size;
+        self
+    }
+
+    /// Adds a point where the integrand has a removable singularity.
+    pub fn removable_singularity(mut self, x: f64) -> Self {
+        self.qag.removable_singularities.push(x);
+        self
+    }
+
+    /// Replaces the points where the integrand has a removable singularity.
+    pub fn removable_singularities(mut self, removable_singularities: Vec<f64>) -> Self {
+        self.qag.removable_singularities = removable_singularities;
+        self
+    }
+
+    /// Point where a sub-interval (a, b) is bisected.
+    pub fn split_fn(mut self, split_fn: impl Fn(f64, f64) -> f64 + Send + Sync + 'static) -> Self {
+        self.qag.split_fn = Some(Arc::new(split_fn));
+        self
+    }
+
+    /// How 'epsabs' and 'epsrel' are combined in the convergence test.
+    pub fn tolerance_logic(mut self, tolerance_logic: ToleranceLogic) -> Self {
+        self.qag.tolerance_logic = tolerance_logic;
+        self
+    }
+
+    /// Whether the final sub-intervals with their errors are returned.
+    pub fn errors_only(mut self, errors_only: bool) -> Self {
+        self.qag.errors_only = errors_only;
+        self
+    }
+
+    /// Integration variable in which the subdivision is performed.
+    pub fn reparam(mut self, reparam: Reparam) -> Self {
+        self.qag.reparam = reparam;
+        self
+    }
+
+    /// Weights of the components in the norm of the convergence test.
+    pub fn norm_weights(mut self, norm_weights: Vec<f64>) -> Self {
+        self.qag.norm_weights = Some(norm_weights);
+        self
+    }
+
+    /// Implementation of the threads bisecting the sub-intervals.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.qag.backend = backend;
+        self
+    }
+
+    /// Whether the evaluations of the integrand are returned.
+    pub fn record_point_values(mut self, record_point_values: bool) -> Self {
+        self.qag.record_point_values = record_point_values;
+        self
+    }
+
+    pub fn build(self) -> Qag {
+        self.qag
+    }
+}
+
+impl Qag {
+    /// [QagBuilder] starting from the [default](Qag::default).
+    pub fn builder() -> QagBuilder {
+        QagBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::{GaussKronrodRule, LimitBehavior, Qag};
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn builder() {
+        let qag = Qag::builder().build();
+        assert_eq!(qag.key, GaussKronrodRule::G10K21);
+        assert_eq!(qag.limit, 50);
+        assert!(qag.points.is_empty());
+        assert_eq!(qag.number_of_thread, 1);
+        assert!(!qag.more_info);
+
+        let qag = Qag::builder()
+            .key(6)
+            .limit(1000)
+            .points(vec![0.25])
+            .point(0.5)
+            .more_info(true)
+            .build();
+        assert_eq!(qag.key, GaussKronrodRule::G30K61);
+        assert_eq!(qag.limit, 1000);
+        assert_eq!(qag.points, vec![0.25, 0.5]);
+
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(x - 0.5).abs()]),
+        };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-12, 0.0).unwrap();
+        assert!((res.result[0] - 0.25).abs() < 1.0e-15);
+        assert_eq!(res.more_info.unwrap().heap.len(), 3);
+
+        let qag = Qag::builder()
+            .limit_behavior(LimitBehavior::ReturnBest)
+            .removable_singularity(0.0)
+            .max_depth(3)
+            .batch_size(4)
+            .norm_weights(vec![4.0])
+            .split_fn(|a, b| 0.25 * a + 0.75 * b)
+            .build();
+        assert_eq!(qag.limit_behavior, LimitBehavior::ReturnBest);
+        assert_eq!(qag.removable_singularities, vec![0.0]);
+        assert_eq!(qag.max_depth, Some(3));
+        assert_eq!(qag.batch_size, 4);
+        assert_eq!(qag.norm_weights, Some(vec![4.0]));
+        assert_eq!((qag.split_fn.unwrap())(0.0, 1.0), 0.75);
+    }
+}
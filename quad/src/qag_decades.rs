@@ -0,0 +1,125 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::{Qag, Reparam};
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::Array1;
+
+/// Partial integral (decade_lo, decade_hi, partial) returned by
+/// [integrate_decades](Qag::integrate_decades).
+pub type DecadePartial = (f64, f64, Vec<f64>);
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function over the finite interval ('a', 'b'),
+    /// with 0 < a, b, also returning the partial integrals over every decade [10^k, 10^(k+1)].
+    ///
+    /// The decade boundaries inside the interval are added to the [points](Qag::points), so that
+    /// every final sub-interval lies within a single decade and the partials are the sums of
+    /// their cached results. The first and the last decades are truncated at the bounds. The
+    /// partials are sorted by decade and, for b < a, negated as the total. The integration is
+    /// performed in x, ignoring [reparam](Qag::reparam).
+    pub fn integrate_decades(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Vec<DecadePartial>), QagError> {
+        if b < a {
+            let (mut res, mut partials) = self.integrate_decades(fun, b, a, epsabs, epsrel)?;
+            res.result.mapv_inplace(|x| -x);
+            for (_, _, partial) in partials.iter_mut() {
+                partial.iter_mut().for_each(|x| *x = -*x);
+            }
+            return Ok((res, partials));
+        }
+        if !(a > 0.0 && b.is_finite()) {
+            return Err(QagError::Invalid);
+        }
+
+        let decades: Vec<f64> = (a.log10().floor() as i32 + 1..=b.log10().ceil() as i32 - 1)
+            .map(|k| 10.0_f64.powi(k))
+            .filter(|&x| a < x && x < b)
+            .collect();
+        let mut points = self.points.clone();
+        points.extend_from_slice(&decades);
+        let qag = Qag {
+            points,
+            more_info: true,
+            reparam: Reparam::Linear,
+            ..self.clone()
+        };
+        let mut res = qag.integrate(fun, a, b, epsabs, epsrel)?;
+
+        let mut edges = vec![a];
+        edges.extend_from_slice(&decades);
+        edges.push(b);
+        let n = res.result.len();
+        let mut partials: Vec<Array1<f64>> = vec![Array1::zeros(n); edges.len() - 1];
+        for ((x, _), interval_result) in res.more_info.as_ref().unwrap().hash.iter() {
+            let decade = edges[1..edges.len() - 1].partition_point(|&edge| edge <= x.x);
+            partials[decade] += interval_result;
+        }
+        if !self.more_info {
+            res.more_info = None;
+        }
+        let partials = edges
+            .windows(2)
+            .zip(partials)
+            .map(|(edge, partial)| (edge[0], edge[1], partial.to_vec()))
+            .collect();
+        Ok((res, partials))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::f64::consts::LN_10;
+    use std::sync::Arc;
+
+    #[test]
+    fn decades() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![1.0 / x, (-x / 100.0).exp()]),
+        };
+
+        let (res, partials) = qag.integrate_decades(&f, 0.5, 2.0e4, 0.0, 1.0e-12).unwrap();
+        assert!(res.more_info.is_none());
+        let edges: Vec<_> = partials.iter().map(|&(lo, hi, _)| (lo, hi)).collect();
+        assert_eq!(
+            edges,
+            vec![
+                (0.5, 1.0),
+                (1.0, 10.0),
+                (10.0, 100.0),
+                (100.0, 1000.0),
+                (1000.0, 1.0e4),
+                (1.0e4, 2.0e4)
+            ]
+        );
+        assert!((partials[0].2[0] - 2.0_f64.ln()).abs() < 1.0e-10);
+        for (_, _, partial) in &partials[1..5] {
+            assert!((partial[0] - LN_10).abs() < 1.0e-10);
+        }
+        for k in 0..2 {
+            let sum: f64 = partials.iter().map(|(_, _, partial)| partial[k]).sum();
+            assert!((sum - res.result[k]).abs() < 1.0e-10);
+        }
+
+        let (res_reversed, partials_reversed) =
+            qag.integrate_decades(&f, 2.0e4, 0.5, 0.0, 1.0e-12).unwrap();
+        assert_eq!(res_reversed.result, -&res.result);
+        assert_eq!(partials_reversed[1].2[0], -partials[1].2[0]);
+
+        let res = qag.integrate_decades(&f, 0.0, 1.0, 0.0, 1.0e-12);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use ndarray::array;
+use std::sync::Arc;
+
+impl Qag {
+    /// Normalization of the unnormalized probability density 'kernel' with support ('a', 'b').
+    ///
+    /// It returns the normalizing constant Z, the integral of 'kernel' computed with
+    /// [integrate](Qag::integrate), and the normalized density x -> kernel(x) / Z. Infinite
+    /// supports are handled by the usual transformations. If Z is not positive
+    /// [Invalid](QagError::Invalid) is returned.
+    pub fn normalize_density<K>(
+        &self,
+        kernel: K,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(f64, impl Fn(f64) -> f64), QagError>
+    where
+        K: Fn(f64) -> f64 + Send + Sync,
+    {
+        let fun = FnVec {
+            components: Arc::new(|x: f64| array![kernel(x)]),
+        };
+        let z = self.integrate(&fun, a, b, epsabs, epsrel)?.result[0];
+        drop(fun);
+        if !(z > 0.0 && z.is_finite()) {
+            return Err(QagError::Invalid);
+        }
+        Ok((z, move |x: f64| kernel(x) / z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn normal_density() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let kernel = |x: f64| (-0.5 * x * x).exp();
+
+        let (z, density) = qag
+            .normalize_density(kernel, f64::NEG_INFINITY, f64::INFINITY, 1.0e-12, 0.0)
+            .unwrap();
+        assert!((z - (2.0 * PI).sqrt()).abs() < 1.0e-12);
+        assert!((density(0.0) - 1.0 / (2.0 * PI).sqrt()).abs() < 1.0e-12);
+
+        let (z, density) = qag
+            .normalize_density(|x: f64| x, 0.0, 2.0, 1.0e-12, 0.0)
+            .unwrap();
+        assert!((z - 2.0).abs() < 1.0e-12);
+        assert!((density(1.0) - 0.5).abs() < 1.0e-12);
+
+        let invalid = qag.normalize_density(|x: f64| -x * x, 0.0, 1.0, 1.0e-12, 0.0);
+        assert_eq!(invalid.err(), Some(QagError::Invalid));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::constants::{norm_ar, FnVec, EPMACH, ROUNDOFF_FACTOR};
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function up to a number of significant 'digits'.
+    ///
+    /// [integrate](Qag::integrate) is called with epsabs = 0 and epsrel = 10^(-digits), the latter
+    /// clamped to 10 * [ROUNDOFF_FACTOR] * [EPMACH], above the roundoff error of the rule, so
+    /// that more digits than the roundoff allows still converge instead of returning
+    /// [BadTolerance](QagError::BadTolerance).
+    ///
+    /// Together with the result, it returns the number of correct digits achieved, inferred from
+    /// -log10(abserr / |result|).
+    pub fn integrate_digits(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        digits: u32,
+    ) -> Result<(QagIntegrationResult, f64), QagError> {
+        // Beyond 308 digits 10^(-digits) underflows anyway, and the cast cannot overflow.
+        let epsrel = 10.0_f64
+            .powi(-(digits.min(308) as i32))
+            .max(10.0 * ROUNDOFF_FACTOR * EPMACH);
+        let res = self.integrate(fun, a, b, 0.0, epsrel)?;
+        let norm = norm_ar(&res.result);
+        let achieved = if res.abserr == 0.0 {
+            f64::INFINITY
+        } else if norm == 0.0 {
+            0.0
+        } else {
+            -(res.abserr / norm).log10()
+        };
+        Ok((res, achieved))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::{FnVec, EPMACH, ROUNDOFF_FACTOR};
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn digits() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.exp()]),
+        };
+        let correct_result = 1.0_f64.exp() - 1.0;
+
+        for digits in [3, 6, 10, 12] {
+            let (res, achieved) = qag.integrate_digits(&f, 0.0, 1.0, digits).unwrap();
+            assert!(achieved >= digits as f64);
+            assert!((res.result[0] - correct_result).abs() <= res.abserr);
+        }
+
+        // More digits than the roundoff allows are clamped.
+        let max_digits = -(10.0 * ROUNDOFF_FACTOR * EPMACH).log10();
+        for digits in [30, 400, u32::MAX] {
+            let (res, achieved) = qag.integrate_digits(&f, 0.0, 1.0, digits).unwrap();
+            assert!(achieved >= max_digits);
+            assert!(achieved < max_digits + 3.0);
+            assert!((res.result[0] - correct_result).abs() <= res.abserr);
+        }
+    }
+}
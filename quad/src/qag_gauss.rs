@@ -0,0 +1,196 @@
+use crate::constants::{FnVec, Myf64};
+use crate::errors::QagError;
+use crate::qag::{GaussKronrodRule, OutputMap, Qag};
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qag_workspace::QagWorkspace;
+use crate::qk15::qk15_quadrature_with_gauss;
+use crate::qk21::qk21_quadrature_with_gauss;
+use crate::qk31::qk31_quadrature_with_gauss;
+use crate::qk41::qk41_quadrature_with_gauss;
+use crate::qk51::qk51_quadrature_with_gauss;
+use crate::qk61::qk61_quadrature_with_gauss;
+use ndarray::Array1;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function, also returning the estimate of the lower
+    /// order Gauss rule.
+    ///
+    /// The Gauss rule is evaluated alongside the Kronrod one on every sub-interval, and the
+    /// estimates of the final partition are summed, so no further evaluation is needed. The gap
+    /// between the Kronrod result and the Gauss one is an independent indicator of the
+    /// reliability of the returned error. The integrand is transformed as in
+    /// [integrate](Qag::integrate), and the Gauss estimate refers to the original integrand.
+    pub fn integrate_with_gauss(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Array1<f64>), QagError> {
+        let core = |qag: &Qag,
+                    ws: &mut QagWorkspace,
+                    f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+                    a: f64,
+                    b: f64,
+                    epsabs: f64,
+                    epsrel: f64,
+                    map: &OutputMap| {
+            let (res, gauss) = qag.qintegrate_with_gauss(ws, f, a, b, epsabs, epsrel)?;
+            Ok((res, map.apply(&gauss)))
+        };
+        let mut ws = QagWorkspace::new();
+        let f = &*fun.components;
+        self.integrate_pipeline(
+            &mut ws,
+            f,
+            a,
+            b,
+            epsabs,
+            epsrel,
+            &OutputMap::default(),
+            &core,
+        )
+    }
+
+    /// Same as [integrate_with_gauss](Qag::integrate_with_gauss) over the finite interval
+    /// ('a', 'b'), after the transformations of the integrand.
+    fn qintegrate_with_gauss(
+        &self,
+        ws: &mut QagWorkspace,
+        f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Array1<f64>), QagError> {
+        let n = f(0.5 * (a + b)).len();
+        let gauss_cache = Mutex::new(HashMap::new());
+        let rule = |x: f64, y: f64| {
+            let (result, abserr, round_error, gauss) = match self.key {
+                GaussKronrodRule::G7K15 => qk15_quadrature_with_gauss(f, x, y),
+                GaussKronrodRule::G10K21 => qk21_quadrature_with_gauss(f, x, y),
+                GaussKronrodRule::G15K31 => qk31_quadrature_with_gauss(f, x, y),
+                GaussKronrodRule::G20K41 => qk41_quadrature_with_gauss(f, x, y),
+                GaussKronrodRule::G25K51 => qk51_quadrature_with_gauss(f, x, y),
+                GaussKronrodRule::G30K61 => qk61_quadrature_with_gauss(f, x, y),
+            };
+            gauss_cache
+                .lock()
+                .unwrap()
+                .insert((Myf64 { x }, Myf64 { x: y }), gauss);
+            (result, abserr, round_error)
+        };
+
+        let qag = Qag {
+            errors_only: true,
+            ..self.clone()
+        };
+        let mut res = qag.qintegrate_rule(ws, n, &rule, a, b, epsabs, epsrel, None)?;
+
+        let gauss_cache = gauss_cache.into_inner().unwrap();
+        let mut gauss = Array1::<f64>::zeros(n);
+        for &(x, y, _) in res.interval_errors.as_ref().unwrap() {
+            match gauss_cache.get(&(Myf64 { x }, Myf64 { x: y })) {
+                Some(interval_gauss) => gauss += interval_gauss,
+                None => return Err(QagError::MissingInterval { a: x, b: y }),
+            }
+        }
+        if !self.errors_only {
+            res.interval_errors = None;
+        }
+        Ok((res, gauss))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::{GaussKronrodRule, Qag, Reparam};
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn gauss() {
+        let a = 0.0;
+        let b = 10.0;
+        let epsrel = 0.0;
+        let epsabs = 1.0e-8;
+        let limit = 10000;
+        let correct_result = [1.0 - 10.0_f64.cos(), 10.0_f64.sin()];
+
+        for key in GaussKronrodRule::ALL {
+            let qag = Qag {
+                key,
+                limit,
+                ..Default::default()
+            };
+            let f = FnVec {
+                components: Arc::new(|x: f64| array![x.sin(), x.cos()]),
+            };
+            let (res, gauss) = qag.integrate_with_gauss(&f, a, b, epsabs, epsrel).unwrap();
+
+            assert!(res.more_info.is_none());
+            for k in 0..2 {
+                assert!((res.result[k] - correct_result[k]).abs() < epsabs);
+                assert!((gauss[k] - correct_result[k]).abs() < 1.0e-3);
+                assert!((gauss[k] - res.result[k]).abs() > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn gauss_semi_infinite() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(-x).exp()]),
+        };
+        let (res, gauss) = qag
+            .integrate_with_gauss(&f, 0.0, f64::INFINITY, 1.0e-10, 0.0)
+            .unwrap();
+
+        assert!(res.more_info.is_some());
+        assert!((res.result[0] - 1.0).abs() < 1.0e-10);
+        assert!((gauss[0] - 1.0).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn gauss_reversed_and_reparam() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.cos()]),
+        };
+        let (res, gauss) = qag
+            .integrate_with_gauss(&f, 0.0, 10.0, 1.0e-8, 0.0)
+            .unwrap();
+        let (res_reversed, gauss_reversed) = qag
+            .integrate_with_gauss(&f, 10.0, 0.0, 1.0e-8, 0.0)
+            .unwrap();
+        assert_eq!(res_reversed.result, -res.result);
+        assert_eq!(gauss_reversed, -gauss);
+
+        // 1/x is a polynomial of degree 0 in u = ln(x), where both rules are exact.
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![1.0 / x]),
+        };
+        let qag_log = Qag {
+            reparam: Reparam::Log,
+            ..qag
+        };
+        let (res, gauss) = qag_log
+            .integrate_with_gauss(&g, 1.0e-6, 1.0e6, 1.0e-8, 0.0)
+            .unwrap();
+        let correct_result = 12.0 * 10.0_f64.ln();
+        assert!((res.result[0] - correct_result).abs() < 1.0e-12);
+        assert!((gauss[0] - correct_result).abs() < 1.0e-12);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::constants::{FnVec, EPMACH};
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+/// Endpoint of the integration interval where the integrand is singular.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Endpoint {
+    Left,
+    Right,
+}
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function with an integrable singularity at one
+    /// endpoint, e.g. f ~ (x-a)^(-p) with p < 1.
+    ///
+    /// Before the adaptive bisection, the interval is split in a geometrically graded partition
+    /// toward the singular 'endpoint': the breakpoints lie at distance (b-a)*ratio^k from it, for
+    /// k = 1, 2, ... until the distance becomes smaller than |b-a|*[EPMACH], or until the
+    /// partition has half of [limit](Qag::limit) sub-intervals. These are added to the
+    /// [points](Qag::points) and [integrate](Qag::integrate) is called with the limit reduced by
+    /// their number, so that the final sub-intervals are at most [limit](Qag::limit).
+    ///
+    /// The interval must be finite and 'ratio' must lie in (0,1), otherwise
+    /// [Invalid](QagError::Invalid) is returned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn integrate_graded(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        endpoint: Endpoint,
+        ratio: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        if !(ratio > 0.0 && ratio < 1.0 && a.is_finite() && b.is_finite()) {
+            return Err(QagError::Invalid);
+        }
+
+        let mut points = self.points.clone();
+        let mut h = (b - a) * ratio;
+        // The other half of the limit is left to the bisections.
+        while points.len() + 1 < self.limit / 2 && h.abs() >= (b - a).abs() * EPMACH {
+            points.push(match endpoint {
+                Endpoint::Left => a + h,
+                Endpoint::Right => b - h,
+            });
+            h *= ratio;
+        }
+
+        let qag = Qag {
+            limit: self.limit.saturating_sub(points.len()).max(1),
+            points,
+            ..self.clone()
+        };
+        qag.integrate(fun, a, b, epsabs, epsrel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::{GaussKronrodRule, LimitBehavior, Qag};
+    use crate::qag_graded::Endpoint;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn graded() {
+        let a = 0.0;
+        let b = 1.0;
+        let epsrel = 0.0;
+        let epsabs = 1.0e-3;
+        let limit = 10000;
+        let key = GaussKronrodRule::G10K21;
+        let correct_result = 10.0;
+
+        let qag = Qag {
+            key,
+            limit,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.9)]),
+        };
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![(-x).powf(-0.9)]),
+        };
+
+        let res_plain = qag.integrate(&f, a, b, epsabs, epsrel).unwrap();
+        let res_left = qag
+            .integrate_graded(&f, a, b, Endpoint::Left, 0.2, epsabs, epsrel)
+            .unwrap();
+        let res_right = qag
+            .integrate_graded(&g, -b, a, Endpoint::Right, 0.2, epsabs, epsrel)
+            .unwrap();
+
+        assert!((res_left.result[0] - correct_result).abs() < epsabs);
+        assert!((res_right.result[0] - correct_result).abs() < epsabs);
+        assert!(res_left.more_info.unwrap().hash.len() < res_plain.more_info.unwrap().hash.len());
+    }
+
+    #[test]
+    fn graded_limit() {
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.9)]),
+        };
+        // A ratio close to 1 grades far more points than the limit, and a tight tolerance keeps
+        // bisecting after the grading.
+        for (ratio, epsabs) in [(0.9, 1.0e-3), (0.2, 1.0e-14)] {
+            let qag = Qag {
+                limit: 30,
+                more_info: true,
+                limit_behavior: LimitBehavior::ReturnBest,
+                ..Default::default()
+            };
+            let res = qag
+                .integrate_graded(&f, 0.0, 1.0, Endpoint::Left, ratio, epsabs, 0.0)
+                .unwrap();
+            assert!(res.more_info.unwrap().heap.len() <= qag.limit);
+        }
+    }
+
+    #[test]
+    fn graded_invalid() {
+        let qag = Qag::default();
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x]),
+        };
+        let res = qag.integrate_graded(&f, 0.0, 1.0, Endpoint::Left, 1.5, 1.0e-3, 0.0);
+
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::{s, Array1, Array2};
+use std::sync::Arc;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function f(x; theta) together with its gradient
+    /// with respect to the parameters 'theta'.
+    ///
+    /// The derivatives are approximated by central differences with steps 'h', one per
+    /// parameter, and integrated together with f on the same adaptive mesh, so that the
+    /// gradient is consistent with the value instead of being the noisy difference of
+    /// independently adapted integrations. The returned result contains only the value of the
+    /// integral, while 'abserr' and [MoreInfo](crate::qag_integration_result::MoreInfo) refer to
+    /// the whole stack [f, df/dtheta_0, df/dtheta_1, ...]. The Jacobian has shape
+    /// (components, parameters). If 'h' and 'theta' have different lengths, or a step is zero,
+    /// [Invalid](QagError::Invalid) is returned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn integrate_gradient<F>(
+        &self,
+        f: F,
+        theta: &[f64],
+        h: &[f64],
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Array2<f64>), QagError>
+    where
+        F: Fn(f64, &[f64]) -> Vec<f64> + Sync + Send,
+    {
+        if h.len() != theta.len() || h.iter().any(|&h| !h.is_finite() || h == 0.0) {
+            return Err(QagError::Invalid);
+        }
+        let p = theta.len();
+        let stacked = FnVec {
+            components: Arc::new(|x: f64| {
+                let value = f(x, theta);
+                let n = value.len();
+                let mut y = Array1::zeros(n * (p + 1));
+                y.slice_mut(s![..n]).assign(&Array1::from(value));
+                let mut shifted = theta.to_vec();
+                for j in 0..p {
+                    shifted[j] = theta[j] + h[j];
+                    let forward = Array1::from(f(x, &shifted));
+                    shifted[j] = theta[j] - h[j];
+                    let backward = Array1::from(f(x, &shifted));
+                    shifted[j] = theta[j];
+                    y.slice_mut(s![n * (j + 1)..n * (j + 2)])
+                        .assign(&((forward - backward) / (2.0 * h[j])));
+                }
+                y
+            }),
+        };
+
+        let mut res = self.integrate(&stacked, a, b, epsabs, epsrel)?;
+        let n = res.result.len() / (p + 1);
+        let mut jacobian = Array2::zeros((n, p));
+        for j in 0..p {
+            jacobian
+                .column_mut(j)
+                .assign(&res.result.slice(s![n * (j + 1)..n * (j + 2)]));
+        }
+        res.result = res.result.slice(s![..n]).to_owned();
+        Ok((res, jacobian))
+    }
+
+    /// Derivative with respect to t of I(t), the integral of f(x, t) from a(t) to b(t), by the
+    /// Leibniz rule.
+    ///
+    /// 'a' and 'b' are the bounds and 'a_prime' and 'b_prime' their derivatives at 't', while
+    /// 'dfdt' is the partial derivative of f with respect to t. f and 'dfdt' are integrated
+    /// together on the same adaptive mesh, and the boundary terms f(b, t) b'(t) - f(a, t) a'(t)
+    /// are added to the integral of 'dfdt'. It returns I(t), with 'abserr' referring to both
+    /// integrals, together with dI/dt. An infinite bound is accepted only if its derivative is
+    /// zero, otherwise [Invalid](QagError::Invalid) is returned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn leibniz_derivative<F, D>(
+        &self,
+        f: F,
+        dfdt: D,
+        a: f64,
+        b: f64,
+        a_prime: f64,
+        b_prime: f64,
+        t: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Array1<f64>), QagError>
+    where
+        F: Fn(f64, f64) -> Vec<f64> + Sync + Send,
+        D: Fn(f64, f64) -> Vec<f64> + Sync + Send,
+    {
+        if (!a.is_finite() && a_prime != 0.0) || (!b.is_finite() && b_prime != 0.0) {
+            return Err(QagError::Invalid);
+        }
+        let stacked = FnVec {
+            components: Arc::new(|x: f64| {
+                let mut y = f(x, t);
+                y.extend(dfdt(x, t));
+                Array1::from(y)
+            }),
+        };
+
+        let mut res = self.integrate(&stacked, a, b, epsabs, epsrel)?;
+        let n = res.result.len() / 2;
+        let mut derivative = res.result.slice(s![n..]).to_owned();
+        if b_prime != 0.0 {
+            derivative += &(Array1::from(f(b, t)) * b_prime);
+        }
+        if a_prime != 0.0 {
+            derivative -= &(Array1::from(f(a, t)) * a_prime);
+        }
+        res.result = res.result.slice(s![..n]).to_owned();
+        Ok((res, derivative))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+
+    #[test]
+    fn gradient() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = |x: f64, theta: &[f64]| {
+            vec![
+                theta[0] * x * x + (theta[1] * x).sin(),
+                (theta[0] * x).exp(),
+            ]
+        };
+        let (t0, t1): (f64, f64) = (0.7, 2.0);
+        let correct_result = [t0 / 3.0 + (1.0 - t1.cos()) / t1, (t0.exp() - 1.0) / t0];
+        let correct_jacobian = [
+            [1.0 / 3.0, t1.sin() / t1 - (1.0 - t1.cos()) / (t1 * t1)],
+            [(t0 * t0.exp() - t0.exp() + 1.0) / (t0 * t0), 0.0],
+        ];
+
+        let (res, jacobian) = qag
+            .integrate_gradient(f, &[t0, t1], &[1.0e-5, 1.0e-5], 0.0, 1.0, 1.0e-12, 0.0)
+            .unwrap();
+        assert_eq!(res.result.len(), 2);
+        assert_eq!(jacobian.dim(), (2, 2));
+        for i in 0..2 {
+            assert!((res.result[i] - correct_result[i]).abs() < 1.0e-12);
+            for j in 0..2 {
+                assert!((jacobian[[i, j]] - correct_jacobian[i][j]).abs() < 1.0e-8);
+            }
+        }
+
+        let res = qag.integrate_gradient(f, &[t0, t1], &[1.0e-5], 0.0, 1.0, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+
+    #[test]
+    fn leibniz() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        // I(t) is the integral of sin(t x) from 1 / t to t^2.
+        let f = |x: f64, t: f64| vec![(t * x).sin()];
+        let dfdt = |x: f64, t: f64| vec![x * (t * x).cos()];
+        let t: f64 = 1.3;
+        let (a, b, a_prime, b_prime) = (1.0 / t, t * t, -1.0 / (t * t), 2.0 * t);
+        let correct_result = (1.0_f64.cos() - t.powi(3).cos()) / t;
+        let correct_derivative =
+            3.0 * t * t.powi(3).sin() - (1.0_f64.cos() - t.powi(3).cos()) / (t * t);
+
+        let (res, derivative) = qag
+            .leibniz_derivative(f, dfdt, a, b, a_prime, b_prime, t, 1.0e-12, 0.0)
+            .unwrap();
+        assert!((res.result[0] - correct_result).abs() < 1.0e-12);
+        assert!((derivative[0] - correct_derivative).abs() < 1.0e-12);
+
+        let res = qag.leibniz_derivative(f, dfdt, a, f64::INFINITY, a_prime, 1.0, t, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
@@ -1,24 +1,77 @@
 #[cfg(doc)]
-use crate::qag::Qag;
+use crate::qag::{LimitBehavior, Qag};
 
 use crate::constants::{HeapItem, Myf64};
+use crate::errors::QagError;
 use ndarray::{array, Array1};
 use std::collections::{BinaryHeap, HashMap};
 /// Result of [integrate](Qag::integrate).
 ///
 /// It contains the result [Array1], the error and optionally a [MoreInfo].
+#[must_use]
 #[derive(Debug, Clone)]
 pub struct QagIntegrationResult {
     pub result: Array1<f64>,
+    /// Estimate of the absolute error, sum of [truncation_err](QagIntegrationResult::truncation_err)
+    /// and [roundoff_err](QagIntegrationResult::roundoff_err).
     pub abserr: f64,
+    /// Error of the Gauss-Kronrod rules over the sub-intervals, reduced by tightening the
+    /// tolerance.
+    pub truncation_err: f64,
+    /// Accumulated roundoff error of the Gauss-Kronrod sums, reduced only by higher precision.
+    pub roundoff_err: f64,
     pub more_info: Option<MoreInfo>,
+    /// Error condition met during the integration, when a best estimate is returned anyway
+    /// (e.g. [ReturnBest](LimitBehavior::ReturnBest)).
+    pub failure: Option<QagError>,
+    /// Tolerance that has been satisfied, if the integration converged.
+    pub success_kind: Option<SuccessKind>,
+    /// Bisections performed during the integration, in processing order, if
+    /// [record_refinement_trace](Qag::record_refinement_trace) is set to true.
+    pub refinement_trace: Option<Vec<RefineEvent>>,
+    /// Final sub-intervals (left, right, err), sorted by their left end, if
+    /// [errors_only](Qag::errors_only) is set to true.
+    ///
+    /// For infinite ranges the intervals refer to the transformed variable, as in [MoreInfo].
+    pub interval_errors: Option<Vec<(f64, f64, f64)>>,
+    /// Additional [points](Qag::points) lying outside the integration interval, which have been
+    /// ignored. It is a warning: the integration went on without them.
+    pub ignored_points: Vec<f64>,
+    /// Condition number of the integral, max_k resabs_k / |result_k| where resabs is the
+    /// integral of |f|, set by [integrate_with_resabs](Qag::integrate_with_resabs) only when it
+    /// exceeds [CONDITION_THRESHOLD].
+    ///
+    /// It measures the cancellation between positive and negative parts of the integrand: a
+    /// relative perturbation of f, such as the rounding of its values, is amplified by up to
+    /// this factor in the result, so about log10(condition) significant digits are lost.
+    pub condition: Option<f64>,
+    /// Largest number of sub-intervals alive at the same time during the integration, to size
+    /// the memory of many concurrent integrations.
+    ///
+    /// Every bisection replaces one sub-interval with two, so it is the number of sub-intervals
+    /// at the end of the integration, also when [MoreInfo] is not requested. It is not changed
+    /// by [compact](QagIntegrationResult::compact), so it still sizes the memory used during
+    /// the integration when the stored [MoreInfo] is smaller.
+    pub peak_intervals: usize,
+    /// Coarse class of the integrand inferred from the final mesh, set by
+    /// [integrate](Qag::integrate).
+    pub difficulty: Option<Difficulty>,
+    /// Abscissae and values of all the evaluations of the integrand, sorted and deduplicated by
+    /// abscissa, if [record_point_values](Qag::record_point_values) is set to true.
+    ///
+    /// Unlike [MoreInfo], the abscissae refer to the original variable also for infinite ranges.
+    /// Besides the Gauss-Kronrod abscissae, it contains the midpoint of the interval, where the
+    /// number of components is probed, which is a Gauss-Kronrod abscissa only without
+    /// [points](Qag::points), and the two sides of the
+    /// [removable_singularities](Qag::removable_singularities), evaluated in their place.
+    pub point_values: Option<Vec<(f64, Vec<f64>)>>,
 }
 
 impl QagIntegrationResult {
     pub fn new_more_info(
         result: Array1<f64>,
         abserr: f64,
-        neval: i32,
+        neval: usize,
         last: usize,
         hash: HashMap<(Myf64, Myf64), Array1<f64>>,
         heap: BinaryHeap<HeapItem>,
@@ -26,7 +79,18 @@ impl QagIntegrationResult {
         Self {
             result,
             abserr,
+            truncation_err: abserr,
+            roundoff_err: 0.0,
             more_info: Some(MoreInfo::new(neval, last, hash, heap)),
+            failure: None,
+            success_kind: None,
+            refinement_trace: None,
+            interval_errors: None,
+            ignored_points: vec![],
+            condition: None,
+            peak_intervals: 0,
+            difficulty: None,
+            point_values: None,
         }
     }
 
@@ -34,18 +98,264 @@ impl QagIntegrationResult {
         Self {
             result,
             abserr,
+            truncation_err: abserr,
+            roundoff_err: 0.0,
             more_info: None,
+            failure: None,
+            success_kind: None,
+            refinement_trace: None,
+            interval_errors: None,
+            ignored_points: vec![],
+            condition: None,
+            peak_intervals: 0,
+            difficulty: None,
+            point_values: None,
         }
     }
 
+    /// Merge the runs of adjacent final sub-intervals in [MoreInfo] whose combined error is
+    /// below 'rel_threshold' * [abserr](QagIntegrationResult::abserr), to shrink it before
+    /// storing it.
+    ///
+    /// A merged interval carries the sum of the results and of the errors of its
+    /// sub-intervals and the smallest depth, so the total result and error are unchanged up to
+    /// rounding, while the merged error estimates are no longer the ones of a single
+    /// Gauss-Kronrod rule. Nothing is done without [MoreInfo].
+    pub fn compact(&mut self, rel_threshold: f64) {
+        let max_err = rel_threshold * self.abserr;
+        let Some(more_info) = self.more_info.as_mut() else {
+            return;
+        };
+        let mut items = std::mem::take(&mut more_info.heap).into_vec();
+        items.sort_by(|x, y| x.interval.0.total_cmp(&y.interval.0));
+
+        let mut runs: Vec<(HeapItem, Array1<f64>)> = vec![];
+        for item in items {
+            let key = (Myf64 { x: item.interval.0 }, Myf64 { x: item.interval.1 });
+            let res = more_info.hash.remove(&key).unwrap_or_default();
+            match runs.last_mut() {
+                Some((run, run_res))
+                    if run.interval.1 == item.interval.0 && run.err + item.err <= max_err =>
+                {
+                    run.interval.1 = item.interval.1;
+                    run.err += item.err;
+                    run.depth = run.depth.min(item.depth);
+                    *run_res += &res;
+                }
+                _ => runs.push((item, res)),
+            }
+        }
+        more_info.hash.clear();
+        for (run, res) in runs {
+            more_info.hash.insert(
+                (Myf64 { x: run.interval.0 }, Myf64 { x: run.interval.1 }),
+                res,
+            );
+            more_info.heap.push(run);
+        }
+    }
+
+    /// Final sub-intervals sorted by their left end, each with its result and error estimate.
+    ///
+    /// They are read from [MoreInfo] without consuming it, and there are none if it has not
+    /// been requested. For infinite ranges the intervals refer to the transformed variable, as
+    /// in [MoreInfo].
+    pub fn subintervals(&self) -> impl Iterator<Item = Subinterval> + '_ {
+        let mut items: Vec<&HeapItem> = self
+            .more_info
+            .iter()
+            .flat_map(|more_info| more_info.heap.iter())
+            .collect();
+        items.sort_by(|x, y| x.interval.0.total_cmp(&y.interval.0));
+        items.into_iter().map(move |item| {
+            let (a, b) = item.interval;
+            let result = self
+                .more_info
+                .as_ref()
+                .and_then(|more_info| more_info.hash.get(&(Myf64 { x: a }, Myf64 { x: b })))
+                .map_or_else(Vec::new, |res| res.to_vec());
+            Subinterval {
+                a,
+                b,
+                result,
+                abserr: item.err,
+            }
+        })
+    }
+
     pub fn new_error() -> Self {
         Self {
             result: array![0.0],
             abserr: 0.0,
+            truncation_err: 0.0,
+            roundoff_err: 0.0,
             more_info: None,
+            failure: None,
+            success_kind: None,
+            refinement_trace: None,
+            interval_errors: None,
+            ignored_points: vec![],
+            condition: None,
+            peak_intervals: 0,
+            difficulty: None,
+            point_values: None,
+        }
+    }
+}
+/// Converged integration returned by [try_integrate](Qag::try_integrate), for which every way of
+/// not achieving the requested tolerance is an error.
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct QagSuccess {
+    pub result: Array1<f64>,
+    pub abserr: f64,
+    pub more_info: Option<MoreInfo>,
+}
+
+impl QagSuccess {
+    /// Converged part of 'res', or its [failure](QagIntegrationResult::failure).
+    pub(crate) fn new(res: QagIntegrationResult) -> Result<Self, QagError> {
+        match res.failure {
+            Some(failure) => Err(failure),
+            None => Ok(Self {
+                result: res.result,
+                abserr: res.abserr,
+                more_info: res.more_info,
+            }),
+        }
+    }
+}
+/// Final sub-interval of an integration, as returned by
+/// [subintervals](QagIntegrationResult::subintervals).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subinterval {
+    pub a: f64,
+    pub b: f64,
+    pub result: Vec<f64>,
+    /// Error estimate of the Gauss-Kronrod rule over the sub-interval.
+    pub abserr: f64,
+}
+/// Threshold of the [condition](QagIntegrationResult::condition) number above which it is
+/// reported.
+pub const CONDITION_THRESHOLD: f64 = 1.0e3;
+/// Bisection of an interval performed by [integrate](Qag::integrate).
+///
+/// For infinite ranges the intervals refer to the transformed variable, as in [MoreInfo].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RefineEvent {
+    /// Bisected interval.
+    pub interval: (f64, f64),
+    /// Error estimate of the bisected interval.
+    pub err: f64,
+    /// Error estimates of the left and right halves.
+    pub children_err: (f64, f64),
+}
+
+impl RefineEvent {
+    pub fn new(interval: (f64, f64), err: f64) -> Self {
+        Self {
+            interval,
+            err,
+            children_err: (0.0, 0.0),
+        }
+    }
+}
+/// Tolerance which determined the error bound of a converged integration, as given by
+/// [success_kind](crate::qag::ToleranceLogic::success_kind).
+///
+/// If the bound came from 'epsabs', tightening 'epsrel' has no effect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SuccessKind {
+    /// Converged by the absolute tolerance 'epsabs'.
+    Absolute,
+    /// Converged by the relative tolerance 'epsrel'.
+    Relative,
+}
+/// Depth gap between the deepest sub-intervals and the median one above which the refinement is
+/// considered localized by [classify](Difficulty::classify).
+pub const DIFFICULTY_DEPTH_GAP: u32 = 8;
+/// Ratio |result| / resabs below which a uniformly refined integrand is considered
+/// [Oscillatory](Difficulty::Oscillatory) by [classify](Difficulty::classify).
+pub const DIFFICULTY_CANCELLATION: f64 = 0.05;
+
+/// Coarse class of an integrand, inferred from where the bisections clustered.
+///
+/// It is a heuristic, meant to suggest a better suited integrator (e.g. a weighted rule for an
+/// endpoint singularity or additional [points](Qag::points) for a discontinuity), not a proof of
+/// the nature of the integrand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    /// No localized refinement and no strong cancellation.
+    Smooth,
+    /// Uniform refinement with strong cancellation between positive and negative parts.
+    Oscillatory,
+    /// Refinement localized at an end of the interval, or at one of the [points](Qag::points).
+    EndpointSingular,
+    /// Refinement localized inside the interval, e.g. at a jump or at an interior singularity.
+    Discontinuous,
+}
+
+impl Difficulty {
+    /// Classification of the final sub-intervals 'heap' of the initial intervals 'edges'.
+    ///
+    /// The refinement is localized if the deepest sub-intervals are at least
+    /// [DIFFICULTY_DEPTH_GAP] bisections deeper than the median one: a singularity or a jump
+    /// keeps being bisected while the rest of the mesh stops, as its error decreases only
+    /// linearly with the length. Otherwise the mesh is uniform and 'cancellation', the ratio
+    /// |result| / resabs, tells an oscillating integrand apart from a smooth one.
+    pub fn classify(heap: &BinaryHeap<HeapItem>, edges: &[(f64, f64)], cancellation: f64) -> Self {
+        let mut depths: Vec<u32> = heap.iter().map(|item| item.depth).collect();
+        depths.sort_unstable();
+        let max_depth = match depths.last() {
+            Some(&depth) if depth > 0 => depth,
+            _ => return Difficulty::Smooth,
+        };
+        if max_depth >= depths[depths.len() / 2] + DIFFICULTY_DEPTH_GAP {
+            let at_edge = heap
+                .iter()
+                .filter(|item| item.depth == max_depth)
+                .any(|item| {
+                    edges
+                        .iter()
+                        .any(|&(lo, hi)| item.interval.0 == lo || item.interval.1 == hi)
+                });
+            if at_edge {
+                Difficulty::EndpointSingular
+            } else {
+                Difficulty::Discontinuous
+            }
+        } else if cancellation < DIFFICULTY_CANCELLATION {
+            Difficulty::Oscillatory
+        } else {
+            Difficulty::Smooth
         }
     }
 }
+/// Header of the serialization [to_bytes](MoreInfo::to_bytes), including the format version.
+pub const MORE_INFO_MAGIC: [u8; 4] = *b"QMI1";
+
+/// Little-endian reader used by [from_bytes](MoreInfo::from_bytes).
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], QagError> {
+        let end = self.pos.checked_add(len).ok_or(QagError::Decode)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(QagError::Decode)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_u64(&mut self) -> Result<u64, QagError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn read_f64(&mut self) -> Result<f64, QagError> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
 /// Optional additional information for the result of [integrate](Qag::integrate).
 ///
 /// It contains the number of function evaluation 'neval', the number of interval subdivision
@@ -53,15 +363,18 @@ impl QagIntegrationResult {
 /// with the error for every sub-interval 'heap'.
 #[derive(Debug, Clone)]
 pub struct MoreInfo {
-    pub neval: i32,
+    pub neval: usize,
     pub last: usize,
     pub hash: HashMap<(Myf64, Myf64), Array1<f64>>,
     pub heap: BinaryHeap<HeapItem>,
+    /// Convergence history: the number of sub-intervals and the error estimate (without the
+    /// roundoff) after the initial evaluation and after every iteration.
+    pub history: Vec<(usize, f64)>,
 }
 
 impl MoreInfo {
     pub fn new(
-        neval: i32,
+        neval: usize,
         last: usize,
         hash: HashMap<(Myf64, Myf64), Array1<f64>>,
         heap: BinaryHeap<HeapItem>,
@@ -71,6 +384,167 @@ impl MoreInfo {
             last,
             hash,
             heap,
+            history: vec![],
+        }
+    }
+
+    /// Empirical order of convergence p, such that abserr ~ neval^(-p).
+    ///
+    /// It is the opposite of the least-squares slope of log(abserr) versus log(last) over the
+    /// [history](MoreInfo::history), since neval is proportional to last. It is None if fewer than
+    /// 3 steps with non-zero error are available.
+    pub fn observed_order(&self) -> Option<f64> {
+        let points: Vec<(f64, f64)> = self
+            .history
+            .iter()
+            .filter(|(_, abserr)| *abserr > 0.0)
+            .map(|&(last, abserr)| ((last as f64).ln(), abserr.ln()))
+            .collect();
+        if points.len() < 3 {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        if sxx == 0.0 {
+            return None;
+        }
+        Some(-sxy / sxx)
+    }
+
+    /// Compact binary serialization, e.g. for caching an adaptive mesh to disk.
+    ///
+    /// After a header with [MORE_INFO_MAGIC], 'neval', 'last', the number of sub-intervals, the
+    /// number of components and the length of the [history](MoreInfo::history), every
+    /// sub-interval is stored as its endpoints, error, depth and result, followed by the history.
+    /// All the numbers are little-endian. Every sub-interval of 'heap' must have its result in
+    /// 'hash'.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let dim = self.hash.values().next().map_or(0, |res| res.len());
+        let mut bytes =
+            Vec::with_capacity(44 + self.heap.len() * (28 + 8 * dim) + self.history.len() * 16);
+        bytes.extend_from_slice(&MORE_INFO_MAGIC);
+        for len in [
+            self.neval,
+            self.last,
+            self.heap.len(),
+            dim,
+            self.history.len(),
+        ] {
+            bytes.extend_from_slice(&(len as u64).to_le_bytes());
+        }
+        for item in self.heap.iter() {
+            let (a, b) = item.interval;
+            for x in [a, b, item.err] {
+                bytes.extend_from_slice(&x.to_le_bytes());
+            }
+            bytes.extend_from_slice(&item.depth.to_le_bytes());
+            let res = self.hash.get(&(Myf64 { x: a }, Myf64 { x: b }));
+            debug_assert!(res.is_some(), "no result for the sub-interval {:?}", (a, b));
+            // Zeros keep the layout decodable if 'hash' and 'heap' disagree.
+            match res {
+                Some(res) => res
+                    .iter()
+                    .for_each(|x| bytes.extend_from_slice(&x.to_le_bytes())),
+                None => bytes.extend(std::iter::repeat(0).take(8 * dim)),
+            }
+        }
+        for &(last, err) in &self.history {
+            bytes.extend_from_slice(&(last as u64).to_le_bytes());
+            bytes.extend_from_slice(&err.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Inverse of [to_bytes](MoreInfo::to_bytes).
+    ///
+    /// It returns [Decode](QagError::Decode) if 'bytes' is not a valid serialization, without
+    /// allocating more than 'bytes' requires.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, QagError> {
+        let mut reader = ByteReader { bytes, pos: 0 };
+        if reader.take(4)? != MORE_INFO_MAGIC {
+            return Err(QagError::Decode);
+        }
+        let neval = reader.read_u64()? as usize;
+        let last = reader.read_u64()? as usize;
+        let number_of_intervals = reader.read_u64()? as usize;
+        let dim = reader.read_u64()? as usize;
+        let history_len = reader.read_u64()? as usize;
+        // The lengths in the header have to match the size of 'bytes' before allocating.
+        let expected_len = dim
+            .checked_mul(8)
+            .and_then(|len| len.checked_add(28))
+            .and_then(|len| len.checked_mul(number_of_intervals))
+            .and_then(|len| len.checked_add(history_len.checked_mul(16)?))
+            .and_then(|len| len.checked_add(reader.pos));
+        if expected_len != Some(bytes.len()) {
+            return Err(QagError::Decode);
+        }
+
+        let mut hash = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        for _ in 0..number_of_intervals {
+            let (a, b, err) = (reader.read_f64()?, reader.read_f64()?, reader.read_f64()?);
+            let depth = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+            let mut res = Array1::<f64>::zeros(dim);
+            for x in res.iter_mut() {
+                *x = reader.read_f64()?;
+            }
+            hash.insert((Myf64 { x: a }, Myf64 { x: b }), res);
+            heap.push(HeapItem::new_with_depth((a, b), err, depth));
+        }
+        let mut history = Vec::with_capacity(history_len);
+        for _ in 0..history_len {
+            history.push((reader.read_u64()? as usize, reader.read_f64()?));
+        }
+
+        let mut more_info = Self::new(neval, last, hash, heap);
+        more_info.history = history;
+        Ok(more_info)
+    }
+
+    /// Sorted and deduplicated endpoints of all the sub-intervals in 'hash'.
+    ///
+    /// These are the nodes of the adaptive mesh chosen by [integrate](Qag::integrate).
+    pub fn mesh(&self) -> Vec<f64> {
+        let mut mesh = vec![];
+        for (a, b) in self.hash.keys() {
+            mesh.push(a.x);
+            mesh.push(b.x);
+        }
+        mesh.sort_by(f64::total_cmp);
+        mesh.dedup();
+        mesh
+    }
+
+    /// Density of the evaluations implied by the final sub-intervals, evaluations per unit
+    /// length, as a step function.
+    ///
+    /// Every final sub-interval in 'heap' is given the same share neval / heap.len() of the
+    /// evaluations, those of the bisected parents included, so that the density integrates to
+    /// [neval](MoreInfo::neval), and it is 0 outside the mesh. The number of final sub-intervals
+    /// is used instead of 'last', which does not count the splits at the break points. It can be used, once normalized,
+    /// as the importance function of a subsequent Monte Carlo integration. For infinite ranges
+    /// the density refers to the transformed variable.
+    pub fn sample_density(&self) -> impl Fn(f64) -> f64 {
+        let share = self.neval as f64 / self.heap.len().max(1) as f64;
+        let mut steps: Vec<(f64, f64, f64)> = self
+            .heap
+            .iter()
+            .map(|item| {
+                let (a, b) = item.interval;
+                (a, b, share / (b - a))
+            })
+            .collect();
+        steps.sort_by(|x, y| x.0.total_cmp(&y.0));
+        move |x: f64| {
+            let i = steps.partition_point(|step| step.0 <= x);
+            match i.checked_sub(1).map(|i| steps[i]) {
+                Some((_, b, density)) if x <= b => density,
+                _ => 0.0,
+            }
         }
     }
 }
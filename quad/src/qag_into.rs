@@ -0,0 +1,106 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_workspace::QagWorkspace;
+
+impl Qag {
+    /// Same as [integrate_with_workspace](Qag::integrate_with_workspace), but writing the result
+    /// into 'out' and returning only the estimate of the absolute error, for callers which keep
+    /// their buffers across many integrations, e.g. in a real-time thread.
+    ///
+    /// The cache, the heap and the thread-pool of the workspace are reused, so after a first
+    /// call with similar integrands they do not grow any more. The values of the integrand and the
+    /// partial sums are still [Array1](ndarray::Array1), so the integration is not completely
+    /// free of allocations. [more_info](Qag::more_info) and
+    /// [record_refinement_trace](Qag::record_refinement_trace) are ignored, as they would move the
+    /// containers out of the workspace. If 'out' has not the length of the result,
+    /// [Invalid](QagError::Invalid) is returned. With
+    /// [ReturnBest](crate::qag::LimitBehavior::ReturnBest) the best estimate is written into
+    /// 'out' and the failure is returned as error.
+    #[allow(clippy::too_many_arguments)]
+    pub fn integrate_into(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        ws: &mut QagWorkspace,
+        out: &mut [f64],
+    ) -> Result<f64, QagError> {
+        let qag_plain;
+        let qag = if self.more_info || self.record_refinement_trace {
+            qag_plain = Qag {
+                more_info: false,
+                record_refinement_trace: false,
+                ..self.clone()
+            };
+            &qag_plain
+        } else {
+            self
+        };
+        let res = qag.integrate_with_workspace(ws, fun, a, b, epsabs, epsrel)?;
+        if res.result.len() != out.len() {
+            return Err(QagError::Invalid);
+        }
+        for (out, result) in out.iter_mut().zip(res.result.iter()) {
+            *out = *result;
+        }
+        match res.failure {
+            Some(failure) => Err(failure),
+            None => Ok(res.abserr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::{LimitBehavior, Qag};
+    use crate::qag_workspace::QagWorkspace;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn into_buffer() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let mut ws = QagWorkspace::new();
+        let mut out = [0.0; 2];
+        for k in 1..4 {
+            let k = k as f64;
+            let f = FnVec {
+                components: Arc::new(move |x: f64| array![(k * x).sin(), x.powf(-0.5)]),
+            };
+            let abserr = qag
+                .integrate_into(&f, 0.0, 1.0, 1.0e-10, 0.0, &mut ws, &mut out)
+                .unwrap();
+            let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+            assert_eq!(out.to_vec(), res.result.to_vec());
+            assert_eq!(abserr, res.abserr);
+            assert!(!ws.heap.is_empty());
+        }
+
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin()]),
+        };
+        let res = qag.integrate_into(&f, 0.0, 1.0, 1.0e-10, 0.0, &mut ws, &mut out);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+
+        let qag_short = Qag {
+            limit: 2,
+            limit_behavior: LimitBehavior::ReturnBest,
+            ..qag
+        };
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), 1.0]),
+        };
+        let res = qag_short.integrate_into(&g, 0.0, 1.0, 1.0e-12, 0.0, &mut ws, &mut out);
+        assert_eq!(res.unwrap_err(), QagError::MaxIteration);
+        assert!((out[1] - 1.0).abs() < 1.0e-15);
+    }
+}
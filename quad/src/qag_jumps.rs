@@ -0,0 +1,113 @@
+use crate::constants::{norm_ar, FnVec, EPMACH};
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+/// Number of cells of the uniform grid scanned by
+/// [integrate_locate_jumps](Qag::integrate_locate_jumps).
+pub const JUMP_SCAN_CELLS: usize = 256;
+/// Ratio between the variation of the integrand over a cell and over its neighbours above which
+/// the cell is considered to contain a jump.
+pub const JUMP_RATIO: f64 = 10.0;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function with jump discontinuities at unknown
+    /// locations, over the finite interval ('a', 'b').
+    ///
+    /// Before integrating, f is evaluated on a uniform grid of [JUMP_SCAN_CELLS] cells. A cell
+    /// whose variation |f(x_i+1) - f(x_i)| exceeds [JUMP_RATIO] times the ones of its
+    /// neighbours is bisected, keeping the half with the larger variation, until the jump is
+    /// located to machine precision. The jumps found are added to the [points](Qag::points) and
+    /// returned together with the result of [integrate](Qag::integrate). Jumps closer than a
+    /// cell to each other, or comparable with the variation of f over a cell, are not detected.
+    pub fn integrate_locate_jumps(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Vec<f64>), QagError> {
+        if !(a.is_finite() && b.is_finite()) {
+            return Err(QagError::Invalid);
+        }
+        let f = &fun.components;
+        let (lo, hi) = (a.min(b), a.max(b));
+        let h = (hi - lo) / JUMP_SCAN_CELLS as f64;
+        let grid: Vec<f64> = (0..=JUMP_SCAN_CELLS).map(|i| lo + i as f64 * h).collect();
+        let values: Vec<_> = grid.iter().map(|&x| f(x)).collect();
+        let variation: Vec<f64> = values
+            .windows(2)
+            .map(|w| norm_ar(&(&w[1] - &w[0])))
+            .collect();
+
+        let mut jumps = vec![];
+        for (i, &var) in variation.iter().enumerate() {
+            let left = if i > 0 { variation[i - 1] } else { 0.0 };
+            let right = variation.get(i + 1).copied().unwrap_or(0.0);
+            if !var.is_finite() || var <= JUMP_RATIO * left.max(right) {
+                continue;
+            }
+            let (mut l, mut r) = (grid[i], grid[i + 1]);
+            let (mut fl, mut fr) = (values[i].clone(), values[i + 1].clone());
+            while r - l > 4.0 * EPMACH * l.abs().max(r.abs()).max(1.0) {
+                let m = 0.5 * (l + r);
+                let fm = f(m);
+                if norm_ar(&(&fm - &fl)) > norm_ar(&(&fr - &fm)) {
+                    (r, fr) = (m, fm);
+                } else {
+                    (l, fl) = (m, fm);
+                }
+            }
+            jumps.push(0.5 * (l + r));
+        }
+
+        let mut points = self.points.clone();
+        points.extend_from_slice(&jumps);
+        let qag = Qag {
+            points,
+            ..self.clone()
+        };
+        Ok((qag.integrate(fun, a, b, epsabs, epsrel)?, jumps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::f64::consts::FRAC_1_SQRT_2;
+    use std::sync::Arc;
+
+    #[test]
+    fn step() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let c = FRAC_1_SQRT_2;
+        let f = FnVec {
+            components: Arc::new(move |x: f64| array![x.sin() + if x < c { 1.0 } else { 2.0 }]),
+        };
+        let correct_result = 1.0 - 1.0_f64.cos() + c + 2.0 * (1.0 - c);
+
+        let (res, jumps) = qag
+            .integrate_locate_jumps(&f, 0.0, 1.0, 1.0e-12, 0.0)
+            .unwrap();
+        assert_eq!(jumps.len(), 1);
+        assert!((jumps[0] - c).abs() < 1.0e-15);
+        assert!((res.result[0] - correct_result).abs() < 1.0e-12);
+
+        let res_plain = qag.integrate(&f, 0.0, 1.0, 1.0e-12, 0.0).unwrap();
+        assert!(res.more_info.unwrap().last < res_plain.more_info.unwrap().last);
+
+        let smooth = FnVec {
+            components: Arc::new(|x: f64| array![(10.0 * x).exp(), x.cos()]),
+        };
+        let (_, jumps) = qag
+            .integrate_locate_jumps(&smooth, 0.0, 1.0, 0.0, 1.0e-10)
+            .unwrap();
+        assert!(jumps.is_empty());
+    }
+}
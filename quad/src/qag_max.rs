@@ -0,0 +1,74 @@
+use crate::constants::{norm_ar, FnVec};
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use std::sync::{Arc, Mutex};
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function which also tracks the largest value of
+    /// the integrand met during the integration.
+    ///
+    /// Together with the result of [integrate](Qag::integrate), it returns (argmax_x, max_value),
+    /// where max_value is the largest Euclidean norm of f(x) over all the evaluated abscissae.
+    /// This is the sampled maximum, not the true global one: a narrow peak falling between the
+    /// abscissae is missed. NaN values of the integrand are ignored.
+    pub fn integrate_with_max(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, (f64, f64)), QagError> {
+        let f = &fun.components;
+        let max = Mutex::new((f64::NAN, f64::NEG_INFINITY));
+        let tracked = FnVec {
+            components: Arc::new(|x: f64| {
+                let y = f(x);
+                let norm = norm_ar(&y);
+                let mut max = max.lock().unwrap();
+                if norm > max.1 {
+                    *max = (x, norm);
+                }
+                y
+            }),
+        };
+        let res = self.integrate(&tracked, a, b, epsabs, epsrel)?;
+        drop(tracked);
+        Ok((res, max.into_inner().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn sampled_max() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 4,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(-(x - 1.3) * (x - 1.3) / 0.01).exp()]),
+        };
+
+        let (res, (argmax, max)) = qag.integrate_with_max(&f, 0.0, 3.0, 1.0e-10, 0.0).unwrap();
+        assert_eq!(
+            res.result,
+            qag.integrate(&f, 0.0, 3.0, 1.0e-10, 0.0).unwrap().result
+        );
+        assert!((argmax - 1.3).abs() < 1.0e-2);
+        assert!(max <= 1.0 && max > 0.99);
+
+        let (_, (argmax, max)) = qag
+            .integrate_with_max(&f, f64::NEG_INFINITY, f64::INFINITY, 1.0e-10, 0.0)
+            .unwrap();
+        assert!((argmax - 1.3).abs() < 1.0e-1);
+        assert!(max <= 1.0 && max > 0.5);
+    }
+}
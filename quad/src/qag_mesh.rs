@@ -0,0 +1,105 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::{LimitBehavior, Qag};
+use crate::qag_integration_result::QagIntegrationResult;
+
+impl Qag {
+    /// Adaptive partition of the finite interval ('a', 'b') capturing the features of 'fun'.
+    ///
+    /// [integrate](Qag::integrate) is called and the nodes of the final
+    /// [mesh](crate::qag_integration_result::MoreInfo::mesh) are returned, to be reused by
+    /// [integrate_on_mesh](Qag::integrate_on_mesh) for other integrands sharing the same structure,
+    /// e.g. f(x) * g(x) for many g.
+    pub fn precompute_mesh(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<Vec<f64>, QagError> {
+        if !(a.is_finite() && b.is_finite()) {
+            return Err(QagError::Invalid);
+        }
+        let qag = Qag {
+            more_info: true,
+            ..self.clone()
+        };
+        let res = qag.integrate(fun, a.min(b), a.max(b), epsabs, epsrel)?;
+        Ok(res.more_info.unwrap().mesh())
+    }
+
+    /// Adaptive integration of a vector-valued function over the sorted 'mesh', from its first to
+    /// its last node, using all the nodes as initial sub-intervals.
+    ///
+    /// If 'refine' is true the sub-intervals are further bisected as in
+    /// [integrate](Qag::integrate) until the requested tolerance is achieved. Otherwise only the
+    /// mesh is used: [limit](Qag::limit) and [limit_behavior](Qag::limit_behavior) are ignored,
+    /// and [failure](QagIntegrationResult::failure) is set to
+    /// [MaxIteration](QagError::MaxIteration) if the tolerance is not met.
+    pub fn integrate_on_mesh(
+        &self,
+        fun: &FnVec,
+        mesh: &[f64],
+        refine: bool,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        if mesh.len() < 2 {
+            return Err(QagError::Invalid);
+        }
+        let (a, b) = (mesh[0], mesh[mesh.len() - 1]);
+        let mut qag = Qag {
+            points: mesh[1..mesh.len() - 1].to_vec(),
+            ..self.clone()
+        };
+        if !refine {
+            qag.limit = 1;
+            qag.limit_behavior = LimitBehavior::ReturnBest;
+        }
+        qag.integrate(fun, a, b, epsabs, epsrel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn mesh_reuse() {
+        let (a, b) = (0.0, 1.0);
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = |x: f64| (x - 0.3).abs().sqrt();
+        let f_vec = FnVec {
+            components: Arc::new(move |x: f64| array![f(x)]),
+        };
+        let mesh = qag.precompute_mesh(&f_vec, a, b, 1.0e-10, 0.0).unwrap();
+        assert_eq!(mesh[0], a);
+        assert_eq!(mesh[mesh.len() - 1], b);
+
+        for k in 1..4 {
+            let fg = FnVec {
+                components: Arc::new(move |x: f64| array![f(x) * (k as f64 * x).cos()]),
+            };
+            let res_mesh = qag
+                .integrate_on_mesh(&fg, &mesh, false, 1.0e-8, 0.0)
+                .unwrap();
+            let res_refine = qag
+                .integrate_on_mesh(&fg, &mesh, true, 1.0e-10, 0.0)
+                .unwrap();
+            let res = qag.integrate(&fg, a, b, 1.0e-10, 0.0).unwrap();
+
+            assert!(res_mesh.failure.is_none());
+            assert_eq!(res_mesh.more_info.unwrap().last, 1);
+            assert!((res_mesh.result[0] - res.result[0]).abs() < 1.0e-8);
+            assert!((res_refine.result[0] - res.result[0]).abs() < 1.0e-10);
+        }
+    }
+}
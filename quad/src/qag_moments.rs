@@ -0,0 +1,131 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::{s, Array1, Array2};
+use std::sync::Arc;
+
+impl Qag {
+    /// Adaptive integration of the moments x^k f(x) of a vector-valued function, for
+    /// k = 0, ..., 'm'.
+    ///
+    /// The stack [f, x f, x^2 f, ..., x^m f] is integrated as a single vector-valued function,
+    /// so that all the moments share the same adaptive mesh and their ratios, e.g. the mean and
+    /// the variance of a density, are consistent. The returned result contains only the
+    /// integral of f, while 'abserr' and [MoreInfo](crate::qag_integration_result::MoreInfo)
+    /// refer to the whole stack. The moments have shape (m + 1, components), the k-th row being
+    /// the integral of x^k f. The [norm_weights](Qag::norm_weights), one per component of f,
+    /// weight every moment.
+    pub fn integrate_moments(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        m: usize,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Array2<f64>), QagError> {
+        let f = &fun.components;
+        let stacked = FnVec {
+            components: Arc::new(|x: f64| {
+                let value = f(x);
+                let n = value.len();
+                let mut y = Array1::zeros(n * (m + 1));
+                let mut power = 1.0;
+                for k in 0..=m {
+                    y.slice_mut(s![n * k..n * (k + 1)])
+                        .assign(&(&value * power));
+                    power *= x;
+                }
+                y
+            }),
+        };
+
+        let qag = Qag {
+            norm_weights: self
+                .norm_weights
+                .as_ref()
+                .map(|weights| weights.repeat(m + 1)),
+            ..self.clone()
+        };
+        let mut res = qag.integrate(&stacked, a, b, epsabs, epsrel)?;
+        let n = res.result.len() / (m + 1);
+        let moments = res
+            .result
+            .clone()
+            .into_shape((m + 1, n))
+            .expect("the stack has m + 1 blocks of n components");
+        res.result = res.result.slice(s![..n]).to_owned();
+        Ok((res, moments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn mean_variance() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        // A normal density with mean 1.5 and standard deviation 0.7, and an exponential one
+        // with rate 2, not normalized.
+        let (mu, sigma, rate): (f64, f64, f64) = (1.5, 0.7, 2.0);
+        let f = FnVec {
+            components: Arc::new(move |x: f64| {
+                let t = (x - mu) / sigma;
+                let exponential = if x >= 0.0 {
+                    3.0 * (-rate * x).exp()
+                } else {
+                    0.0
+                };
+                array![
+                    (-0.5 * t * t).exp() / (sigma * (2.0 * PI).sqrt()),
+                    exponential
+                ]
+            }),
+        };
+
+        let (res, moments) = qag
+            .integrate_moments(&f, f64::NEG_INFINITY, f64::INFINITY, 2, 1.0e-12, 0.0)
+            .unwrap();
+        assert_eq!(moments.dim(), (3, 2));
+        assert_eq!(res.result, moments.row(0));
+        let correct = [(mu, sigma * sigma), (1.0 / rate, 1.0 / (rate * rate))];
+        for (i, &(mean, variance)) in correct.iter().enumerate() {
+            let m0 = moments[[0, i]];
+            let mean_computed = moments[[1, i]] / m0;
+            let variance_computed = moments[[2, i]] / m0 - mean_computed * mean_computed;
+            assert!((mean_computed - mean).abs() < 1.0e-10);
+            assert!((variance_computed - variance).abs() < 1.0e-10);
+        }
+        assert!((moments[[0, 0]] - 1.0).abs() < 1.0e-12);
+        assert!((moments[[0, 1]] - 3.0 / rate).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn norm_weights() {
+        // The second component is a narrow peak, negligible in the unweighted norm.
+        let qag = Qag {
+            limit: 10000,
+            norm_weights: Some(vec![1.0, 1.0e24]),
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x, 1.0e-12 / (1.0e-4 + (x - 0.3) * (x - 0.3))]),
+        };
+        let peak = 1.0e-10 * (70.0_f64.atan() + 30.0_f64.atan());
+
+        let (res, moments) = qag.integrate_moments(&f, 0.0, 1.0, 1, 0.0, 1.0e-8).unwrap();
+        assert_eq!(res.result, moments.row(0));
+        assert!((moments[[0, 0]] - 0.5).abs() < 1.0e-8 * 0.5);
+        assert!((moments[[0, 1]] - peak).abs() < 1.0e-8 * peak);
+        assert!((moments[[1, 0]] - 1.0 / 3.0).abs() < 1.0e-8 / 3.0);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::Array1;
+use std::sync::{Arc, Mutex};
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function which needs to mutate its state, e.g. to
+    /// log every evaluation.
+    ///
+    /// Since an `FnMut` can not be called concurrently, the integration is serial:
+    /// [number_of_thread](Qag::number_of_thread) is ignored and a single thread is used.
+    pub fn integrate_mut<F>(
+        &self,
+        f: F,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError>
+    where
+        F: FnMut(f64) -> Array1<f64> + Send,
+    {
+        let f = Mutex::new(f);
+        let fun = FnVec {
+            components: Arc::new(|x: f64| (*f.lock().unwrap())(x)),
+        };
+        let qag = Qag {
+            number_of_thread: 1,
+            ..self.clone()
+        };
+        qag.integrate(&fun, a, b, epsabs, epsrel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qag::Qag;
+    use ndarray::array;
+
+    #[test]
+    fn evaluation_counter() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 8,
+            more_info: true,
+            ..Default::default()
+        };
+        let mut counter = 0;
+        let res = qag
+            .integrate_mut(
+                |x: f64| {
+                    counter += 1;
+                    array![x.sin(), x.cos()]
+                },
+                0.0,
+                1000.0,
+                1.0e-6,
+                0.0,
+            )
+            .unwrap();
+        let neval = res.more_info.unwrap().neval;
+
+        assert!(neval > 21);
+        // One more evaluation is used to determine the number of components.
+        assert_eq!(counter, neval + 1);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::constants::{norm_ar, FnVec, EPMACH};
+use crate::errors::QagError;
+use crate::qag::{LimitBehavior, Qag};
+use crate::qag_integration_result::QagIntegrationResult;
+/// Number of points of the first trapezoidal rule of
+/// [integrate_periodic](Qag::integrate_periodic).
+pub const PERIODIC_MIN_POINTS: usize = 8;
+/// Largest number of points of the trapezoidal rule of
+/// [integrate_periodic](Qag::integrate_periodic).
+pub const PERIODIC_MAX_POINTS: usize = 1 << 20;
+
+impl Qag {
+    /// Integration of a smooth vector-valued function of period b - a over the finite interval
+    /// ('a', 'b'), by the trapezoidal rule.
+    ///
+    /// For a smooth periodic integrand over a full period the trapezoidal rule converges
+    /// exponentially, much faster than the Gauss-Kronrod rules. As f(a) = f(b), the rule of n
+    /// points samples a, a + h, ..., b - h with equal weights h = (b - a) / n. It starts from
+    /// [PERIODIC_MIN_POINTS] and doubles the points, reusing the previous ones, until the norm
+    /// of the difference between the last two results satisfies the tolerance, which is
+    /// returned as 'abserr'. If it does not with [PERIODIC_MAX_POINTS],
+    /// [MaxIteration](QagError::MaxIteration) is handled as set by
+    /// [limit_behavior](Qag::limit_behavior). The convergence is not faster than the
+    /// algebraic one of a non-periodic or non-smooth integrand.
+    ///
+    /// The tolerance is combined as set by [tolerance_logic](Qag::tolerance_logic), and only
+    /// [limit_behavior](Qag::limit_behavior) and [tolerance_logic](Qag::tolerance_logic) are
+    /// used: the integrand is neither weighted nor reparametrized, as either would break its
+    /// periodicity. For b < a the samples are the same, so the opposite of the integral over
+    /// ('b', 'a') is returned.
+    pub fn integrate_periodic(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        if !(a.is_finite() && b.is_finite())
+            || (epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH))
+        {
+            return Err(QagError::Invalid);
+        }
+        let f = &fun.components;
+        let mut points = PERIODIC_MIN_POINTS;
+        let mut sum = f(a);
+        for i in 1..points {
+            sum += &f(a + (b - a) * i as f64 / points as f64);
+        }
+        let mut result = &sum * ((b - a) / points as f64);
+        loop {
+            // The new points are the midpoints of the previous ones.
+            for i in 0..points {
+                let x = a + (b - a) * (2 * i + 1) as f64 / (2 * points) as f64;
+                sum += &f(x);
+            }
+            points *= 2;
+            let next = &sum * ((b - a) / points as f64);
+            let abserr = norm_ar(&(&next - &result));
+            result = next;
+            let tolerance = &self.tolerance_logic;
+            if abserr <= tolerance.errbnd(epsabs, epsrel, &result) {
+                let mut res = QagIntegrationResult::new(result, abserr);
+                res.success_kind = Some(tolerance.success_kind(epsabs, epsrel, &res.result));
+                return Ok(res);
+            }
+            if points >= PERIODIC_MAX_POINTS {
+                return match self.limit_behavior {
+                    LimitBehavior::Error => Err(QagError::MaxIteration),
+                    LimitBehavior::ReturnBest => {
+                        let mut res = QagIntegrationResult::new(result, abserr);
+                        res.failure = Some(QagError::MaxIteration);
+                        Ok(res)
+                    }
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::{LimitBehavior, Qag, ToleranceLogic};
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn periodic() {
+        let mut qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let evaluations = AtomicUsize::new(0);
+        let f = FnVec {
+            components: Arc::new(|x: f64| {
+                evaluations.fetch_add(1, Ordering::Relaxed);
+                array![1.0 / (2.0 + x.cos()), (x.sin()).exp()]
+            }),
+        };
+        // The integral of exp(sin(x)) over a period is 2 pi I_0(1).
+        let correct_result = [2.0 * PI / 3.0_f64.sqrt(), 2.0 * PI * 1.2660658777520082];
+
+        let res = qag
+            .integrate_periodic(&f, 0.0, 2.0 * PI, 1.0e-13, 0.0)
+            .unwrap();
+        for (result, correct) in res.result.iter().zip(correct_result) {
+            assert!((result - correct).abs() < 1.0e-13);
+        }
+        assert!(res.abserr <= 1.0e-13);
+        let periodic_evaluations = evaluations.swap(0, Ordering::Relaxed);
+        assert!(periodic_evaluations <= 64);
+
+        let res_reversed = qag
+            .integrate_periodic(&f, 2.0 * PI, 0.0, 1.0e-13, 0.0)
+            .unwrap();
+        for (result, correct) in res_reversed.result.iter().zip(correct_result) {
+            assert!((result + correct).abs() < 1.0e-13);
+        }
+        evaluations.store(0, Ordering::Relaxed);
+
+        let res_adaptive = qag.integrate(&f, 0.0, 2.0 * PI, 1.0e-11, 0.0).unwrap();
+        assert!(evaluations.load(Ordering::Relaxed) > 2 * periodic_evaluations);
+        assert!((res_adaptive.result[0] - res.result[0]).abs() < 1.0e-11);
+
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x]),
+        };
+        let res = qag.integrate_periodic(&g, 0.0, 1.0, 1.0e-13, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::MaxIteration);
+        qag.limit_behavior = LimitBehavior::ReturnBest;
+        let res = qag.integrate_periodic(&g, 0.0, 1.0, 1.0e-13, 0.0).unwrap();
+        assert_eq!(res.failure, Some(QagError::MaxIteration));
+        assert!((res.result[0] - 0.5).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn tolerance_logic() {
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(x.sin()).exp()]),
+        };
+        let correct_result = 2.0 * PI * 1.2660658777520082;
+        let (epsabs, epsrel) = (1.0e-1, 1.0e-13);
+
+        let res_or = Qag::default()
+            .integrate_periodic(&f, 0.0, 2.0 * PI, epsabs, epsrel)
+            .unwrap();
+        let res_and = Qag {
+            tolerance_logic: ToleranceLogic::And,
+            ..Default::default()
+        }
+        .integrate_periodic(&f, 0.0, 2.0 * PI, epsabs, epsrel)
+        .unwrap();
+        assert!(res_or.abserr <= epsabs);
+        assert!(res_and.abserr <= epsrel * correct_result);
+        assert!((res_and.result[0] - correct_result).abs() < epsrel * correct_result);
+        assert!(res_or.abserr > res_and.abserr);
+    }
+}
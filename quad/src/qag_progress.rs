@@ -0,0 +1,75 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qag_workspace::QagWorkspace;
+use std::sync::mpsc::Sender;
+
+/// Progress of an integration, sent by
+/// [integrate_with_channel](Qag::integrate_with_channel).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgressMsg {
+    /// Number of sub-intervals.
+    pub last: usize,
+    /// Current estimate of the absolute error, including the roundoff.
+    pub abserr: f64,
+}
+
+impl Qag {
+    /// Same as [integrate](Qag::integrate), sending a [ProgressMsg] through 'tx' after the
+    /// initial evaluation and after every batch of bisections, e.g. to render the progress from
+    /// another thread.
+    ///
+    /// The messages are sent from the thread calling this method, between the batches, never
+    /// from the threads evaluating the integrand. The last one matches the returned result. If
+    /// the receiver is dropped the integration goes on without reporting.
+    pub fn integrate_with_channel(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+        tx: Sender<ProgressMsg>,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let mut ws = QagWorkspace::new();
+        ws.progress = Some(tx);
+        self.integrate_with_workspace(&mut ws, fun, a, b, epsabs, epsrel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use crate::qag_progress::ProgressMsg;
+    use ndarray::array;
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+
+    #[test]
+    fn channel_progress() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 4,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5), x.ln()]),
+        };
+
+        let (tx, rx) = channel();
+        let renderer = std::thread::spawn(move || rx.iter().collect::<Vec<ProgressMsg>>());
+        let res = qag
+            .integrate_with_channel(&f, 0.0, 1.0, 1.0e-10, 0.0, tx)
+            .unwrap();
+        let messages = renderer.join().unwrap();
+        assert!(messages.len() > 1);
+        assert!(messages.windows(2).all(|w| w[0].last < w[1].last));
+        let last = messages.last().unwrap();
+        assert_eq!(last.last, res.more_info.unwrap().last);
+        assert_eq!(last.abserr, res.abserr);
+        assert_eq!(messages[0].last, 1);
+    }
+}
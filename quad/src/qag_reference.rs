@@ -0,0 +1,87 @@
+use crate::constants::{norm_ar, FnVec, EPMACH};
+use crate::errors::QagError;
+use crate::qag::{OutputMap, Qag};
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qag_workspace::QagWorkspace;
+use ndarray::Array1;
+use std::sync::Arc;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function stopping as soon as the true relative
+    /// error |result - reference| / |reference| is below 'rtol', regardless of the error estimate.
+    ///
+    /// It is meant to characterize how conservative the returned 'abserr' is, comparing it with
+    /// the true error at the same stage of the subdivision. The internal tolerance is set to the
+    /// smallest accepted one, so that the integration is stopped by the reference, by
+    /// [limit](Qag::limit) or by the other error conditions. The integrand is transformed as in
+    /// [integrate](Qag::integrate), and the result is compared with the reference after mapping
+    /// it back to the original integrand.
+    pub fn integrate_to_reference(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        reference: &[f64],
+        rtol: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let reference = Array1::from(reference.to_vec());
+        let tolerance = rtol * norm_ar(&reference);
+        let (epsabs, epsrel) = (0.0, 50.0 * EPMACH);
+        let core = |qag: &Qag,
+                    ws: &mut QagWorkspace,
+                    f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+                    a: f64,
+                    b: f64,
+                    epsabs: f64,
+                    epsrel: f64,
+                    map: &OutputMap| {
+            // The result is compared with the reference as a result of the original integrand.
+            let stop = |result: &Array1<f64>| {
+                result.len() == reference.len()
+                    && norm_ar(&(map.apply(result) - &reference)) <= tolerance
+            };
+            let fun = FnVec {
+                components: Arc::new(f),
+            };
+            let res = qag.qintegrate_until(ws, &fun, a, b, epsabs, epsrel, Some(&stop))?;
+            Ok((res, ()))
+        };
+        let mut ws = QagWorkspace::new();
+        let f = &*fun.components;
+        let map = OutputMap::default();
+        let (res, ()) = self.integrate_pipeline(&mut ws, f, a, b, epsabs, epsrel, &map, &core)?;
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::norm_ar;
+    use crate::qag::{LimitBehavior, Qag};
+    use crate::test_functions::battery;
+
+    #[test]
+    fn reference() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            limit_behavior: LimitBehavior::ReturnBest,
+            ..Default::default()
+        };
+        let rtol = 1.0e-6;
+
+        for test in battery() {
+            let reference = test.correct_result.to_vec();
+            let res = qag
+                .integrate_to_reference(&test.f, test.a, test.b, &reference, rtol)
+                .unwrap();
+            let err = norm_ar(&(&res.result - &test.correct_result));
+            assert!(
+                err <= rtol * norm_ar(&test.correct_result),
+                "{}: true error {}",
+                test.name,
+                err
+            );
+        }
+    }
+}
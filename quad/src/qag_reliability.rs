@@ -0,0 +1,158 @@
+use crate::constants::{FnVec, Myf64};
+use crate::errors::QagError;
+use crate::qag::{GaussKronrodRule, OutputMap, Qag};
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qag_workspace::QagWorkspace;
+use crate::qk::qk_quadrature_with_reliability;
+use crate::rules::{
+    WG15, WG21, WG31, WG41, WG51, WG61, WGK15, WGK21, WGK31, WGK41, WGK51, WGK61, XGK15, XGK21,
+    XGK31, XGK41, XGK51, XGK61,
+};
+use ndarray::Array1;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function also returning a heuristic score
+    /// between 0 and 1 of how much 'abserr' can be trusted.
+    ///
+    /// Every Gauss-Kronrod rule scores its own error estimate as in
+    /// [qk_quadrature_with_reliability], and the score
+    /// of the integration is the mean over the final sub-intervals weighted by their errors, so
+    /// that it reflects the sub-intervals dominating 'abserr'. No further evaluation is needed.
+    /// The score is only a heuristic: a low one suggests verifying the result, e.g. with a
+    /// tighter tolerance or another [key](Qag::key), while a high one is no guarantee, as a
+    /// feature of the integrand missed by all the abscissae goes unnoticed. The integrand is
+    /// transformed as in [integrate](Qag::integrate).
+    pub fn integrate_with_reliability(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, f64), QagError> {
+        let core = |qag: &Qag,
+                    ws: &mut QagWorkspace,
+                    f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+                    a: f64,
+                    b: f64,
+                    epsabs: f64,
+                    epsrel: f64,
+                    _: &OutputMap| {
+            qag.qintegrate_with_reliability(ws, f, a, b, epsabs, epsrel)
+        };
+        let mut ws = QagWorkspace::new();
+        let f = &*fun.components;
+        self.integrate_pipeline(
+            &mut ws,
+            f,
+            a,
+            b,
+            epsabs,
+            epsrel,
+            &OutputMap::default(),
+            &core,
+        )
+    }
+
+    /// Same as [integrate_with_reliability](Qag::integrate_with_reliability) over the finite
+    /// interval ('a', 'b'), after the transformations of the integrand.
+    fn qintegrate_with_reliability(
+        &self,
+        ws: &mut QagWorkspace,
+        f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, f64), QagError> {
+        let n = f(0.5 * (a + b)).len();
+        let score_cache = Mutex::new(HashMap::new());
+        let rule = |x: f64, y: f64| {
+            let (result, abserr, round_error, score) = match self.key {
+                GaussKronrodRule::G7K15 => {
+                    qk_quadrature_with_reliability(f, x, y, &XGK15, &WGK15, &WG15)
+                }
+                GaussKronrodRule::G10K21 => {
+                    qk_quadrature_with_reliability(f, x, y, &XGK21, &WGK21, &WG21)
+                }
+                GaussKronrodRule::G15K31 => {
+                    qk_quadrature_with_reliability(f, x, y, &XGK31, &WGK31, &WG31)
+                }
+                GaussKronrodRule::G20K41 => {
+                    qk_quadrature_with_reliability(f, x, y, &XGK41, &WGK41, &WG41)
+                }
+                GaussKronrodRule::G25K51 => {
+                    qk_quadrature_with_reliability(f, x, y, &XGK51, &WGK51, &WG51)
+                }
+                GaussKronrodRule::G30K61 => {
+                    qk_quadrature_with_reliability(f, x, y, &XGK61, &WGK61, &WG61)
+                }
+            };
+            score_cache
+                .lock()
+                .unwrap()
+                .insert((Myf64 { x }, Myf64 { x: y }), score);
+            (result, abserr, round_error)
+        };
+
+        let qag = Qag {
+            errors_only: true,
+            ..self.clone()
+        };
+        let mut res = qag.qintegrate_rule(ws, n, &rule, a, b, epsabs, epsrel, None)?;
+
+        let score_cache = score_cache.into_inner().unwrap();
+        let (mut weighted, mut total) = (0.0, 0.0);
+        for &(x, y, err) in res.interval_errors.as_ref().unwrap() {
+            match score_cache.get(&(Myf64 { x }, Myf64 { x: y })) {
+                Some(score) => {
+                    weighted += err * score;
+                    total += err;
+                }
+                None => return Err(QagError::MissingInterval { a: x, b: y }),
+            }
+        }
+        if !self.errors_only {
+            res.interval_errors = None;
+        }
+        let score = if total > 0.0 { weighted / total } else { 1.0 };
+        Ok((res, score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn reliability() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let smooth = FnVec {
+            components: Arc::new(|x: f64| array![x.exp()]),
+        };
+        let oscillating = FnVec {
+            components: Arc::new(|x: f64| array![(40.0 * x).sin() * x.exp()]),
+        };
+
+        let (res, score_smooth) = qag
+            .integrate_with_reliability(&smooth, 0.0, 1.0, 1.0e-6, 0.0)
+            .unwrap();
+        let res_plain = qag.integrate(&smooth, 0.0, 1.0, 1.0e-6, 0.0).unwrap();
+        assert_eq!(res.result, res_plain.result);
+        assert_eq!(res.abserr, res_plain.abserr);
+        assert!(res.interval_errors.is_none());
+        let (_, score_oscillating) = qag
+            .integrate_with_reliability(&oscillating, 0.0, 1.0, 1.0e-6, 0.0)
+            .unwrap();
+        assert!(0.0 <= score_oscillating && score_oscillating < score_smooth);
+        assert!(score_smooth <= 1.0);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::constants::{FnVec, Myf64};
+use crate::errors::QagError;
+use crate::qag::{GaussKronrodRule, OutputMap, Qag};
+use crate::qag_integration_result::{QagIntegrationResult, CONDITION_THRESHOLD};
+use crate::qag_workspace::QagWorkspace;
+use crate::qk::qk_quadrature_with_resabs;
+use crate::rules::{
+    WG15, WG21, WG31, WG41, WG51, WG61, WGK15, WGK21, WGK31, WGK41, WGK51, WGK61, XGK15, XGK21,
+    XGK31, XGK41, XGK51, XGK61,
+};
+use ndarray::Array1;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function also returning the integral of the
+    /// absolute value of every component, resabs.
+    ///
+    /// The resabs are the Kronrod estimates of the integral of |f| computed alongside the result,
+    /// summed over the final sub-intervals, so no further evaluation is needed. They are not
+    /// refined on their own, hence they are accurate only where |f| is as smooth as f. The ratio
+    /// |result| / resabs measures the cancellation in the integral: values much smaller than 1
+    /// mean that the result is ill-conditioned, as reported by the
+    /// [condition](QagIntegrationResult::condition) number.
+    ///
+    /// The integrand is transformed as in [integrate](Qag::integrate), and the resabs refer to the
+    /// original integrand.
+    pub fn integrate_with_resabs(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Array1<f64>), QagError> {
+        let core = |qag: &Qag,
+                    ws: &mut QagWorkspace,
+                    f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+                    a: f64,
+                    b: f64,
+                    epsabs: f64,
+                    epsrel: f64,
+                    map: &OutputMap| {
+            let (res, resabs) = qag.qintegrate_with_resabs(ws, f, a, b, epsabs, epsrel)?;
+            Ok((res, map.apply_abs(&resabs)))
+        };
+        let mut ws = QagWorkspace::new();
+        let f = &*fun.components;
+        self.integrate_pipeline(
+            &mut ws,
+            f,
+            a,
+            b,
+            epsabs,
+            epsrel,
+            &OutputMap::default(),
+            &core,
+        )
+    }
+
+    /// Same as [integrate_with_resabs](Qag::integrate_with_resabs) over the finite interval
+    /// ('a', 'b'), after the transformations of the integrand.
+    fn qintegrate_with_resabs(
+        &self,
+        ws: &mut QagWorkspace,
+        f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Array1<f64>), QagError> {
+        let n = f(0.5 * (a + b)).len();
+        let resabs_cache = Mutex::new(HashMap::new());
+        let rule = |x: f64, y: f64| {
+            let (result, abserr, round_error, resabs) = match self.key {
+                GaussKronrodRule::G7K15 => {
+                    qk_quadrature_with_resabs(f, x, y, &XGK15, &WGK15, &WG15)
+                }
+                GaussKronrodRule::G10K21 => {
+                    qk_quadrature_with_resabs(f, x, y, &XGK21, &WGK21, &WG21)
+                }
+                GaussKronrodRule::G15K31 => {
+                    qk_quadrature_with_resabs(f, x, y, &XGK31, &WGK31, &WG31)
+                }
+                GaussKronrodRule::G20K41 => {
+                    qk_quadrature_with_resabs(f, x, y, &XGK41, &WGK41, &WG41)
+                }
+                GaussKronrodRule::G25K51 => {
+                    qk_quadrature_with_resabs(f, x, y, &XGK51, &WGK51, &WG51)
+                }
+                GaussKronrodRule::G30K61 => {
+                    qk_quadrature_with_resabs(f, x, y, &XGK61, &WGK61, &WG61)
+                }
+            };
+            resabs_cache
+                .lock()
+                .unwrap()
+                .insert((Myf64 { x }, Myf64 { x: y }), resabs);
+            (result, abserr, round_error)
+        };
+
+        let qag = Qag {
+            errors_only: true,
+            ..self.clone()
+        };
+        let mut res = qag.qintegrate_rule(ws, n, &rule, a, b, epsabs, epsrel, None)?;
+
+        let resabs_cache = resabs_cache.into_inner().unwrap();
+        let mut resabs = Array1::<f64>::zeros(n);
+        for &(x, y, _) in res.interval_errors.as_ref().unwrap() {
+            match resabs_cache.get(&(Myf64 { x }, Myf64 { x: y })) {
+                Some(interval_resabs) => resabs += interval_resabs,
+                None => return Err(QagError::MissingInterval { a: x, b: y }),
+            }
+        }
+        if !self.errors_only {
+            res.interval_errors = None;
+        }
+        let condition = resabs
+            .iter()
+            .zip(res.result.iter())
+            .map(|(resabs, result)| resabs / result.abs())
+            .fold(0.0, f64::max);
+        if condition > CONDITION_THRESHOLD {
+            res.condition = Some(condition);
+        }
+        Ok((res, resabs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn cancellation() {
+        // |sin(x)| has a kink at pi, where a breakpoint is needed.
+        let qag = Qag {
+            limit: 10000,
+            points: vec![PI],
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x * x]),
+        };
+
+        let (res, resabs) = qag
+            .integrate_with_resabs(&f, 0.0, 2.0 * PI, 1.0e-10, 0.0)
+            .unwrap();
+        let res_plain = qag.integrate(&f, 0.0, 2.0 * PI, 1.0e-10, 0.0).unwrap();
+        assert_eq!(res.result, res_plain.result);
+        assert_eq!(res.abserr, res_plain.abserr);
+        assert!(res.interval_errors.is_none());
+        assert!((resabs[0] - 4.0).abs() < 1.0e-10);
+        assert!((resabs[1] - res.result[1]).abs() < 1.0e-10);
+        assert!(res.result[0].abs() / resabs[0] < 1.0e-10);
+        assert!(res.condition.unwrap() > 1.0e10);
+
+        let (res, resabs) = qag
+            .integrate_with_resabs(&f, 2.0 * PI, 0.0, 1.0e-10, 0.0)
+            .unwrap();
+        assert!(res.result[1] < 0.0);
+        assert!((resabs[1] + res.result[1]).abs() < 1.0e-10);
+
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![-(-x * x).exp()]),
+        };
+        let (res, resabs) = qag
+            .integrate_with_resabs(&g, f64::NEG_INFINITY, 0.0, 1.0e-10, 0.0)
+            .unwrap();
+        assert!((resabs[0] - 0.5 * PI.sqrt()).abs() < 1.0e-10);
+        assert!(res.condition.is_none());
+    }
+}
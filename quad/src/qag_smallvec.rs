@@ -0,0 +1,174 @@
+use crate::errors::QagError;
+use crate::qag::{GaussKronrodRule, OutputMap, Qag, Values};
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qag_workspace::QagWorkspace;
+use crate::qk::qk_quadrature;
+use crate::rules::{
+    WG15, WG21, WG31, WG41, WG51, WG61, WGK15, WGK21, WGK31, WGK41, WGK51, WGK61, XGK15, XGK21,
+    XGK31, XGK41, XGK51, XGK61,
+};
+use ndarray::Array1;
+pub use smallvec::{smallvec, SmallVec};
+/// Value of an integrand with up to 8 components, stored on the stack.
+pub type SmallArray = SmallVec<[f64; 8]>;
+
+impl Values for SmallArray {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn zeros(n: usize) -> Self {
+        smallvec![0.0; n]
+    }
+
+    fn to_vec(&self) -> Vec<f64> {
+        self.as_slice().to_vec()
+    }
+
+    fn component(&self, k: usize) -> f64 {
+        self[k]
+    }
+
+    fn component_mut(&mut self, k: usize) -> &mut f64 {
+        &mut self[k]
+    }
+
+    fn scaled(mut self, factor: f64) -> Self {
+        self.iter_mut().for_each(|y| *y *= factor);
+        self
+    }
+
+    fn divided(mut self, divisor: f64) -> Self {
+        self.iter_mut().for_each(|y| *y /= divisor);
+        self
+    }
+
+    fn weighted(mut self, weights: &Array1<f64>) -> Self {
+        self.iter_mut().zip(weights).for_each(|(y, w)| *y *= w);
+        self
+    }
+
+    fn mean(mut self, other: Self) -> Self {
+        self.iter_mut()
+            .zip(other)
+            .for_each(|(y, z)| *y = 0.5 * (*y + z));
+        self
+    }
+}
+
+impl Qag {
+    /// Same as [integrate](Qag::integrate), for an integrand returning a [SmallArray].
+    ///
+    /// Integrands with up to 8 components are evaluated without any heap allocation, while the
+    /// results of the sub-intervals are still stored as [Array1].
+    pub fn integrate_small<F>(
+        &self,
+        f: F,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError>
+    where
+        F: Fn(f64) -> SmallArray + Sync,
+    {
+        let core = |qag: &Qag,
+                    ws: &mut QagWorkspace,
+                    f: &(dyn Fn(f64) -> SmallArray + Sync),
+                    a: f64,
+                    b: f64,
+                    epsabs: f64,
+                    epsrel: f64,
+                    _: &OutputMap| {
+            Ok((qag.qintegrate_small(ws, f, a, b, epsabs, epsrel)?, ()))
+        };
+        let mut ws = QagWorkspace::new();
+        let (res, ()) = self.integrate_pipeline(
+            &mut ws,
+            &f,
+            a,
+            b,
+            epsabs,
+            epsrel,
+            &OutputMap::default(),
+            &core,
+        )?;
+        Ok(res)
+    }
+
+    /// Adaptive integration over the finite interval ('a', 'b') of an integrand returning a
+    /// [SmallArray].
+    fn qintegrate_small(
+        &self,
+        ws: &mut QagWorkspace,
+        f: &(dyn Fn(f64) -> SmallArray + Sync),
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let n = f(0.5 * (a + b)).len();
+        let rule = |x: f64, y: f64| match self.key {
+            GaussKronrodRule::G7K15 => qk_quadrature(f, x, y, &XGK15, &WGK15, &WG15),
+            GaussKronrodRule::G10K21 => qk_quadrature(f, x, y, &XGK21, &WGK21, &WG21),
+            GaussKronrodRule::G15K31 => qk_quadrature(f, x, y, &XGK31, &WGK31, &WG31),
+            GaussKronrodRule::G20K41 => qk_quadrature(f, x, y, &XGK41, &WGK41, &WG41),
+            GaussKronrodRule::G25K51 => qk_quadrature(f, x, y, &XGK51, &WGK51, &WG51),
+            GaussKronrodRule::G30K61 => qk_quadrature(f, x, y, &XGK61, &WGK61, &WG61),
+        };
+        self.qintegrate_rule(ws, n, &rule, a, b, epsabs, epsrel, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::{GaussKronrodRule, Qag};
+    use crate::qag_smallvec::{smallvec, SmallArray};
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn same_as_array() {
+        let qag = Qag {
+            key: GaussKronrodRule::G7K15,
+            limit: 10000,
+            number_of_thread: 4,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| {
+                array![
+                    (-x * x).exp(),
+                    x * (-x * x).exp(),
+                    (-x * x).exp() * x.cos(),
+                    1.0 / (1.0 + x * x)
+                ]
+            }),
+        };
+        let f_small = |x: f64| -> SmallArray {
+            smallvec![
+                (-x * x).exp(),
+                x * (-x * x).exp(),
+                (-x * x).exp() * x.cos(),
+                1.0 / (1.0 + x * x)
+            ]
+        };
+
+        for key in GaussKronrodRule::ALL {
+            let qag = Qag { key, ..qag.clone() };
+            for (a, b) in [
+                (0.0, 10.0),
+                (10.0, 0.0),
+                (0.0, f64::INFINITY),
+                (f64::NEG_INFINITY, 0.0),
+                (f64::NEG_INFINITY, f64::INFINITY),
+            ] {
+                let res = qag.integrate(&f, a, b, 1.0e-10, 0.0).unwrap();
+                let res_small = qag.integrate_small(f_small, a, b, 1.0e-10, 0.0).unwrap();
+                assert_eq!(res.result, res_small.result);
+                assert_eq!(res.abserr, res_small.abserr);
+            }
+        }
+    }
+}
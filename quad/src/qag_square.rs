@@ -0,0 +1,102 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::{s, Array1};
+use std::sync::Arc;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function f together with its square f^2,
+    /// component by component.
+    ///
+    /// The stack [f, f^2] is integrated as a single vector-valued function, so that both
+    /// integrals are computed at the same abscissae and a variance estimated from them is
+    /// consistent. The returned result contains only the integral of f, while 'abserr' and
+    /// [MoreInfo](crate::qag_integration_result::MoreInfo) refer to the whole stack. The
+    /// [norm_weights](Qag::norm_weights), one per component of f, weight both f and f^2.
+    pub fn integrate_with_square(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Array1<f64>), QagError> {
+        let f = &fun.components;
+        let stacked = FnVec {
+            components: Arc::new(|x: f64| {
+                let value = f(x);
+                let n = value.len();
+                let mut y = Array1::zeros(2 * n);
+                y.slice_mut(s![n..]).assign(&(&value * &value));
+                y.slice_mut(s![..n]).assign(&value);
+                y
+            }),
+        };
+
+        let qag = Qag {
+            norm_weights: self.norm_weights.as_ref().map(|weights| weights.repeat(2)),
+            ..self.clone()
+        };
+        let mut res = qag.integrate(&stacked, a, b, epsabs, epsrel)?;
+        let n = res.result.len() / 2;
+        let square = res.result.slice(s![n..]).to_owned();
+        res.result = res.result.slice(s![..n]).to_owned();
+        Ok((res, square))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn sin_exp() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), (-x).exp()]),
+        };
+
+        let (res, square) = qag
+            .integrate_with_square(&f, 0.0, PI, 1.0e-12, 0.0)
+            .unwrap();
+        assert_eq!(res.result.len(), 2);
+        assert_eq!(square.len(), 2);
+        assert!((res.result[0] - 2.0).abs() < 1.0e-12);
+        assert!((res.result[1] - (1.0 - (-PI).exp())).abs() < 1.0e-12);
+        assert!((square[0] - 0.5 * PI).abs() < 1.0e-12);
+        assert!((square[1] - 0.5 * (1.0 - (-2.0 * PI).exp())).abs() < 1.0e-12);
+        // All the components are evaluated on the same mesh.
+        let more_info = res.more_info.unwrap();
+        assert!(more_info.hash.values().all(|value| value.len() == 4));
+    }
+
+    #[test]
+    fn norm_weights() {
+        // The second component is a narrow peak, negligible in the unweighted norm.
+        let qag = Qag {
+            limit: 10000,
+            norm_weights: Some(vec![1.0, 1.0e24]),
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x, 1.0e-12 / (1.0e-4 + (x - 0.3) * (x - 0.3))]),
+        };
+        let peak = 1.0e-10 * (70.0_f64.atan() + 30.0_f64.atan());
+
+        let (res, square) = qag
+            .integrate_with_square(&f, 0.0, 1.0, 0.0, 1.0e-8)
+            .unwrap();
+        assert!((res.result[0] - 0.5).abs() < 1.0e-8 * 0.5);
+        assert!((res.result[1] - peak).abs() < 1.0e-8 * peak);
+        assert!((square[0] - 1.0 / 3.0).abs() < 1.0e-8 / 3.0);
+    }
+}
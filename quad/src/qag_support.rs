@@ -0,0 +1,163 @@
+use crate::constants::{norm_ar, FnVec, EPMACH};
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::Array1;
+
+/// Number of sub-intervals of the first scan of
+/// [integrate_find_support](Qag::integrate_find_support), doubled at every level up to
+/// [SUPPORT_SCAN_MAX].
+pub const SUPPORT_SCAN_MIN: usize = 1 << 4;
+/// Largest number of sub-intervals of the scan of
+/// [integrate_find_support](Qag::integrate_find_support).
+pub const SUPPORT_SCAN_MAX: usize = 1 << 16;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function which vanishes outside an unknown
+    /// sub-interval of the finite interval ('a', 'b'), returning also the detected support.
+    ///
+    /// The interval is scanned on uniform grids of [SUPPORT_SCAN_MIN] sub-intervals, doubled at
+    /// every level and reusing the previous abscissae, until some value of the integrand is
+    /// non-zero or [SUPPORT_SCAN_MAX] is reached. The support is bracketed by the grid
+    /// abscissae next to the first and the last values whose norm exceeds EPMACH times the
+    /// largest one. The bracket is scanned again in the same way until the non-negligible values
+    /// cover at least half of its grid, so that a support found through a single tail value is
+    /// resolved before the integration, which is performed over it only. The support is assumed
+    /// to be a single interval: a feature narrower than the grid spacing can be missed, as any
+    /// other one lying between the outermost non-negligible abscissae is kept.
+    ///
+    /// The grids are uniform rather than logarithmically dense: the support can be anywhere in
+    /// ('a', 'b'), so no point of it is favoured, and the doubling already spends the
+    /// evaluations on a logarithmic sequence of resolutions.
+    ///
+    /// If no non-zero value is found, the result is zero and no support is returned. Infinite
+    /// bounds are rejected with [Invalid](QagError::Invalid). If b < a the bounds are swapped
+    /// and the result is negated, while the support is always increasing.
+    pub fn integrate_find_support(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, Option<(f64, f64)>), QagError> {
+        if b < a {
+            let (mut res, support) = self.integrate_find_support(fun, b, a, epsabs, epsrel)?;
+            res.result.mapv_inplace(|x| -x);
+            return Ok((res, support));
+        }
+        if !(a.is_finite() && b.is_finite()) {
+            return Err(QagError::Invalid);
+        }
+
+        let mut support = (a, b);
+        let mut detected = false;
+        while let Some((lo, hi, resolved)) = scan(fun, support.0, support.1) {
+            detected = true;
+            if (lo, hi) == support {
+                break;
+            }
+            support = (lo, hi);
+            if resolved {
+                break;
+            }
+        }
+        if !detected {
+            let n = (fun.components)(a).len();
+            return Ok((QagIntegrationResult::new(Array1::zeros(n), 0.0), None));
+        }
+        let res = self.integrate(fun, support.0, support.1, epsabs, epsrel)?;
+        Ok((res, Some(support)))
+    }
+}
+
+/// Bracket (lo, hi) of the non-negligible values of 'fun' over ('a', 'b'), scanned on grids of
+/// doubling size, and whether it spans at least half of the grid abscissae of the last scan.
+fn scan(fun: &FnVec, a: f64, b: f64) -> Option<(f64, f64, bool)> {
+    let f = &fun.components;
+    let abscissa = |i: usize, intervals: usize| a + (b - a) * i as f64 / intervals as f64;
+    let mut intervals = SUPPORT_SCAN_MIN;
+    let mut norms: Vec<f64> = (0..=intervals)
+        .map(|i| norm_ar(&f(abscissa(i, intervals))))
+        .collect();
+    while norms.iter().all(|&norm| norm == 0.0) && intervals < SUPPORT_SCAN_MAX {
+        intervals *= 2;
+        let mut refined = Vec::with_capacity(intervals + 1);
+        for (i, &norm) in norms.iter().enumerate() {
+            if i > 0 {
+                refined.push(norm_ar(&f(abscissa(2 * i - 1, intervals))));
+            }
+            refined.push(norm);
+        }
+        norms = refined;
+    }
+
+    let threshold = EPMACH * norms.iter().cloned().fold(0.0, f64::max);
+    let lo = norms.iter().position(|&norm| norm > threshold)?;
+    let hi = norms.iter().rposition(|&norm| norm > threshold)?;
+    let grid_size = norms.len();
+    Some((
+        abscissa(lo.saturating_sub(1), intervals),
+        abscissa((hi + 1).min(intervals), intervals),
+        2 * (hi - lo + 1) >= grid_size,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn gaussian_bump() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let sigma = 0.05;
+        // Centers from a linear congruential generator.
+        let mut seed: u64 = 12345;
+        for _ in 0..5 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let center = -1000.0 + 2000.0 * (seed >> 11) as f64 / (1u64 << 53) as f64;
+            let f = FnVec {
+                components: Arc::new(move |x: f64| {
+                    let t = (x - center) / sigma;
+                    array![(-0.5 * t * t).exp()]
+                }),
+            };
+
+            let (res, support) = qag
+                .integrate_find_support(&f, -1000.0, 1000.0, 1.0e-12, 0.0)
+                .unwrap();
+            let (lo, hi) = support.unwrap();
+            assert!(lo < center - 8.0 * sigma && center + 8.0 * sigma < hi);
+            assert!(hi - lo < 1.5);
+            assert!((res.result[0] - sigma * (2.0 * PI).sqrt()).abs() < 1.0e-12);
+
+            let (res_reversed, support_reversed) = qag
+                .integrate_find_support(&f, 1000.0, -1000.0, 1.0e-12, 0.0)
+                .unwrap();
+            assert_eq!(res_reversed.result, -&res.result);
+            assert_eq!(support_reversed, support);
+        }
+
+        let zero = FnVec {
+            components: Arc::new(|_: f64| array![0.0, 0.0]),
+        };
+        let (res, support) = qag
+            .integrate_find_support(&zero, 0.0, 1.0, 1.0e-12, 0.0)
+            .unwrap();
+        assert_eq!(res.result, array![0.0, 0.0]);
+        assert!(support.is_none());
+        let res = qag.integrate_find_support(&zero, 0.0, f64::INFINITY, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::constants::{norm_ar, FnVec};
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::rules::XGK61;
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function over the symmetric interval ('a', 'b'),
+    /// with b = -a, also checking whether the integrand is even.
+    ///
+    /// Together with the result of [integrate](Qag::integrate), it returns the maximum over the
+    /// 30-61 points Kronrod abscissae x in (0, b) of |f(x) - f(-x)|, as a diagnostic of symmetry
+    /// breaking. If 'b' is infinite the abscissae are mapped to (0, inf) by x / (1 - x). The
+    /// extra evaluations do not affect the result.
+    pub fn integrate_checked_symmetry(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<(QagIntegrationResult, f64), QagError> {
+        if a != -b {
+            return Err(QagError::Invalid);
+        }
+        let res = self.integrate(fun, a, b, epsabs, epsrel)?;
+
+        let f = &fun.components;
+        let half_width = b.abs();
+        let mut asymmetry: f64 = 0.0;
+        for &xgk in XGK61.iter() {
+            let x = if half_width.is_finite() {
+                half_width * xgk
+            } else {
+                xgk / (1.0 - xgk)
+            };
+            asymmetry = asymmetry.max(norm_ar(&(f(x) - f(-x))));
+        }
+        Ok((res, asymmetry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn symmetry() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let even = FnVec {
+            components: Arc::new(|x: f64| array![x.cos(), x * x]),
+        };
+        let broken = FnVec {
+            components: Arc::new(|x: f64| array![x.cos() + 1.0e-6 * x.powi(3), x * x]),
+        };
+
+        let (res, asymmetry) = qag
+            .integrate_checked_symmetry(&even, -1.0, 1.0, 1.0e-10, 0.0)
+            .unwrap();
+        assert_eq!(asymmetry, 0.0);
+        assert_eq!(
+            res.result,
+            qag.integrate(&even, -1.0, 1.0, 1.0e-10, 0.0)
+                .unwrap()
+                .result
+        );
+
+        let (_, asymmetry) = qag
+            .integrate_checked_symmetry(&broken, -1.0, 1.0, 1.0e-10, 0.0)
+            .unwrap();
+        assert!(asymmetry > 1.0e-6 && asymmetry <= 2.0e-6);
+
+        let gaussian = FnVec {
+            components: Arc::new(|x: f64| array![(-x * x).exp()]),
+        };
+        let (_, asymmetry) = qag
+            .integrate_checked_symmetry(&gaussian, f64::NEG_INFINITY, f64::INFINITY, 1.0e-10, 0.0)
+            .unwrap();
+        assert_eq!(asymmetry, 0.0);
+
+        let invalid = qag.integrate_checked_symmetry(&even, -1.0, 2.0, 1.0e-10, 0.0);
+        assert_eq!(invalid.unwrap_err(), QagError::Invalid);
+    }
+}
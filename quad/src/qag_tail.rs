@@ -0,0 +1,116 @@
+use crate::constants::{norm_ar, FnVec};
+use crate::errors::QagError;
+use crate::qag::Qag;
+use ndarray::Array1;
+
+/// Number of evaluations beyond the truncation point used by [tail_estimate](Qag::tail_estimate).
+const TAIL_SAMPLES: usize = 4;
+
+/// Asymptotic form of the integrand used by [tail_estimate](Qag::tail_estimate).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TailModel {
+    /// f(x) ~ C * x^(-exponent), with exponent > 1.
+    PowerLaw { exponent: f64 },
+    /// f(x) ~ C * exp(-rate * x), with rate > 0.
+    Exponential { rate: f64 },
+}
+
+impl Qag {
+    /// Estimate of the tail integral from 'l' to infinity of a vector-valued function, assuming
+    /// that it follows the asymptotic [TailModel] 'model' beyond 'l'.
+    ///
+    /// The constant C of the model is fitted, for each component, to a few evaluations beyond 'l'
+    /// (at l * 2^k for [PowerLaw](TailModel::PowerLaw), at l + k / rate for
+    /// [Exponential](TailModel::Exponential)), taking the farthest one as the estimate. The
+    /// returned error is the spread of the fitted constants times the analytic tail integral.
+    ///
+    /// [PowerLaw](TailModel::PowerLaw) requires l > 0.
+    pub fn tail_estimate(
+        &self,
+        fun: &FnVec,
+        l: f64,
+        model: TailModel,
+    ) -> Result<(Array1<f64>, f64), QagError> {
+        let (abscissae, factors, tail): (Vec<f64>, Vec<f64>, f64) = match model {
+            TailModel::PowerLaw { exponent } => {
+                if !(exponent > 1.0 && l > 0.0 && l.is_finite()) {
+                    return Err(QagError::Invalid);
+                }
+                let abscissae: Vec<f64> = (0..TAIL_SAMPLES)
+                    .map(|k| l * 2.0_f64.powi(k as i32))
+                    .collect();
+                let factors = abscissae.iter().map(|x| x.powf(exponent)).collect();
+                let tail = l.powf(1.0 - exponent) / (exponent - 1.0);
+                (abscissae, factors, tail)
+            }
+            TailModel::Exponential { rate } => {
+                if !(rate > 0.0 && l.is_finite()) {
+                    return Err(QagError::Invalid);
+                }
+                let abscissae: Vec<f64> = (0..TAIL_SAMPLES).map(|k| l + k as f64 / rate).collect();
+                let factors = abscissae.iter().map(|x| (rate * (x - l)).exp()).collect();
+                let tail = 1.0 / rate;
+                (abscissae, factors, tail)
+            }
+        };
+
+        let f = &fun.components;
+        let constants: Vec<Array1<f64>> = abscissae
+            .iter()
+            .zip(factors.iter())
+            .map(|(&x, &factor)| f(x) * factor)
+            .collect();
+        let best = &constants[TAIL_SAMPLES - 1];
+        let mut spread = Array1::<f64>::zeros(best.len());
+        for c in &constants[..TAIL_SAMPLES - 1] {
+            for (s, (ck, bk)) in spread.iter_mut().zip(c.iter().zip(best.iter())) {
+                *s = s.max((ck - bk).abs());
+            }
+        }
+
+        Ok((best * tail, norm_ar(&spread) * tail))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use crate::qag_tail::TailModel;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn tail() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powi(-2), x.powi(-2) + x.powi(-3)]),
+        };
+        let l = 100.0;
+        let correct_result = [1.0 / l, 1.0 / l + 0.5 / (l * l)];
+        let (tail, err) = qag
+            .tail_estimate(&f, l, TailModel::PowerLaw { exponent: 2.0 })
+            .unwrap();
+        assert!((tail[0] - correct_result[0]).abs() < 1.0e-15);
+        assert!((tail[1] - correct_result[1]).abs() <= err);
+
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![3.0 * (-2.0 * x).exp()]),
+        };
+        let (tail, err) = qag
+            .tail_estimate(&f, 1.0, TailModel::Exponential { rate: 2.0 })
+            .unwrap();
+        assert!((tail[0] - 1.5 * (-2.0_f64).exp()).abs() < 1.0e-14);
+        assert!(err < 1.0e-14);
+
+        let invalid = qag.tail_estimate(&f, 0.0, TailModel::PowerLaw { exponent: 2.0 });
+        assert_eq!(invalid.unwrap_err(), QagError::Invalid);
+        let invalid = qag.tail_estimate(&f, 1.0, TailModel::Exponential { rate: -1.0 });
+        assert_eq!(invalid.unwrap_err(), QagError::Invalid);
+    }
+}
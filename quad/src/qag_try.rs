@@ -0,0 +1,160 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagSuccess;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+impl Qag {
+    /// Same as [integrate](Qag::integrate), guaranteed not to panic on any input.
+    ///
+    /// NaN bounds, additional points or tolerances are rejected with [QagError::Invalid]. Any
+    /// other panic, raised by the integrand or by an inconsistent number of components, is caught
+    /// and returned as [QagError::Panicked] with its message. The panic hook still runs, so the
+    /// message is also printed on stderr unless the hook is replaced. A best estimate returned
+    /// by [integrate](Qag::integrate) with a
+    /// [failure](crate::qag_integration_result::QagIntegrationResult::failure) is returned as
+    /// that error, so that only a [QagSuccess] is Ok.
+    pub fn try_integrate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagSuccess, QagError> {
+        if [a, b, epsabs, epsrel].iter().any(|x| x.is_nan())
+            || self.points.iter().any(|x| x.is_nan())
+            || self.removable_singularities.iter().any(|x| x.is_nan())
+        {
+            return Err(QagError::Invalid);
+        }
+        match catch_unwind(AssertUnwindSafe(|| {
+            self.integrate(fun, a, b, epsabs, epsrel)
+        })) {
+            Ok(res) => QagSuccess::new(res?),
+            Err(payload) => {
+                let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                    message.to_string()
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    message.clone()
+                } else {
+                    String::new()
+                };
+                Err(QagError::Panicked(message))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::{LimitBehavior, Qag};
+    use ndarray::{array, Array1};
+    use std::sync::Arc;
+
+    #[test]
+    fn adversarial_inputs() {
+        let qag = Qag {
+            limit: 200,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), 1.0 / x, (-x * x).exp()]),
+        };
+        let special = [
+            f64::NAN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            0.0,
+            -0.0,
+            1.0,
+            -1.0,
+            f64::MAX,
+            f64::MIN,
+            f64::MIN_POSITIVE,
+            1.0e-310,
+            -1.0e-310,
+            f64::EPSILON,
+            1.0e300,
+        ];
+
+        // Deterministic xorshift, so that failures are reproducible.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            special[(state % special.len() as u64) as usize]
+        };
+        for _ in 0..300 {
+            let (a, b) = (next(), next());
+            let points = vec![next(), next()];
+            let qag = Qag {
+                points,
+                ..qag.clone()
+            };
+            let res = qag.try_integrate(&f, a, b, 1.0e-8, 0.0);
+            assert!(
+                !matches!(res, Err(QagError::Panicked(_))),
+                "({}, {}) with points {:?}: {:?}",
+                a,
+                b,
+                qag.points,
+                res
+            );
+        }
+        let res = qag.try_integrate(&f, f64::NAN, 1.0, 1.0e-8, 0.0);
+        assert!(matches!(res, Err(QagError::Invalid)));
+
+        let inconsistent = FnVec {
+            components: Arc::new(|x: f64| Array1::zeros(if x < 0.3 { 1 } else { 2 })),
+        };
+        let res = qag.try_integrate(&inconsistent, 0.0, 1.0, 1.0e-8, 0.0);
+        assert!(matches!(res, Err(QagError::Panicked(_))));
+
+        let panicking = FnVec {
+            components: Arc::new(|x: f64| {
+                if x > 0.5 {
+                    panic!("integrand undefined at {}", x);
+                }
+                array![x]
+            }),
+        };
+        let res = qag.try_integrate(&panicking, 0.0, 1.0, 1.0e-8, 0.0);
+        assert!(
+            matches!(res, Err(QagError::Panicked(message)) if message.starts_with("integrand"))
+        );
+    }
+
+    #[test]
+    fn success() {
+        let qag = Qag {
+            limit: 20,
+            more_info: true,
+            limit_behavior: LimitBehavior::ReturnBest,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.exp()]),
+        };
+        let success = qag.try_integrate(&f, 0.0, 1.0, 1.0e-12, 0.0).unwrap();
+        assert!((success.result[0] - (1.0_f64.exp() - 1.0)).abs() < 1.0e-12);
+        assert!(success.abserr < 1.0e-12);
+        assert!(success.more_info.is_some());
+
+        let singular = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.9)]),
+        };
+        let res = qag.integrate(&singular, 0.0, 1.0, 1.0e-12, 0.0).unwrap();
+        assert_eq!(res.failure, Some(QagError::MaxIteration));
+        let error = qag
+            .try_integrate(&singular, 0.0, 1.0, 1.0e-12, 0.0)
+            .unwrap_err();
+        assert_eq!(error, QagError::MaxIteration);
+        let error = qag.try_integrate(&f, 0.0, 1.0, -1.0, 0.0).unwrap_err();
+        assert_eq!(error, QagError::Invalid);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::constants::FnVec;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use std::sync::Arc;
+
+impl Qag {
+    /// Adaptive integration of the vector-valued function 'g' weighted by the scalar 'weight',
+    /// i.e. of g(x) * weight(x).
+    ///
+    /// Every evaluation of 'g' is scaled in place by the weight at the same abscissa, so that no
+    /// further array is allocated. If 'weight' is a probability density, the result is the
+    /// expectation value of 'g'.
+    pub fn integrate_weighted<W>(
+        &self,
+        g: &FnVec,
+        weight: W,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError>
+    where
+        W: Fn(f64) -> f64 + Send + Sync,
+    {
+        let g = &g.components;
+        let fun = FnVec {
+            components: Arc::new(move |x: f64| {
+                let mut y = g(x);
+                y *= weight(x);
+                y
+            }),
+        };
+        self.integrate(&fun, a, b, epsabs, epsrel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn expectation() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![1.0, x, x * x]),
+        };
+        let normal = |x: f64| (-0.5 * x * x).exp() / (2.0 * PI).sqrt();
+
+        let res = qag
+            .integrate_weighted(&g, normal, f64::NEG_INFINITY, f64::INFINITY, 1.0e-10, 0.0)
+            .unwrap();
+
+        let correct_result = array![1.0, 0.0, 1.0];
+        for k in 0..3 {
+            assert!((res.result[k] - correct_result[k]).abs() < 1.0e-10);
+        }
+    }
+}
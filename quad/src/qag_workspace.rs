@@ -0,0 +1,170 @@
+#[cfg(doc)]
+use crate::qag::Qag;
+
+use crate::constants::{HeapItem, Myf64};
+use crate::qag_progress::ProgressMsg;
+use ndarray::Array1;
+use rayon::{ThreadPool, ThreadPoolBuildError};
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+/// Reusable containers for [integrate_with_workspace](Qag::integrate_with_workspace).
+///
+/// It owns the sub-interval cache, the error heap, the intermediate lists of intervals and the
+/// thread-pool, so that they are allocated once and reused across many integrations.
+///
+/// If [more_info](Qag::more_info) is set to true the cache and the heap are moved into the
+/// returned [MoreInfo](crate::qag_integration_result::MoreInfo), and have to be allocated again
+/// by the next call.
+#[derive(Debug, Default)]
+pub struct QagWorkspace {
+    pub(crate) interval_cache: HashMap<(Myf64, Myf64), Array1<f64>>,
+    pub(crate) heap: BinaryHeap<HeapItem>,
+    pub(crate) points: Vec<f64>,
+    pub(crate) initial_intervals: Vec<(f64, f64)>,
+    pub(crate) to_process: Vec<(f64, f64, u32, f64)>,
+    pub(crate) capped: Vec<HeapItem>,
+    pub(crate) pool: Option<(usize, Option<ThreadPool>)>,
+    /// Flag checked after the initial evaluation and after every batch of bisections: when set,
+    /// the integration stops early returning the current estimate.
+    pub(crate) interrupt: Option<Arc<AtomicBool>>,
+    /// Override of [more_info](Qag::more_info) for the integrations using this workspace.
+    pub(crate) more_info: Option<bool>,
+    /// Channel receiving a [ProgressMsg] after the initial evaluation and after every batch of
+    /// bisections.
+    pub(crate) progress: Option<Sender<ProgressMsg>>,
+    #[cfg(feature = "affinity")]
+    pub(crate) cores: Option<Vec<usize>>,
+}
+
+impl QagWorkspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty all the containers, keeping their allocated capacity.
+    pub fn clear(&mut self) {
+        self.interval_cache.clear();
+        self.heap.clear();
+        self.points.clear();
+        self.initial_intervals.clear();
+        self.to_process.clear();
+        self.capped.clear();
+    }
+
+    /// Build a thread-pool with 'number_of_thread' threads, unless it is already available.
+    ///
+    /// If the thread-pool cannot be built (e.g. the OS forbids spawning threads) the integration
+    /// falls back to run serially on the current thread.
+    pub(crate) fn set_pool(&mut self, number_of_thread: usize) {
+        if !matches!(self.pool, Some((n, _)) if n == number_of_thread) {
+            let builder = rayon::ThreadPoolBuilder::new().num_threads(number_of_thread);
+            #[cfg(feature = "affinity")]
+            let builder = match self.cores.clone() {
+                Some(cores) if !cores.is_empty() => builder.start_handler(move |index| {
+                    let id = cores[index % cores.len()];
+                    core_affinity::set_for_current(core_affinity::CoreId { id });
+                }),
+                _ => builder,
+            };
+            let pool = builder.build();
+            self.set_built_pool(number_of_thread, pool);
+        }
+    }
+
+    /// Store the outcome of building the thread-pool, keeping no pool if the build failed.
+    pub(crate) fn set_built_pool(
+        &mut self,
+        number_of_thread: usize,
+        pool: Result<ThreadPool, ThreadPoolBuildError>,
+    ) {
+        self.pool = Some((number_of_thread, pool.ok()));
+    }
+
+    /// Pin the threads of the thread-pool to the cores with ids 'cores', the i-th thread to
+    /// cores\[i % cores.len()\].
+    ///
+    /// The thread-pool is built again by the next integration. If 'cores' is empty the threads
+    /// are not pinned. Pinning is a best effort: cores that do not exist are silently ignored.
+    #[cfg(feature = "affinity")]
+    pub fn set_core_affinity(&mut self, cores: Vec<usize>) {
+        self.cores = Some(cores);
+        self.pool = None;
+    }
+
+    /// Whether the integrations using this workspace run serially because the thread-pool could
+    /// not be built.
+    pub fn is_serial_fallback(&self) -> bool {
+        matches!(self.pool, Some((_, None)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use crate::qag_workspace::QagWorkspace;
+    use ndarray::array;
+    use std::io;
+    use std::sync::Arc;
+
+    #[test]
+    fn pool_build_failure() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 4,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.powf(-0.5)]),
+        };
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(qag.number_of_thread)
+            .spawn_handler(|_| Err(io::Error::new(io::ErrorKind::Other, "no threads")))
+            .build();
+        assert!(pool.is_err());
+        let mut ws = QagWorkspace::new();
+        ws.set_built_pool(qag.number_of_thread, pool);
+        assert!(ws.is_serial_fallback());
+
+        let res_serial = qag
+            .integrate_with_workspace(&mut ws, &f, 0.0, 1.0, 1.0e-10, 0.0)
+            .unwrap();
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+
+        assert!(ws.is_serial_fallback());
+        assert_eq!(res_serial.result, res.result);
+        assert_eq!(res_serial.abserr, res.abserr);
+    }
+
+    #[cfg(feature = "affinity")]
+    #[test]
+    fn core_affinity() {
+        let qag = Qag {
+            limit: 10000,
+            number_of_thread: 2,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.powf(-0.5)]),
+        };
+        let cores: Vec<usize> = core_affinity::get_core_ids()
+            .unwrap_or_default()
+            .iter()
+            .map(|core| core.id)
+            .collect();
+
+        let mut ws = QagWorkspace::new();
+        ws.set_core_affinity(cores);
+        let res_pinned = qag
+            .integrate_with_workspace(&mut ws, &f, 0.0, 1.0, 1.0e-10, 0.0)
+            .unwrap();
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+
+        assert!(!ws.is_serial_fallback());
+        assert_eq!(res_pinned.result, res.result);
+        assert_eq!(res_pinned.abserr, res.abserr);
+    }
+}
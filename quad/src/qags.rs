@@ -0,0 +1,511 @@
+use crate::constants::*;
+use crate::errors::QagError;
+use crate::qag::{LimitBehavior, OutputMap, Qag};
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qag_workspace::QagWorkspace;
+use ndarray::Array1;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Arc;
+
+/// Maximum number of elements of the epsilon table, 'limexp' in QUADPACK.
+pub const QAGS_LIMEXP: usize = 50;
+
+/// Adaptive integrator with the extrapolation of QUADPACK's qags, for integrands with
+/// singularities at the bounds or inside the interval.
+///
+/// The interval is bisected as in [Qag], but the sequence of the approximations of the integral
+/// is accelerated with the epsilon algorithm of Wynn: when all the sub-intervals are small
+/// with respect to the current level, the sum is added to the epsilon table and the
+/// extrapolated value replaces the direct one if its error estimate is smaller. The integrand is
+/// transformed as in [integrate](Qag::integrate), then the bisection is serial, and only
+/// [key](Qag::key), [limit](Qag::limit), [more_info](Qag::more_info),
+/// [limit_behavior](Qag::limit_behavior) and [tolerance_logic](Qag::tolerance_logic) of 'qag'
+/// are used.
+#[derive(Clone)]
+pub struct Qags {
+    pub qag: Qag,
+}
+
+/// State of the epsilon algorithm of one component, as in QUADPACK's qelg.
+#[derive(Clone)]
+struct Qelg {
+    /// Epsilon table, 1-based as in QUADPACK, with room for the two elements of the new
+    /// diagonal.
+    epstab: [f64; QAGS_LIMEXP + 3],
+    /// Number of elements of the table.
+    n: usize,
+    /// Last three extrapolated results.
+    res3la: [f64; 3],
+    /// Number of calls to [extrapolate](Qelg::extrapolate).
+    nres: usize,
+}
+
+impl Qelg {
+    fn new() -> Self {
+        Self {
+            epstab: [0.0; QAGS_LIMEXP + 3],
+            n: 0,
+            res3la: [0.0; 3],
+            nres: 0,
+        }
+    }
+
+    fn push(&mut self, x: f64) {
+        self.n += 1;
+        self.epstab[self.n] = x;
+    }
+
+    /// Extrapolated limit of the elements of the table, with its error estimate, computed as
+    /// the distance from the last three limits. The error is f64::MAX while fewer than four
+    /// limits have been computed.
+    fn extrapolate(&mut self) -> (f64, f64) {
+        let oflow = f64::MAX;
+        self.nres += 1;
+        let mut abserr = oflow;
+        let num = self.n;
+        let e = &mut self.epstab;
+        let mut result = e[num];
+        if num < 3 {
+            return (result, abserr.max(5.0 * EPMACH * result.abs()));
+        }
+        e[num + 2] = e[num];
+        let newelm = (num - 1) / 2;
+        e[num] = oflow;
+        let mut n = num;
+        let mut k1 = num;
+        for i in 1..=newelm {
+            let (k2, k3) = (k1 - 1, k1 - 2);
+            let res = e[k1 + 2];
+            let (e0, e1, e2) = (e[k3], e[k2], res);
+            let e1abs = e1.abs();
+            let delta2 = e2 - e1;
+            let err2 = delta2.abs();
+            let tol2 = e2.abs().max(e1abs) * EPMACH;
+            let delta3 = e1 - e0;
+            let err3 = delta3.abs();
+            let tol3 = e1abs.max(e0.abs()) * EPMACH;
+            if err2 <= tol2 && err3 <= tol3 {
+                // e0, e1 and e2 are equal to within machine accuracy: convergence is assumed.
+                result = res;
+                abserr = err2 + err3;
+                return (result, abserr.max(5.0 * EPMACH * result.abs()));
+            }
+            let e3 = e[k1];
+            e[k1] = e1;
+            let delta1 = e1 - e3;
+            let err1 = delta1.abs();
+            let tol1 = e1abs.max(e3.abs()) * EPMACH;
+            // Two elements are very close or the behaviour is irregular: the table is
+            // truncated.
+            if err1 <= tol1 || err2 <= tol2 || err3 <= tol3 {
+                n = 2 * i - 1;
+                break;
+            }
+            let ss = 1.0 / delta1 + 1.0 / delta2 - 1.0 / delta3;
+            if (ss * e1).abs() <= 1.0e-4 {
+                n = 2 * i - 1;
+                break;
+            }
+            let res = e1 + 1.0 / ss;
+            e[k1] = res;
+            k1 -= 2;
+            let error = err2 + (res - e2).abs() + err3;
+            if error <= abserr {
+                abserr = error;
+                result = res;
+            }
+        }
+
+        // Shift of the table.
+        if n == QAGS_LIMEXP {
+            n = 2 * (QAGS_LIMEXP / 2) - 1;
+        }
+        let mut ib = if num % 2 == 1 { 1 } else { 2 };
+        for _ in 0..=newelm {
+            e[ib] = e[ib + 2];
+            ib += 2;
+        }
+        if num != n {
+            let shift = num - n;
+            for i in 1..=n {
+                e[i] = e[i + shift];
+            }
+        }
+        self.n = n;
+
+        if self.nres < 4 {
+            self.res3la[self.nres - 1] = result;
+            abserr = oflow;
+        } else {
+            abserr = self.res3la.iter().map(|r| (result - r).abs()).sum();
+            self.res3la = [self.res3la[1], self.res3la[2], result];
+        }
+        (result, abserr.max(5.0 * EPMACH * result.abs()))
+    }
+}
+
+impl Qags {
+    pub fn new(qag: Qag) -> Self {
+        Self { qag }
+    }
+
+    /// Adaptive integration with extrapolation of a vector-valued function.
+    ///
+    /// The epsilon algorithm is applied to every component separately, and the error of the
+    /// extrapolated result is the norm of the component errors. The integrand is transformed as
+    /// in [integrate](Qag::integrate), e.g. semi-infinite and infinite intervals are mapped to
+    /// finite ones, and if b < a the bounds are swapped and the result is negated.
+    pub fn integrate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let core = |qag: &Qag,
+                    _: &mut QagWorkspace,
+                    f: &(dyn Fn(f64) -> Array1<f64> + Sync),
+                    a: f64,
+                    b: f64,
+                    epsabs: f64,
+                    epsrel: f64,
+                    _: &OutputMap| {
+            let fun = FnVec {
+                components: Arc::new(f),
+            };
+            Ok((Qags::new(qag.clone()).qags(&fun, a, b, epsabs, epsrel)?, ()))
+        };
+        let mut ws = QagWorkspace::new();
+        let f = &*fun.components;
+        let map = OutputMap::default();
+        let (res, ()) = self
+            .qag
+            .integrate_pipeline(&mut ws, f, a, b, epsabs, epsrel, &map, &core)?;
+        Ok(res)
+    }
+
+    /// Port of QUADPACK's qagse on the heap and the cache of the sub-intervals of [Qag].
+    fn qags(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        if epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH) {
+            return Err(QagError::Invalid);
+        }
+        let f = &fun.components;
+        let key = self.qag.key;
+        let rule = |x: f64, y: f64| key.quadrature(&**f, x, y);
+        let tolerance = &self.qag.tolerance_logic;
+        let oflow = f64::MAX;
+
+        let (result0, abserr0, mut rounderr) = rule(a, b);
+        let n = result0.len();
+        if n == 0 {
+            return Err(QagError::EmptyIntegrand);
+        }
+        let defabs = rounderr / (ROUNDOFF_FACTOR * EPMACH);
+        let mut errbnd = tolerance.errbnd(epsabs, epsrel, &result0);
+        let mut heap = BinaryHeap::new();
+        let mut interval_cache = HashMap::new();
+        heap.push(HeapItem::new((a, b), abserr0));
+        interval_cache.insert((Myf64 { x: a }, Myf64 { x: b }), result0.clone());
+
+        let mut ier = 0;
+        if abserr0 <= 2.0 * rounderr && abserr0 > errbnd {
+            ier = 2;
+        }
+        if self.qag.limit <= 1 {
+            ier = 1;
+        }
+
+        let mut last = 1;
+        let mut area = result0.clone();
+        let mut errsum = abserr0;
+        let mut result = result0.clone();
+        let mut abserr = oflow;
+        let ksgn = norm_ar(&result0) >= (1.0 - ROUNDOFF_FACTOR * EPMACH) * defabs;
+        let mut converged = abserr0 <= errbnd || abserr0 == 0.0;
+        if !converged && ier == 0 {
+            let mut qelg = vec![Qelg::new(); n];
+            for (q, &x) in qelg.iter_mut().zip(result0.iter()) {
+                q.push(x);
+            }
+            let (mut erlarg, mut ertest, mut small, mut correc) = (errsum, errbnd, 0.0, 0.0);
+            let (mut ktmin, mut iroff1, mut iroff2, mut iroff3) = (0, 0, 0, 0);
+            let mut ierro = 0;
+            let (mut extrap, mut noext) = (false, false);
+            let mut next: Option<HeapItem> = None;
+
+            while last < self.qag.limit {
+                last += 1;
+                let item = match next.take().or_else(|| heap.pop()) {
+                    Some(item) => item,
+                    None => break,
+                };
+                let (a1, b2) = item.interval;
+                let errmax = item.err;
+                let (b1, a2) = (0.5 * (a1 + b2), 0.5 * (a1 + b2));
+                let (area1, error1, round1) = rule(a1, b1);
+                let (area2, error2, round2) = rule(a2, b2);
+                let old = interval_cache
+                    .remove(&(Myf64 { x: a1 }, Myf64 { x: b2 }))
+                    .ok_or(QagError::MissingInterval { a: a1, b: b2 })?;
+                let area12 = &area1 + &area2;
+                let erro12 = error1 + error2;
+                rounderr += round1 + round2;
+                errsum += erro12 - errmax;
+                area += &(&area12 - &old);
+                if norm_ar(&(&old - &area12)) <= 1.0e-5 * norm_ar(&area12)
+                    && erro12 >= 0.99 * errmax
+                {
+                    if extrap {
+                        iroff2 += 1;
+                    } else {
+                        iroff1 += 1;
+                    }
+                }
+                if last > 10 && erro12 > errmax {
+                    iroff3 += 1;
+                }
+                let depth = item.depth + 1;
+                interval_cache.insert((Myf64 { x: a1 }, Myf64 { x: b1 }), area1);
+                interval_cache.insert((Myf64 { x: a2 }, Myf64 { x: b2 }), area2);
+                heap.push(HeapItem::new_with_depth((a1, b1), error1, depth));
+                heap.push(HeapItem::new_with_depth((a2, b2), error2, depth));
+                errbnd = tolerance.errbnd(epsabs, epsrel, &area);
+
+                if iroff1 + iroff2 >= 10 || iroff3 >= 20 {
+                    ier = 2;
+                }
+                if iroff2 >= 5 {
+                    ierro = 3;
+                }
+                if last == self.qag.limit {
+                    ier = 1;
+                }
+                if bad_function_flag(a1, b2) {
+                    ier = 4;
+                }
+                if errsum <= errbnd {
+                    converged = true;
+                    break;
+                }
+                if ier != 0 {
+                    break;
+                }
+                if last == 2 {
+                    small = (b - a) * 0.375;
+                    erlarg = errsum;
+                    ertest = errbnd;
+                    for (q, &x) in qelg.iter_mut().zip(area.iter()) {
+                        q.push(x);
+                    }
+                    continue;
+                }
+                if noext {
+                    continue;
+                }
+                erlarg -= errmax;
+                if b1 - a1 > small {
+                    erlarg += erro12;
+                }
+                if !extrap {
+                    // The extrapolation waits until every sub-interval is small.
+                    if matches!(heap.peek(), Some(item) if item.interval.1 - item.interval.0 > small)
+                    {
+                        continue;
+                    }
+                    extrap = true;
+                }
+                if ierro != 3 && erlarg > ertest {
+                    // The large intervals are bisected first.
+                    if let Some(item) = pop_large(&mut heap, small) {
+                        next = Some(item);
+                        continue;
+                    }
+                }
+
+                for (q, &x) in qelg.iter_mut().zip(area.iter()) {
+                    q.push(x);
+                }
+                let (reseps, abseps): (Vec<f64>, Vec<f64>) =
+                    qelg.iter_mut().map(|q| q.extrapolate()).unzip();
+                let abseps = norm_ar(&Array1::from(abseps));
+                ktmin += 1;
+                if ktmin > 5 && abserr < 1.0e-3 * errsum {
+                    ier = 5;
+                }
+                if abseps < abserr {
+                    ktmin = 0;
+                    abserr = abseps;
+                    result = Array1::from(reseps);
+                    correc = erlarg;
+                    ertest = tolerance.errbnd(epsabs, epsrel, &result);
+                    if abserr <= ertest {
+                        break;
+                    }
+                }
+                if qelg.iter().any(|q| q.n == 1) {
+                    noext = true;
+                }
+                if ier == 5 {
+                    break;
+                }
+                extrap = false;
+                small *= 0.5;
+                erlarg = errsum;
+            }
+
+            // Choice between the extrapolated result and the sum over the sub-intervals.
+            if !converged && abserr != oflow {
+                let (norm_result, norm_area) = (norm_ar(&result), norm_ar(&area));
+                let mut check_divergence = true;
+                if ier != 0 || ierro == 3 {
+                    if ierro == 3 {
+                        abserr += correc;
+                    }
+                    if ier == 0 {
+                        ier = 3;
+                    }
+                    if norm_result != 0.0 && norm_area != 0.0 {
+                        converged = abserr / norm_result > errsum / norm_area;
+                    } else if abserr > errsum {
+                        converged = true;
+                    } else if norm_area == 0.0 {
+                        check_divergence = false;
+                    }
+                }
+                // The sign of the ratio between the two results is the one of their product.
+                if !converged
+                    && check_divergence
+                    && norm_area != 0.0
+                    && (ksgn || norm_result.max(norm_area) > 0.01 * defabs)
+                {
+                    let ratio = result.dot(&area) / (norm_area * norm_area);
+                    if !(0.01..=100.0).contains(&ratio) || errsum > norm_area {
+                        ier = 6;
+                    }
+                }
+            } else {
+                converged = true;
+            }
+        } else {
+            converged = true;
+        }
+        if converged {
+            result = interval_cache
+                .values()
+                .fold(Array1::zeros(n), |sum, x| sum + x);
+            abserr = errsum;
+        }
+        if errsum <= errbnd {
+            ier = 0;
+        }
+
+        let mut failure = None;
+        match ier {
+            0 => (),
+            1 => match self.qag.limit_behavior {
+                LimitBehavior::Error => return Err(QagError::MaxIteration),
+                LimitBehavior::ReturnBest => failure = Some(QagError::MaxIteration),
+            },
+            2 | 3 | 5 => return Err(QagError::BadTolerance),
+            4 => return Err(QagError::BadFunction),
+            _ => return Err(QagError::Diverge),
+        }
+
+        let truncation_err = abserr;
+        abserr += rounderr;
+        let mut res = if self.qag.more_info {
+            QagIntegrationResult::new_more_info(
+                result,
+                abserr,
+                evals_for(key, last),
+                last,
+                interval_cache,
+                heap,
+            )
+        } else {
+            QagIntegrationResult::new(result, abserr)
+        };
+        res.truncation_err = truncation_err;
+        res.roundoff_err = rounderr;
+        if failure.is_none() {
+            res.success_kind = Some(tolerance.success_kind(epsabs, epsrel, &res.result));
+        }
+        res.failure = failure;
+        Ok(res)
+    }
+}
+
+/// Sub-interval with the largest error among the ones longer than 'small', removed from the
+/// heap.
+fn pop_large(heap: &mut BinaryHeap<HeapItem>, small: f64) -> Option<HeapItem> {
+    let mut stash = vec![];
+    let large = loop {
+        match heap.pop() {
+            Some(item) if item.interval.1 - item.interval.0 > small => break Some(item),
+            Some(item) => stash.push(item),
+            None => break None,
+        }
+    };
+    heap.extend(stash);
+    large
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use crate::qags::Qags;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn sqrt_singularity() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let qags = Qags::new(qag.clone());
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.powf(-0.5)]),
+        };
+
+        let res = qags.integrate(&f, 0.0, 1.0, 0.0, 1.0e-9).unwrap();
+        assert!((res.result[0] - 2.0).abs() < 2.0e-9);
+        assert!(res.abserr < 2.0e-9);
+        let last = res.more_info.unwrap().last;
+        let last_qag = qag
+            .integrate(&f, 0.0, 1.0, 0.0, 1.0e-9)
+            .unwrap()
+            .more_info
+            .unwrap()
+            .last;
+        assert!(4 * last < last_qag);
+
+        let res_reversed = qags.integrate(&f, 1.0, 0.0, 0.0, 1.0e-9).unwrap();
+        assert_eq!(res_reversed.result, -&res.result);
+
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x.ln() / x.sqrt(), x.cos()]),
+        };
+        let res = qags.integrate(&g, 0.0, 1.0, 0.0, 1.0e-9).unwrap();
+        assert!((res.result[0] + 4.0).abs() < 4.0e-9);
+        assert!((res.result[1] - 1.0_f64.sin()).abs() < 4.0e-9);
+
+        let h = FnVec {
+            components: Arc::new(|x: f64| array![1.0 / x]),
+        };
+        assert!(qags.integrate(&h, 0.0, 1.0, 0.0, 1.0e-9).is_err());
+        let res = qags.integrate(&f, 0.0, 1.0, 0.0, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
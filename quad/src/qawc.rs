@@ -0,0 +1,156 @@
+use crate::constants::*;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qawo::{chebyshev_values, modified_clenshaw_curtis, qintegrate_serial, QAWO_CC_DEGREE};
+
+/// Distance of the singularity from the centre of a sub-interval, in units of its half-length,
+/// from which the Gauss-Kronrod rule is used instead of the modified Clenshaw-Curtis one.
+pub const QAWC_CC_MAX: f64 = 1.1;
+
+/// Adaptive integrator of the Cauchy principal value of f(x) / (x - c), as QUADPACK's qawc.
+///
+/// On the sub-intervals containing or close to 'c', f is interpolated at the Chebyshev points
+/// and the interpolant is integrated exactly against 1 / (x - c). On the other ones the
+/// Gauss-Kronrod rule of [key](Qag::key) is applied to the whole integrand. The sub-interval
+/// containing 'c' is never split at 'c'. The bisection is serial, and uses the same parameters
+/// of 'qag' as [Qawo](crate::qawo::Qawo).
+#[derive(Clone)]
+pub struct Qawc {
+    pub qag: Qag,
+    /// Location of the singularity.
+    pub c: f64,
+}
+
+impl Qawc {
+    pub fn new(qag: Qag, c: f64) -> Self {
+        Self { qag, c }
+    }
+
+    /// Adaptive integration of the principal value of a vector-valued function divided by
+    /// x - c over the finite interval ('a', 'b').
+    ///
+    /// Infinite bounds and 'c' equal to one of the bounds are rejected with
+    /// [Invalid](QagError::Invalid). If b < a the bounds are swapped and the result is negated.
+    pub fn integrate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        if b < a {
+            let mut res = self.integrate(fun, b, a, epsabs, epsrel)?;
+            res.result.mapv_inplace(|x| -x);
+            return Ok(res);
+        }
+        let c = self.c;
+        let valid = a.is_finite() && b.is_finite() && c.is_finite() && c != a && c != b;
+        if !valid || (epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH)) {
+            return Err(QagError::Invalid);
+        }
+
+        let f = &fun.components;
+        let weighted = |x: f64| f(x) / (x - c);
+        let key = self.qag.key;
+        let mut neval = 0;
+        let mut rule = |x: f64, y: f64| {
+            let (center, half_length) = (0.5 * (x + y), 0.5 * (y - x));
+            let cc = (c - center) / half_length;
+            if cc.abs() >= QAWC_CC_MAX {
+                neval += key.points();
+                return key.quadrature(weighted, x, y);
+            }
+            neval += QAWO_CC_DEGREE + 1;
+            // With x = center + half_length t, dx / (x - c) = dt / (t - cc).
+            let values = chebyshev_values(&**f, center, half_length);
+            let (result, result_half) = modified_clenshaw_curtis(&values, &cauchy_moments(cc));
+            let abserr = norm_ar(&(&result - &result_half));
+            let rounderr = ROUNDOFF_FACTOR * EPMACH * norm_ar(&result);
+            (result, abserr, rounderr)
+        };
+        // The sub-interval containing c is split in the middle of the larger side of c.
+        let split = |x: f64, y: f64| {
+            let mid = 0.5 * (x + y);
+            if c > x && c <= mid {
+                0.5 * (c + y)
+            } else if c > mid && c < y {
+                0.5 * (x + c)
+            } else {
+                mid
+            }
+        };
+
+        let mut res = qintegrate_serial(&self.qag, &mut rule, &split, &[(a, b)], epsabs, epsrel)?;
+        if let Some(more_info) = res.more_info.as_mut() {
+            more_info.neval = neval;
+        }
+        Ok(res)
+    }
+}
+
+/// Principal values over (-1, 1) of T_k(t) / (t - cc), for k up to [QAWO_CC_DEGREE], by the
+/// recursion of QUADPACK's qc25c.
+fn cauchy_moments(cc: f64) -> Vec<f64> {
+    let mut moments = vec![0.0; QAWO_CC_DEGREE + 1];
+    moments[0] = ((1.0 - cc) / (1.0 + cc)).abs().ln();
+    moments[1] = 2.0 + cc * moments[0];
+    for k in 1..QAWO_CC_DEGREE {
+        // T_(k+1) = 2 t T_k - T_(k-1), and the integral of T_k is 2 / (1 - k^2) for even k.
+        moments[k + 1] = 2.0 * cc * moments[k] - moments[k - 1];
+        if k % 2 == 0 {
+            moments[k + 1] += 4.0 / (1.0 - (k * k) as f64);
+        }
+    }
+    moments
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use crate::qawc::Qawc;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn principal_value() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let qawc = Qawc::new(qag.clone(), 0.5);
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![1.0, x, x.exp()]),
+        };
+        let log = (1.0_f64 / 3.0).ln();
+        let res = qawc.integrate(&f, -1.0, 1.0, 1.0e-12, 0.0).unwrap();
+        assert!((res.result[0] - log).abs() < 1.0e-12);
+        assert!((res.result[1] - (2.0 + 0.5 * log)).abs() < 1.0e-12);
+
+        // The exponential against the subtraction of the singularity.
+        let regular = FnVec {
+            components: Arc::new(|x: f64| {
+                if x == 0.5 {
+                    array![0.5_f64.exp()]
+                } else {
+                    array![(x.exp() - 0.5_f64.exp()) / (x - 0.5)]
+                }
+            }),
+        };
+        let correct_exp = qag
+            .integrate(&regular, -1.0, 1.0, 1.0e-13, 0.0)
+            .unwrap()
+            .result[0]
+            + 0.5_f64.exp() * log;
+        assert!((res.result[2] - correct_exp).abs() < 1.0e-12);
+
+        let res_reversed = qawc.integrate(&f, 1.0, -1.0, 1.0e-12, 0.0).unwrap();
+        assert_eq!(res_reversed.result, -&res.result);
+
+        let res = qawc.integrate(&f, 0.5, 1.0, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
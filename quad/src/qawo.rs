@@ -0,0 +1,534 @@
+use crate::constants::*;
+use crate::errors::QagError;
+use crate::patterson_quad::gauss_legendre;
+use crate::qag::{LimitBehavior, Qag};
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::Array1;
+use std::collections::{BinaryHeap, HashMap};
+use std::f64::consts::PI;
+
+/// Degree of the modified Clenshaw-Curtis rule, whose error is estimated with the nested rule
+/// of half the degree.
+pub const QAWO_CC_DEGREE: usize = 24;
+/// Value of |omega| times the half-length of a sub-interval up to which the Gauss-Kronrod rule
+/// is used instead of the modified Clenshaw-Curtis one.
+pub const QAWO_CC_MIN: f64 = 2.0;
+/// Value of |omega| times the half-length of a sub-interval above which the Chebyshev moments
+/// are computed by forward recursion, stable only if it exceeds the degree.
+pub const QAWO_RECURRENCE_MIN: f64 = 24.0;
+
+/// Oscillating factor of the integrand of [Qawo].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weight {
+    /// sin(omega x).
+    Sin,
+    /// cos(omega x).
+    Cos,
+}
+
+/// Oscillatory weight w(x) integrated by [integrate_oscillatory](Qag::integrate_oscillatory)
+/// through its modified Chebyshev moments.
+///
+/// [Qawo] provides sin(omega x) and cos(omega x), and
+/// [BesselJ0](crate::qag_bessel::BesselJ0) the Bessel function J0(omega x). Other weights
+/// implement this trait, with [weight_moments] for the moments without a closed form.
+pub trait OscillatoryWeight: Sync {
+    /// Value of the weight at 'x'.
+    fn value(&self, x: f64) -> f64;
+
+    /// Angular frequency of the oscillations: on the sub-intervals where its product with the
+    /// half-length is at most [QAWO_CC_MIN] the Gauss-Kronrod rule is used.
+    fn omega(&self) -> f64;
+
+    /// Moments over (-1, 1) of w(center + half_length t) T_k(t), for k up to
+    /// [QAWO_CC_DEGREE].
+    fn moments(&self, center: f64, half_length: f64) -> Vec<f64>;
+}
+
+/// Adaptive integrator of f(x) sin(omega x) or f(x) cos(omega x), as QUADPACK's qawo.
+///
+/// On the sub-intervals where the weight has many oscillations, f is interpolated at the
+/// Chebyshev points and the interpolant is integrated exactly against the weight through its
+/// modified Chebyshev moments, so that the number of evaluations does not grow with 'omega'.
+/// On the other ones the Gauss-Kronrod rule of [key](Qag::key) is applied to the whole
+/// integrand. The bisection is serial, and only [key](Qag::key), [limit](Qag::limit),
+/// [more_info](Qag::more_info), [limit_behavior](Qag::limit_behavior),
+/// [roundoff_warmup](Qag::roundoff_warmup) and [tolerance_logic](Qag::tolerance_logic) of
+/// 'qag' are used. The same algorithm is available for any [OscillatoryWeight] through
+/// [integrate_oscillatory](Qag::integrate_oscillatory).
+#[derive(Clone)]
+pub struct Qawo {
+    pub qag: Qag,
+    /// Angular frequency of the weight.
+    pub omega: f64,
+    pub weight: Weight,
+}
+
+impl Qawo {
+    pub fn new(qag: Qag, omega: f64, weight: Weight) -> Self {
+        Self { qag, omega, weight }
+    }
+
+    /// Adaptive integration of a vector-valued function times the weight over the finite
+    /// interval ('a', 'b').
+    ///
+    /// Infinite bounds are rejected with [Invalid](QagError::Invalid). If b < a the bounds are
+    /// swapped and the result is negated.
+    pub fn integrate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        self.qag
+            .integrate_oscillatory(self, fun, a, b, epsabs, epsrel)
+    }
+}
+
+impl OscillatoryWeight for Qawo {
+    fn value(&self, x: f64) -> f64 {
+        match self.weight {
+            Weight::Sin => (self.omega * x).sin(),
+            Weight::Cos => (self.omega * x).cos(),
+        }
+    }
+
+    fn omega(&self) -> f64 {
+        self.omega
+    }
+
+    fn moments(&self, center: f64, half_length: f64) -> Vec<f64> {
+        let (cos_moments, sin_moments) = chebyshev_moments(self.omega * half_length);
+        // sin(omega x) and cos(omega x) as combinations of cos(p t) and sin(p t), with
+        // x = center + half_length t.
+        let (sin_center, cos_center) = (self.omega * center).sin_cos();
+        let (c, s) = match self.weight {
+            Weight::Sin => (sin_center, cos_center),
+            Weight::Cos => (cos_center, -sin_center),
+        };
+        cos_moments
+            .iter()
+            .zip(&sin_moments)
+            .map(|(mc, ms)| c * mc + s * ms)
+            .collect()
+    }
+}
+
+impl Qag {
+    /// Adaptive integration of a vector-valued function times an [OscillatoryWeight] over the
+    /// finite interval ('a', 'b'), with the algorithm and the parameters of [Qawo].
+    ///
+    /// Infinite bounds or frequency are rejected with [Invalid](QagError::Invalid). If b < a
+    /// the bounds are swapped and the result is negated.
+    pub fn integrate_oscillatory<W>(
+        &self,
+        weight: &W,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError>
+    where
+        W: OscillatoryWeight + ?Sized,
+    {
+        if b < a {
+            let mut res = self.integrate_oscillatory(weight, fun, b, a, epsabs, epsrel)?;
+            res.result.mapv_inplace(|x| -x);
+            return Ok(res);
+        }
+        let omega = weight.omega();
+        if !(a.is_finite() && b.is_finite() && omega.is_finite())
+            || (epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH))
+        {
+            return Err(QagError::Invalid);
+        }
+
+        let f = &fun.components;
+        let weighted = |x: f64| f(x) * weight.value(x);
+        let key = self.key;
+        let mut neval = 0;
+        let mut rule = |x: f64, y: f64| {
+            if (omega * 0.5 * (y - x)).abs() <= QAWO_CC_MIN {
+                neval += key.points();
+                key.quadrature(weighted, x, y)
+            } else {
+                neval += QAWO_CC_DEGREE + 1;
+                qc25(&**f, x, y, weight)
+            }
+        };
+        let mut res = qintegrate_serial(self, &mut rule, &bisect, &[(a, b)], epsabs, epsrel)?;
+        if let Some(more_info) = res.more_info.as_mut() {
+            more_info.neval = neval;
+        }
+        Ok(res)
+    }
+}
+
+/// Serial adaptive bisection of the initial 'intervals', whose estimates are given by 'rule',
+/// with the parameters of 'qag' used by [Qawo]. A sub-interval (x, y) is split at split(x, y).
+///
+/// The number of evaluations of [MoreInfo](crate::qag_integration_result::MoreInfo) is left to
+/// the caller, as it depends on the rules chosen by 'rule'.
+pub(crate) fn qintegrate_serial<R, S>(
+    qag: &Qag,
+    rule: &mut R,
+    split: &S,
+    intervals: &[(f64, f64)],
+    epsabs: f64,
+    epsrel: f64,
+) -> Result<QagIntegrationResult, QagError>
+where
+    R: FnMut(f64, f64) -> (Array1<f64>, f64, f64),
+    S: Fn(f64, f64) -> f64,
+{
+    let tolerance = &qag.tolerance_logic;
+    let mut heap = BinaryHeap::new();
+    let mut interval_cache = HashMap::new();
+    let (mut result, mut abserr, mut rounderr) = (Array1::zeros(0), 0.0, 0.0);
+    for &(x, y) in intervals {
+        let (res, err, round) = rule(x, y);
+        if res.is_empty() {
+            return Err(QagError::EmptyIntegrand);
+        }
+        result = if result.is_empty() {
+            res.clone()
+        } else {
+            result + &res
+        };
+        abserr += err;
+        rounderr += round;
+        heap.push(HeapItem::new((x, y), err));
+        interval_cache.insert((Myf64 { x }, Myf64 { x: y }), res);
+    }
+    let mut errbnd = tolerance.errbnd(epsabs, epsrel, &result);
+
+    let mut last = intervals.len();
+    let (mut iroff1, mut iroff2) = (0, 0);
+    while abserr > errbnd && last < qag.limit {
+        let item = heap.pop().unwrap();
+        let (x, y) = item.interval;
+        if bad_function_flag(x, y) {
+            return Err(QagError::BadFunction);
+        }
+        let old_res = interval_cache
+            .remove(&(Myf64 { x }, Myf64 { x: y }))
+            .ok_or(QagError::MissingInterval { a: x, b: y })?;
+        let mid = split(x, y);
+        let (res1, err1, round1) = rule(x, mid);
+        let (res2, err2, round2) = rule(mid, y);
+        let new_res = &res1 + &res2;
+        let new_abserr = err1 + err2;
+        last += 1;
+        if iroff1_flag(&old_res, &new_res, new_abserr, item.err) {
+            iroff1 += 1;
+        }
+        if last > qag.roundoff_warmup && new_abserr > item.err {
+            iroff2 += 1;
+        }
+        result += &(&new_res - &old_res);
+        abserr += new_abserr - item.err;
+        rounderr += round1 + round2;
+        let depth = item.depth + 1;
+        interval_cache.insert((Myf64 { x }, Myf64 { x: mid }), res1);
+        interval_cache.insert((Myf64 { x: mid }, Myf64 { x: y }), res2);
+        heap.push(HeapItem::new_with_depth((x, mid), err1, depth));
+        heap.push(HeapItem::new_with_depth((mid, y), err2, depth));
+
+        errbnd = tolerance.errbnd(epsabs, epsrel, &result);
+        if abserr <= errbnd {
+            break;
+        }
+        if abserr < rounderr || iroff1 >= IROFF1_THRESHOLD || iroff2 >= IROFF2_THRESHOLD {
+            return Err(QagError::BadTolerance);
+        }
+    }
+
+    let mut failure = None;
+    if abserr > errbnd {
+        match qag.limit_behavior {
+            LimitBehavior::Error => return Err(QagError::MaxIteration),
+            LimitBehavior::ReturnBest => failure = Some(QagError::MaxIteration),
+        }
+    }
+
+    let truncation_err = abserr;
+    abserr += rounderr;
+    let mut res = if qag.more_info {
+        QagIntegrationResult::new_more_info(result, abserr, 0, last, interval_cache, heap)
+    } else {
+        QagIntegrationResult::new(result, abserr)
+    };
+    res.truncation_err = truncation_err;
+    res.roundoff_err = rounderr;
+    if failure.is_none() {
+        res.success_kind = Some(tolerance.success_kind(epsabs, epsrel, &res.result));
+    }
+    res.failure = failure;
+    Ok(res)
+}
+
+/// Midpoint of ('x', 'y'), the split of [qintegrate_serial] for the bisection.
+pub(crate) fn bisect(x: f64, y: f64) -> f64 {
+    0.5 * (x + y)
+}
+
+/// Integral of 'f' times the weight over ('a', 'b') with the modified Clenshaw-Curtis rule of
+/// degree [QAWO_CC_DEGREE], its error estimate and its roundoff error, as QUADPACK's qc25f.
+fn qc25<F, W>(f: F, a: f64, b: f64, weight: &W) -> (Array1<f64>, f64, f64)
+where
+    F: Fn(f64) -> Array1<f64>,
+    W: OscillatoryWeight + ?Sized,
+{
+    let (center, half_length) = (0.5 * (a + b), 0.5 * (b - a));
+    let moments = weight.moments(center, half_length);
+
+    let values = chebyshev_values(f, center, half_length);
+    let (result, result_half) = modified_clenshaw_curtis(&values, &moments);
+    let abserr = half_length * norm_ar(&(&result - &result_half));
+    let resabs = 2.0 * half_length * values.iter().map(norm_ar).sum::<f64>() / values.len() as f64;
+    (
+        result * half_length,
+        abserr,
+        ROUNDOFF_FACTOR * EPMACH * resabs,
+    )
+}
+
+/// Values of 'f' at the Chebyshev points cos(j pi / [QAWO_CC_DEGREE]) of the interval with
+/// 'center' and 'half_length'.
+pub(crate) fn chebyshev_values<F>(f: F, center: f64, half_length: f64) -> Vec<Array1<f64>>
+where
+    F: Fn(f64) -> Array1<f64>,
+{
+    let n = QAWO_CC_DEGREE;
+    (0..=n)
+        .map(|j| f(center + half_length * (j as f64 * PI / n as f64).cos()))
+        .collect()
+}
+
+/// Integrals over (-1, 1) of the Chebyshev interpolants of degree [QAWO_CC_DEGREE] and of half
+/// the degree of the 'values' at the Chebyshev points, given the 'moments' of the weight
+/// against T_k.
+pub(crate) fn modified_clenshaw_curtis(
+    values: &[Array1<f64>],
+    moments: &[f64],
+) -> (Array1<f64>, Array1<f64>) {
+    let n = QAWO_CC_DEGREE;
+    // The rule of degree n / 2 uses every other Chebyshev point.
+    let integral = |degree: usize| {
+        let stride = n / degree;
+        let mut result = Array1::zeros(values[0].len());
+        for j in 0..=degree {
+            let mut w: f64 = (0..=degree)
+                .map(|k| {
+                    let halve = if k == 0 || k == degree { 0.5 } else { 1.0 };
+                    halve * (j as f64 * k as f64 * PI / degree as f64).cos() * moments[k]
+                })
+                .sum();
+            w *= 2.0 / degree as f64;
+            if j == 0 || j == degree {
+                w *= 0.5;
+            }
+            result.scaled_add(w, &values[j * stride]);
+        }
+        result
+    };
+    (integral(n), integral(n / 2))
+}
+
+/// Moments of w(t) T_k(t) over (-1, 1), for k up to [QAWO_CC_DEGREE], of a weight w
+/// oscillating with angular frequency 'p' in t.
+///
+/// They are computed with a composite Gauss-Legendre rule of 32 points per panel, with enough
+/// panels to resolve the oscillations, so only w is evaluated. This gives the
+/// [moments](OscillatoryWeight::moments) of the weights without a closed form.
+pub fn weight_moments<W>(w: W, p: f64) -> Vec<f64>
+where
+    W: Fn(f64) -> f64,
+{
+    let n = QAWO_CC_DEGREE;
+    let panels = 1 + (p.abs() / 4.0).ceil() as usize;
+    let rule = gauss_legendre(32);
+    let width = 2.0 / panels as f64;
+    let mut moments = vec![0.0; n + 1];
+    for panel in 0..panels {
+        let center = -1.0 + width * (panel as f64 + 0.5);
+        for &(u, rule_weight) in &rule {
+            let t = center + 0.5 * width * u;
+            let wt = 0.5 * width * rule_weight * w(t);
+            // T_(-1) = T_1 starts the recursion T_(k+1) = 2 t T_k - T_(k-1).
+            let (mut previous, mut chebyshev) = (t, 1.0);
+            for moment in moments.iter_mut() {
+                *moment += wt * chebyshev;
+                let next = 2.0 * t * chebyshev - previous;
+                previous = chebyshev;
+                chebyshev = next;
+            }
+        }
+    }
+    moments
+}
+
+/// Moments of T_k(t) cos(p t) and T_k(t) sin(p t) over (-1, 1), for k up to
+/// [QAWO_CC_DEGREE].
+///
+/// They are computed with a Gauss-Legendre rule if |p| is at most [QAWO_RECURRENCE_MIN],
+/// otherwise by the forward recursion obtained by integrating by parts, where the moments of
+/// the odd cosines and of the even sines vanish.
+fn chebyshev_moments(p: f64) -> (Vec<f64>, Vec<f64>) {
+    let n = QAWO_CC_DEGREE;
+    let mut cos_moments = vec![0.0; n + 1];
+    let mut sin_moments = vec![0.0; n + 1];
+    if p.abs() <= QAWO_RECURRENCE_MIN {
+        for (t, w) in gauss_legendre(64) {
+            let (sin_pt, cos_pt) = (p * t).sin_cos();
+            let (mut previous, mut chebyshev) = (0.0, 1.0);
+            for k in 0..=n {
+                cos_moments[k] += w * chebyshev * cos_pt;
+                sin_moments[k] += w * chebyshev * sin_pt;
+                let next = if k == 0 {
+                    t
+                } else {
+                    2.0 * t * chebyshev - previous
+                };
+                previous = chebyshev;
+                chebyshev = next;
+            }
+        }
+        return (cos_moments, sin_moments);
+    }
+
+    let (sin_p, cos_p) = p.sin_cos();
+    cos_moments[0] = 2.0 * sin_p / p;
+    sin_moments[1] = 2.0 * (sin_p - p * cos_p) / (p * p);
+    cos_moments[2] =
+        4.0 * (sin_p / p + 2.0 * cos_p / (p * p) - 2.0 * sin_p / (p * p * p)) - cos_moments[0];
+    for k in 2..n {
+        let (kp, km) = ((k + 1) as f64, (k - 1) as f64);
+        if k % 2 == 1 {
+            cos_moments[k + 1] = kp / p
+                * (2.0 * sin_p * (1.0 / kp - 1.0 / km) - 2.0 * sin_moments[k])
+                + kp / km * cos_moments[k - 1];
+        } else {
+            sin_moments[k + 1] = -kp / p
+                * (2.0 * cos_p * (1.0 / kp - 1.0 / km) - 2.0 * cos_moments[k])
+                + kp / km * sin_moments[k - 1];
+        }
+    }
+    (cos_moments, sin_moments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chebyshev_moments;
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::patterson_quad::gauss_legendre;
+    use crate::qag::Qag;
+    use crate::qawo::{weight_moments, OscillatoryWeight, Qawo, Weight};
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn moments() {
+        // The forward recursion against a Gauss-Legendre rule, accurate for this p.
+        let p = 40.0;
+        let (cos_moments, sin_moments) = chebyshev_moments(p);
+        for k in 0..cos_moments.len() {
+            let (mut c, mut s) = (0.0, 0.0);
+            for (t, w) in gauss_legendre(128) {
+                let chebyshev = (k as f64 * f64::acos(t)).cos();
+                c += w * chebyshev * (p * t).cos();
+                s += w * chebyshev * (p * t).sin();
+            }
+            assert!((cos_moments[k] - c).abs() < 1.0e-12);
+            assert!((sin_moments[k] - s).abs() < 1.0e-12);
+        }
+    }
+
+    #[test]
+    fn exp_polynomial() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let omega: f64 = 50.0;
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(-x).exp(), x * x]),
+        };
+        let decay = 1.0 - (-2.0 * PI).exp();
+        let correct_sin = [
+            omega * decay / (1.0 + omega * omega),
+            -4.0 * PI * PI / omega,
+        ];
+        let correct_cos = [decay / (1.0 + omega * omega), 4.0 * PI / (omega * omega)];
+
+        for (weight, correct_result) in [(Weight::Sin, correct_sin), (Weight::Cos, correct_cos)] {
+            let qawo = Qawo::new(qag.clone(), omega, weight);
+            let res = qawo.integrate(&f, 0.0, 2.0 * PI, 1.0e-12, 0.0).unwrap();
+            for (x, correct) in res.result.iter().zip(correct_result) {
+                assert!((x - correct).abs() < 1.0e-12);
+            }
+            assert!(res.truncation_err <= 1.0e-12);
+            let res_reversed = qawo.integrate(&f, 2.0 * PI, 0.0, 1.0e-12, 0.0).unwrap();
+            assert_eq!(res_reversed.result, -&res.result);
+        }
+
+        let qawo = Qawo::new(qag, omega, Weight::Sin);
+        let res = qawo.integrate(&f, 0.0, f64::INFINITY, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+
+    #[test]
+    fn custom_weight() {
+        // cos(omega x) without the closed form of its moments.
+        struct Cos {
+            omega: f64,
+        }
+        impl OscillatoryWeight for Cos {
+            fn value(&self, x: f64) -> f64 {
+                (self.omega * x).cos()
+            }
+            fn omega(&self) -> f64 {
+                self.omega
+            }
+            fn moments(&self, center: f64, half_length: f64) -> Vec<f64> {
+                let omega = self.omega;
+                weight_moments(
+                    |t| (omega * (center + half_length * t)).cos(),
+                    omega * half_length,
+                )
+            }
+        }
+
+        for p in [1.0, 40.0, 500.0] {
+            let (cos_moments, _) = chebyshev_moments(p);
+            let moments = weight_moments(|t| (p * t).cos(), p);
+            for (x, correct) in moments.iter().zip(cos_moments) {
+                assert!((x - correct).abs() < 1.0e-12);
+            }
+        }
+
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![(-x).exp(), x * x]),
+        };
+        let omega = 50.0;
+        let res = qag
+            .integrate_oscillatory(&Cos { omega }, &f, 0.0, 2.0 * PI, 1.0e-12, 0.0)
+            .unwrap();
+        let res_qawo = Qawo::new(qag, omega, Weight::Cos)
+            .integrate(&f, 0.0, 2.0 * PI, 1.0e-12, 0.0)
+            .unwrap();
+        for (x, correct) in res.result.iter().zip(&res_qawo.result) {
+            assert!((x - correct).abs() < 1.0e-12);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::constants::*;
+use crate::errors::QagError;
+use crate::qag::Qag;
+use crate::qag_integration_result::QagIntegrationResult;
+use crate::qawo::{
+    bisect, chebyshev_values, modified_clenshaw_curtis, qintegrate_serial, QAWO_CC_DEGREE,
+};
+
+/// Adaptive integrator of f(x) (x - a)^alpha (b - x)^beta v(x), with the logarithmic factor
+/// v(x) selected by 'integr', as QUADPACK's qaws.
+///
+/// The interval is bisected at the start, and on the sub-intervals with an endpoint at 'a' or
+/// 'b' the singular factor of that endpoint is integrated exactly against the Chebyshev
+/// interpolant of the rest of the integrand. On the other ones the Gauss-Kronrod rule of
+/// [key](Qag::key) is applied to the whole integrand. The bisection is serial, and uses the
+/// same parameters of 'qag' as [Qawo](crate::qawo::Qawo).
+#[derive(Clone)]
+pub struct Qaws {
+    pub qag: Qag,
+    /// Exponent of (x - a), greater than -1.
+    pub alpha: f64,
+    /// Exponent of (b - x), greater than -1.
+    pub beta: f64,
+    /// Logarithmic factor.
+    pub integr: AlgebraicLog,
+}
+
+/// Logarithmic factor v(x) of the weight of [Qaws], numbered from 1 to 4 in QUADPACK.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlgebraicLog {
+    /// v(x) = 1.
+    None,
+    /// v(x) = log(x - a).
+    LogA,
+    /// v(x) = log(b - x).
+    LogB,
+    /// v(x) = log(x - a) log(b - x).
+    LogAB,
+}
+
+impl AlgebraicLog {
+    /// Whether v(x) contains log(x - a).
+    fn log_a(self) -> bool {
+        matches!(self, AlgebraicLog::LogA | AlgebraicLog::LogAB)
+    }
+
+    /// Whether v(x) contains log(b - x).
+    fn log_b(self) -> bool {
+        matches!(self, AlgebraicLog::LogB | AlgebraicLog::LogAB)
+    }
+}
+
+/// Modified Chebyshev moments over (-1, 1) of the endpoint singularities, as computed by
+/// QUADPACK's qmomo.
+///
+/// They do not depend on the length of the sub-interval, so they are computed once per
+/// integration and shared by all the sub-intervals with an endpoint at 'a' or 'b'.
+struct QawsMoments {
+    /// Moments of (1 + t)^alpha T_k(t).
+    ri: Vec<f64>,
+    /// Moments of (1 - t)^beta T_k(t).
+    rj: Vec<f64>,
+    /// Moments of (1 + t)^alpha log((1 + t) / 2) T_k(t).
+    rg: Vec<f64>,
+    /// Moments of (1 - t)^beta log((1 - t) / 2) T_k(t).
+    rh: Vec<f64>,
+}
+
+impl QawsMoments {
+    fn new(alpha: f64, beta: f64) -> Self {
+        let (ri, rg) = algebraic_log_moments(alpha);
+        let (mut rj, mut rh) = algebraic_log_moments(beta);
+        // T_k(-t) = (-1)^k T_k(t).
+        for k in (1..rj.len()).step_by(2) {
+            rj[k] = -rj[k];
+            rh[k] = -rh[k];
+        }
+        Self { ri, rj, rg, rh }
+    }
+}
+
+/// Moments over (-1, 1) of (1 + t)^alpha T_k(t) and of (1 + t)^alpha log((1 + t) / 2) T_k(t),
+/// for k up to [QAWO_CC_DEGREE], by the recursions of QUADPACK's qmomo.
+fn algebraic_log_moments(alpha: f64) -> (Vec<f64>, Vec<f64>) {
+    let n = QAWO_CC_DEGREE + 1;
+    let (alfp1, alfp2) = (alpha + 1.0, alpha + 2.0);
+    let ralf = 2.0_f64.powf(alfp1);
+    let mut ri = vec![0.0; n];
+    let mut rg = vec![0.0; n];
+    ri[0] = ralf / alfp1;
+    ri[1] = ri[0] * alpha / alfp2;
+    rg[0] = -ri[0] / alfp1;
+    rg[1] = -2.0 * ralf / (alfp2 * alfp2) - rg[0];
+    for k in 2..n {
+        let (an, anm1) = (k as f64, (k - 1) as f64);
+        ri[k] = -(ralf + an * (an - alfp2) * ri[k - 1]) / (anm1 * (an + alfp1));
+        rg[k] = -(an * (an - alfp2) * rg[k - 1] - an * ri[k - 1] + anm1 * ri[k])
+            / (anm1 * (an + alfp1));
+    }
+    (ri, rg)
+}
+
+impl Qaws {
+    pub fn new(qag: Qag, alpha: f64, beta: f64, integr: AlgebraicLog) -> Self {
+        Self {
+            qag,
+            alpha,
+            beta,
+            integr,
+        }
+    }
+
+    /// Adaptive integration of a vector-valued function times the weight over the finite
+    /// interval ('a', 'b').
+    ///
+    /// As the weight depends on the bounds, they are not swapped: b <= a, infinite bounds and
+    /// exponents not greater than -1 are rejected with [Invalid](QagError::Invalid).
+    pub fn integrate(
+        &self,
+        fun: &FnVec,
+        a: f64,
+        b: f64,
+        epsabs: f64,
+        epsrel: f64,
+    ) -> Result<QagIntegrationResult, QagError> {
+        let valid =
+            a.is_finite() && b.is_finite() && a < b && self.alpha > -1.0 && self.beta > -1.0;
+        if !valid || (epsabs <= 0.0 && epsrel < 0.5e-28_f64.max(50.0 * EPMACH)) {
+            return Err(QagError::Invalid);
+        }
+
+        let f = &fun.components;
+        let (alpha, beta, integr) = (self.alpha, self.beta, self.integr);
+        let (log_a, log_b) = (integr.log_a(), integr.log_b());
+        let weighted = |x: f64| {
+            let mut w = (x - a).powf(alpha) * (b - x).powf(beta);
+            if log_a {
+                w *= (x - a).ln();
+            }
+            if log_b {
+                w *= (b - x).ln();
+            }
+            f(x) * w
+        };
+        let moments = QawsMoments::new(alpha, beta);
+        let key = self.qag.key;
+        let mut neval = 0;
+        let mut rule = |x: f64, y: f64| {
+            if x != a && y != b {
+                neval += key.points();
+                return key.quadrature(weighted, x, y);
+            }
+            neval += QAWO_CC_DEGREE + 1;
+            let (center, half_length) = (0.5 * (x + y), 0.5 * (y - x));
+            // The factors of the other endpoint are regular and interpolated with f.
+            let (values, exponent, log, (r, r_log)) = if x == a {
+                let values = chebyshev_values(
+                    |t| {
+                        let mut w = (b - t).powf(beta);
+                        if log_b {
+                            w *= (b - t).ln();
+                        }
+                        f(t) * w
+                    },
+                    center,
+                    half_length,
+                );
+                (values, alpha, log_a, (&moments.ri, &moments.rg))
+            } else {
+                let values = chebyshev_values(
+                    |t| {
+                        let mut w = (t - a).powf(alpha);
+                        if log_a {
+                            w *= (t - a).ln();
+                        }
+                        f(t) * w
+                    },
+                    center,
+                    half_length,
+                );
+                (values, beta, log_b, (&moments.rj, &moments.rh))
+            };
+            let (mut result, mut result_half) = modified_clenshaw_curtis(&values, r);
+            // log(x - a) = log(2 h) + log((1 + t) / 2), and the same for b - x.
+            if log {
+                let (log_result, log_result_half) = modified_clenshaw_curtis(&values, r_log);
+                let log_length = (2.0 * half_length).ln();
+                result = result * log_length + log_result;
+                result_half = result_half * log_length + log_result_half;
+            }
+            let factor = half_length.powf(exponent + 1.0);
+            let abserr = factor * norm_ar(&(&result - &result_half));
+            let result = result * factor;
+            let rounderr = ROUNDOFF_FACTOR * EPMACH * norm_ar(&result);
+            (result, abserr, rounderr)
+        };
+
+        let mid = 0.5 * (a + b);
+        let mut res = qintegrate_serial(
+            &self.qag,
+            &mut rule,
+            &bisect,
+            &[(a, mid), (mid, b)],
+            epsabs,
+            epsrel,
+        )?;
+        if let Some(more_info) = res.more_info.as_mut() {
+            more_info.neval = neval;
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::qag::Qag;
+    use crate::qaws::{AlgebraicLog, Qaws};
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn log_singularities() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![1.0, x]),
+        };
+        // (alpha, beta, integr) and the integrals of the weight and of x times the weight.
+        let cases = [
+            ((-0.5, 0.0, AlgebraicLog::LogA), [-4.0, -4.0 / 9.0]),
+            ((0.0, -0.5, AlgebraicLog::LogB), [-4.0, -32.0 / 9.0]),
+            (
+                (0.0, 0.0, AlgebraicLog::LogAB),
+                [2.0 - PI * PI / 6.0, 1.0 - PI * PI / 12.0],
+            ),
+            ((-0.5, -0.5, AlgebraicLog::None), [PI, 0.5 * PI]),
+        ];
+        for ((alpha, beta, integr), correct_result) in cases {
+            let qaws = Qaws::new(qag.clone(), alpha, beta, integr);
+            let res = qaws.integrate(&f, 0.0, 1.0, 1.0e-12, 0.0).unwrap();
+            for (x, correct) in res.result.iter().zip(correct_result) {
+                assert!((x - correct).abs() < 1.0e-12);
+            }
+            assert!(res.more_info.unwrap().last < 10);
+        }
+
+        let qaws = Qaws::new(qag.clone(), -1.0, 0.0, AlgebraicLog::None);
+        let res = qaws.integrate(&f, 0.0, 1.0, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+        let qaws = Qaws::new(qag, -0.5, 0.0, AlgebraicLog::LogA);
+        let res = qaws.integrate(&f, 1.0, 0.0, 1.0e-12, 0.0);
+        assert_eq!(res.unwrap_err(), QagError::Invalid);
+    }
+}
@@ -1,123 +1,365 @@
 use crate::constants::*;
-use ndarray::{Array1, Axis};
+use crate::qag::Values;
+use ndarray::{Array1, ScalarOperand};
+use num_traits::Float;
+use std::ops::{AddAssign, MulAssign};
+/// Centre of the interval ('a', 'b') and the map from a node x of (0, 1) to the abscissae
+/// (centre - h x, centre + h x), where h is the half-length.
+///
+/// Far from the origin, when |centre| > [ABSCISSA_SHIFT_RATIO] h, the centre rounding would shift
+/// all the abscissae by the same amount, up to half a unit in the last place of the centre,
+/// which biases the integral. The abscissae are then computed relative to the bounds, as
+/// a + h (1 - x) and b - h (1 - x), so that every one of them is rounded once and they are
+/// symmetric within the interval.
+pub fn abscissae<T: Float>(a: T, b: T) -> (T, impl Fn(T) -> (T, T)) {
+    let half = T::from(0.5).unwrap();
+    let hlgth = half * (b - a);
+    let centr = half * (b + a);
+    let shifted = centr.abs() > T::from(ABSCISSA_SHIFT_RATIO).unwrap() * hlgth.abs();
+    let centre = if shifted { a + hlgth } else { centr };
+    let abscissa = move |x: T| {
+        if shifted {
+            (a + hlgth * (T::one() - x), b - hlgth * (T::one() - x))
+        } else {
+            (centr - hlgth * x, centr + hlgth * x)
+        }
+    };
+    (centre, abscissa)
+}
 /// Generates the various Gauss-Kronrod quadratures by giving their respective nodes 'xgk'
 /// and weights 'wgk' and 'wg'.
-pub fn qk_quadrature<const M: usize, F>(
+///
+/// The rule is evaluated in the floating point type of the bounds, e.g. f32 for reproducibility
+/// studies in single precision: the tables are converted to it with T::from, and the roundoff
+/// and underflow thresholds are its epsilon and smallest positive value. The integrand may
+/// return any [Values] of it, e.g. a SmallArray with the smallvec feature.
+pub fn qk_quadrature<T, V, const M: usize, F>(
+    f: F,
+    a: T,
+    b: T,
+    xgk: &[f64; M],
+    wgk: &[f64],
+    wg: &[f64],
+) -> (Array1<T>, T, T)
+where
+    T: Float + AddAssign + MulAssign,
+    V: Values<T>,
+    F: Fn(T) -> V,
+{
+    let (result, abserr, round_error, _, _, _) = qk_quadrature_all(f, a, b, xgk, wgk, wg);
+    (result, abserr, round_error)
+}
+/// Same as [qk_quadrature], but also returning the estimate of the lower order Gauss rule.
+pub fn qk_quadrature_with_gauss<T, const M: usize, F>(
+    f: F,
+    a: T,
+    b: T,
+    xgk: &[f64; M],
+    wgk: &[f64],
+    wg: &[f64],
+) -> (Array1<T>, T, T, Array1<T>)
+where
+    T: Float + ScalarOperand + AddAssign + MulAssign,
+    F: Fn(T) -> Array1<T>,
+{
+    let (result, abserr, round_error, resg, _, _) = qk_quadrature_all(f, a, b, xgk, wgk, wg);
+    (result, abserr, round_error, resg)
+}
+/// Same as [qk_quadrature], but also returning the Kronrod estimate of the integral of the
+/// absolute value of every component.
+pub fn qk_quadrature_with_resabs<const M: usize, F>(
+    f: F,
+    a: f64,
+    b: f64,
+    xgk: &[f64; M],
+    wgk: &[f64],
+    wg: &[f64],
+) -> (Array1<f64>, f64, f64, Array1<f64>)
+where
+    F: Fn(f64) -> Array1<f64>,
+{
+    let (result, abserr, round_error, _, resabs, _) = qk_quadrature_all(f, a, b, xgk, wgk, wg);
+    (result, abserr, round_error, resabs)
+}
+/// Same as [qk_quadrature], but also returning a heuristic score between 0 and 1 of the
+/// reliability of the error estimate.
+///
+/// The score is the product of two factors. The first is the ratio, capped at 1, between the
+/// error estimate and the raw difference between the Kronrod and the Gauss results: the
+/// scaling of the raw difference makes the estimate smaller than it when the rule is assumed to
+/// be in its asymptotic regime, which is optimistic if it is not. The second is
+/// 1 - resasc / (2 resabs), where resasc is the integral of the deviation of f from its mean:
+/// it lowers the score for integrands oscillating or changing sign over the interval, for which
+/// the estimate is less trustworthy than for nearly constant ones.
+pub fn qk_quadrature_with_reliability<const M: usize, F>(
     f: F,
     a: f64,
     b: f64,
     xgk: &[f64; M],
     wgk: &[f64],
     wg: &[f64],
-) -> (Array1<f64>, f64, f64)
+) -> (Array1<f64>, f64, f64, f64)
 where
     F: Fn(f64) -> Array1<f64>,
 {
-    let hlgth: f64 = 0.5 * (b - a);
-    let dhlgth: f64 = hlgth.abs();
-    let centr: f64 = 0.5 * (b + a);
+    let (result, abserr, round_error, resg, resabs, resasc) =
+        qk_quadrature_all(f, a, b, xgk, wgk, wg);
+    let raw = norm_ar(&(&result - &resg));
+    let scaling = if raw > 0.0 {
+        (abserr / raw).min(1.0)
+    } else {
+        1.0
+    };
+    let (resabs, resasc) = (norm_ar(&resabs), norm_ar(&resasc));
+    let variation = if resabs > 0.0 {
+        1.0 - 0.5 * (resasc / resabs).min(1.0)
+    } else {
+        1.0
+    };
+    (result, abserr, round_error, scaling * variation)
+}
+/// Gauss-Kronrod quadrature returning the result, the error, the roundoff error, the Gauss
+/// estimate, the integral of the absolute value and the one of the deviation from the mean.
+///
+/// The evaluations and the sums are kept in the value type of the integrand, so that e.g. for a
+/// SmallArray they stay on the stack.
+fn qk_quadrature_all<T, V, const M: usize, F>(
+    f: F,
+    a: T,
+    b: T,
+    xgk: &[f64; M],
+    wgk: &[f64],
+    wg: &[f64],
+) -> (Array1<T>, T, T, V, V, V)
+where
+    T: Float + AddAssign + MulAssign,
+    V: Values<T>,
+    F: Fn(T) -> V,
+{
+    #[cfg(feature = "profiling")]
+    crate::profiling::count_evaluations(2 * M + 1);
+    let c = |x: f64| T::from(x).unwrap();
+    let hlgth: T = c(0.5) * (b - a);
+    let dhlgth: T = hlgth.abs();
+    let (centr, abscissa) = abscissae(a, b);
     let fc = f(centr);
     let dim = fc.len();
-    let mut fv1 = Array1::<f64>::zeros(0);
-    let mut fv2 = Array1::<f64>::zeros(0);
-    let mut resg = {
+    let mut fv1: [V; M] = std::array::from_fn(|_| V::zeros(0));
+    let mut fv2: [V; M] = std::array::from_fn(|_| V::zeros(0));
+    let mut resg = V::zeros(dim);
+    let mut resk = V::zeros(dim);
+    let mut resabs = V::zeros(dim);
+    for k in 0..dim {
         if M % 2 == 1 {
-            &fc * wg[(M + 1) / 2 - 1]
-        } else {
-            Array1::<f64>::zeros(dim)
+            *resg.component_mut(k) = fc.component(k) * c(wg[M / 2]);
         }
-    };
-    let mut resk = &fc * wgk[M];
-    let mut resabs = resk.map(|x| x.abs());
+        *resk.component_mut(k) = fc.component(k) * c(wgk[M]);
+        *resabs.component_mut(k) = resk.component(k).abs();
+    }
 
     for j in 1..M / 2 + 1 {
         let jtw1 = 2 * j - 1;
         let jtw2 = 2 * j;
 
-        let absc1 = hlgth * xgk[jtw1 - 1];
-        let absc2 = hlgth * xgk[jtw2 - 1];
-
-        let f11 = f(centr - absc1);
-        let f12 = f(centr - absc2);
-        let f21 = f(centr + absc1);
-        let f22 = f(centr + absc2);
+        let (x11, x21) = abscissa(c(xgk[jtw1 - 1]));
+        let (x12, x22) = abscissa(c(xgk[jtw2 - 1]));
 
-        fv1.append(Axis(0), f11.view());
-        fv1.append(Axis(0), f12.view());
-        fv2.append(Axis(0), f21.view());
-        fv2.append(Axis(0), f22.view());
-
-        //resabs += &(&(f11.map(|x| x.abs()) + &(f21.map(|x| x.abs()) ) ) * wgk[jtw1 -1]);
-        //resabs += &(&(f12.map(|x| x.abs()) + &(f22.map(|x| x.abs()) ) ) * wgk[jtw1 -1]);
+        fv1[jtw1 - 1] = f(x11);
+        fv1[jtw2 - 1] = f(x12);
+        fv2[jtw1 - 1] = f(x21);
+        fv2[jtw2 - 1] = f(x22);
 
         for k in 0..dim {
-            resabs[k] += wgk[jtw1 - 1] * (f11[k].abs() + f21[k].abs())
-                + wgk[jtw2 - 1] * (f12[k].abs() + f22[k].abs());
-        }
+            let (f11, f12) = (fv1[jtw1 - 1].component(k), fv1[jtw2 - 1].component(k));
+            let (f21, f22) = (fv2[jtw1 - 1].component(k), fv2[jtw2 - 1].component(k));
 
-        let fsum1 = f11 + f21;
-        let fsum2 = f12 + f22;
+            *resabs.component_mut(k) += c(wgk[jtw1 - 1]) * (f11.abs() + f21.abs())
+                + c(wgk[jtw2 - 1]) * (f12.abs() + f22.abs());
 
-        resg += &(&fsum2 * wg[j - 1]);
-        resk += &(fsum1 * wgk[jtw1 - 1]);
-        resk += &(fsum2 * wgk[jtw2 - 1]);
-    }
-
-    if M / 2 != (M + 1) / 2 {
-        let jtw1 = M;
-        let absc = hlgth * xgk[jtw1 - 1];
-        let f1 = f(centr - absc);
-        let f2 = f(centr + absc);
-        fv1.append(Axis(0), f1.view());
-        fv2.append(Axis(0), f2.view());
+            let fsum1 = f11 + f21;
+            let fsum2 = f12 + f22;
 
-        for k in 0..dim {
-            resabs[k] += wgk[jtw1 - 1] * (f1[k].abs() + f2[k].abs());
+            *resg.component_mut(k) += fsum2 * c(wg[j - 1]);
+            *resk.component_mut(k) += fsum1 * c(wgk[jtw1 - 1]);
+            *resk.component_mut(k) += fsum2 * c(wgk[jtw2 - 1]);
         }
-
-        resk += &((&f1 + &f2) * wgk[jtw1 - 1]);
     }
 
-    let reskh = &resk * 0.5;
-
-    let mut resasc = (&fc - &reskh).map(|x| x.abs() * wgk[M]);
+    if M % 2 == 1 {
+        let jtw1 = M;
+        let (x1, x2) = abscissa(c(xgk[jtw1 - 1]));
+        fv1[jtw1 - 1] = f(x1);
+        fv2[jtw1 - 1] = f(x2);
 
-    for j in 1..M + 1 {
         for k in 0..dim {
-            resasc[k] += wgk[j - 1]
-                * ((fv1[(j - 1) * dim + k] - reskh[k]).abs()
-                    + (fv2[(j - 1) * dim + k] - reskh[k]).abs());
+            let (f1, f2) = (fv1[jtw1 - 1].component(k), fv2[jtw1 - 1].component(k));
+            *resabs.component_mut(k) += c(wgk[jtw1 - 1]) * (f1.abs() + f2.abs());
+            *resk.component_mut(k) += (f1 + f2) * c(wgk[jtw1 - 1]);
         }
     }
 
-    let result = &resk * hlgth;
-
-    resabs *= dhlgth;
-    resasc *= dhlgth;
-
-    let mut abserr = 0.0;
-    let mut resabs_scalar = 0.0;
-    let mut resasc_scalar = 0.0;
+    let mut resasc = V::zeros(dim);
+    let mut abserr = T::zero();
+    let mut resabs_scalar = T::zero();
+    let mut resasc_scalar = T::zero();
 
     for k in 0..dim {
-        abserr += (((resk[k] - resg[k]) * hlgth).abs()).powi(2);
-        resabs_scalar += resabs[k].powi(2);
-        resasc_scalar += resasc[k].powi(2);
+        let reskh = resk.component(k) * c(0.5);
+        let mut deviation = (fc.component(k) - reskh).abs() * c(wgk[M]);
+        for j in 0..M {
+            deviation += c(wgk[j])
+                * ((fv1[j].component(k) - reskh).abs() + (fv2[j].component(k) - reskh).abs());
+        }
+        *resasc.component_mut(k) = deviation * dhlgth;
+        *resabs.component_mut(k) *= dhlgth;
+
+        abserr += (((resk.component(k) - resg.component(k)) * hlgth).abs()).powi(2);
+        resabs_scalar += resabs.component(k).powi(2);
+        resasc_scalar += resasc.component(k).powi(2);
     }
 
     abserr = abserr.sqrt();
     resabs_scalar = resabs_scalar.sqrt();
     resasc_scalar = resasc_scalar.sqrt();
 
-    if resasc_scalar != 0.0 && abserr != 0.0 {
-        abserr = resasc_scalar * 1.0_f64.min((200.0 * abserr / resasc_scalar).powf(1.5));
+    if resasc_scalar != T::zero() && abserr != T::zero() {
+        abserr = resasc_scalar * T::one().min((c(200.0) * abserr / resasc_scalar).powf(c(1.5)));
     }
 
-    let round_error = 50.0 * EPMACH * resabs_scalar;
+    let round_error = c(ROUNDOFF_FACTOR) * T::epsilon() * resabs_scalar;
 
-    if round_error > UFLOW {
+    if round_error > T::min_positive_value() {
         abserr = abserr.max(round_error);
     }
 
-    (result, abserr, round_error)
+    let result = Array1::from(resk.scaled(hlgth).to_vec());
+    (
+        result,
+        abserr,
+        round_error,
+        resg.scaled(hlgth),
+        resabs,
+        resasc,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qk::qk_quadrature;
+    use crate::qk15::qk15_quadrature;
+    use crate::qk21::qk21_quadrature;
+    use crate::qk31::qk31_quadrature;
+    use crate::qk41::qk41_quadrature;
+    use crate::qk51::qk51_quadrature;
+    use crate::qk61::qk61_quadrature;
+    use crate::rules::{WG21, WGK21, XGK21};
+    use ndarray::{array, Array1};
+    use std::f64::consts::PI;
+
+    type Integrand = Box<dyn Fn(f64) -> Array1<f64>>;
+    type Rule = fn(Integrand, f64, f64) -> (Array1<f64>, f64, f64);
+
+    /// Legendre polynomial P_n(x).
+    fn legendre(x: f64, n: i32) -> f64 {
+        let (mut p, mut p_prev) = (1.0, 0.0);
+        for k in 0..n {
+            let k = k as f64;
+            (p, p_prev) = (((2.0 * k + 1.0) * x * p - k * p_prev) / (k + 1.0), p);
+        }
+        p
+    }
+
+    #[test]
+    fn polynomial_exactness() {
+        // The Kronrod rule of 2n + 1 points is exact up to degree 3n + 1, or 3n + 2 for odd n.
+        let rules: [(Rule, i32); 6] = [
+            (qk15_quadrature::<Integrand>, 23),
+            (qk21_quadrature::<Integrand>, 31),
+            (qk31_quadrature::<Integrand>, 47),
+            (qk41_quadrature::<Integrand>, 61),
+            (qk51_quadrature::<Integrand>, 77),
+            (qk61_quadrature::<Integrand>, 91),
+        ];
+        for (rule, degree) in rules {
+            for k in 0..=degree {
+                let (a, b) = (-0.5, 1.0);
+                let (result, _, _) = rule(Box::new(move |x: f64| array![x.powi(k)]), a, b);
+                let correct = (b.powi(k + 1) - a.powi(k + 1)) / (k + 1) as f64;
+                assert!(
+                    (result[0] - correct).abs() <= 16.0 * f64::EPSILON * correct.abs(),
+                    "degree {} with the rule exact up to {}",
+                    k,
+                    degree
+                );
+            }
+            // The monomials of high degree are too close to the lower ones for the error to be
+            // visible in double precision, while it is for the Legendre polynomial.
+            let k = degree + 1;
+            let (result, _, _) = rule(Box::new(move |x: f64| array![legendre(x, k)]), -1.0, 1.0);
+            assert!(result[0].abs() > 1.0e-6, "degree {} is exact", k);
+        }
+    }
+
+    #[test]
+    fn large_bounds() {
+        let rules: [Rule; 6] = [
+            |f, a, b| qk15_quadrature(f, a, b),
+            |f, a, b| qk21_quadrature(f, a, b),
+            |f, a, b| qk31_quadrature(f, a, b),
+            |f, a, b| qk41_quadrature(f, a, b),
+            |f, a, b| qk51_quadrature(f, a, b),
+            |f, a, b| qk61_quadrature(f, a, b),
+        ];
+        // The width is the one of the representable interval, 3.125, and x - a is exact. The
+        // abscissae are rounded to multiples of ulp = 0.125, so the error of the integral of
+        // x - a is up to ulp / 2 times the width if all of them are shifted together by the
+        // rounding of the centre, while only the centre itself is left unpaired when they are
+        // computed relative to the bounds.
+        let a = 1.0e15;
+        let b = a + PI;
+        let width = b - a;
+        let ulp = 0.125;
+        for rule in rules {
+            let f: Integrand = Box::new(move |x: f64| {
+                assert!((a..=b).contains(&x));
+                array![1.0, x - a]
+            });
+            let (res, _, _) = rule(f, a, b);
+            let (res_shifted, _, _) = rule(Box::new(|t: f64| array![1.0, t]), 0.0, width);
+            assert!((res[0] - width).abs() < 1.0e-14);
+            assert!((res[1] - res_shifted[1]).abs() < 0.1 * ulp * width);
+        }
+    }
+
+    #[test]
+    fn single_precision() {
+        let (res, abserr, round_error) = qk_quadrature(
+            |x: f64| array![x.exp() * x.cos(), 1.0 / (1.0 + x * x)],
+            0.0,
+            1.0,
+            &XGK21,
+            &WGK21,
+            &WG21,
+        );
+        let (res32, abserr32, round_error32) = qk_quadrature(
+            |x: f32| array![x.exp() * x.cos(), 1.0 / (1.0 + x * x)],
+            0.0_f32,
+            1.0,
+            &XGK21,
+            &WGK21,
+            &WG21,
+        );
+        let correct_result = [
+            0.5 * (1.0_f64.exp() * (1.0_f64.cos() + 1.0_f64.sin()) - 1.0),
+            0.25 * PI,
+        ];
+        for k in 0..2 {
+            assert!((res[k] - correct_result[k]).abs() < 1.0e-15);
+            assert!((res32[k] as f64 - res[k]).abs() < 4.0 * f32::EPSILON as f64 * res[k].abs());
+        }
+        // The error estimate is dominated by the roundoff of single precision.
+        assert!(abserr < 1.0e-13);
+        assert!(round_error32 as f64 > round_error * 1.0e8);
+        assert!(abserr32 >= round_error32);
+    }
 }
@@ -1,4 +1,5 @@
-use crate::qk::qk_quadrature;
+use crate::qk::{qk_quadrature, qk_quadrature_with_gauss};
+use crate::rules::{WG21, WGK21, XGK21};
 use ndarray::Array1;
 /// Gauss-Kronrod 10-21 points quadrature with error estimate.
 pub fn qk21_quadrature<F>(f: F, a: f64, b: f64) -> (Array1<f64>, f64, f64)
@@ -7,38 +8,10 @@ where
 {
     qk_quadrature(f, a, b, &XGK21, &WGK21, &WG21)
 }
-
-const XGK21: [f64; 10] = [
-    0.995657163025808080735527280689003,
-    0.973906528517171720077964012084452,
-    0.930157491355708226001207180059508,
-    0.865063366688984510732096688423493,
-    0.780817726586416897063717578345042,
-    0.679409568299024406234327365114874,
-    0.562757134668604683339000099272694,
-    0.433395394129247190799265943165784,
-    0.294392862701460198131126603103866,
-    0.148874338981631210884826001129720,
-];
-
-const WGK21: [f64; 11] = [
-    0.011694638867371874278064396062192,
-    0.032558162307964727478818972459390,
-    0.054755896574351996031381300244580,
-    0.075039674810919952767043140916190,
-    0.093125454583697605535065465083366,
-    0.109387158802297641899210590325805,
-    0.123491976262065851077958109831074,
-    0.134709217311473325928054001771707,
-    0.142775938577060080797094273138717,
-    0.147739104901338491374841515972068,
-    0.149445554002916905664936468389821,
-];
-
-const WG21: [f64; 5] = [
-    0.066671344308688137593568809893332,
-    0.149451349150580593145776339657697,
-    0.219086362515982043995534934228163,
-    0.269266719309996355091226921569469,
-    0.295524224714752870173892994651338,
-];
+/// Gauss-Kronrod 10-21 points quadrature with error estimate and Gauss estimate.
+pub fn qk21_quadrature_with_gauss<F>(f: F, a: f64, b: f64) -> (Array1<f64>, f64, f64, Array1<f64>)
+where
+    F: Fn(f64) -> Array1<f64>,
+{
+    qk_quadrature_with_gauss(f, a, b, &XGK21, &WGK21, &WG21)
+}
@@ -0,0 +1,113 @@
+use crate::constants::{EPMACH, ROUNDOFF_FACTOR, UFLOW};
+use crate::qk::abscissae;
+use crate::rules::{WG61, WGK61, XGK61};
+pub use wide::f64x4;
+/// Gauss-Kronrod 30-61 points quadrature with error estimate of a function with 4 components.
+///
+/// It computes the same quantities of [qk61_quadrature](crate::qk61::qk61_quadrature), but the
+/// components are packed in a [f64x4] and the Kronrod sums are accumulated with SIMD
+/// instructions on the stable toolchain.
+pub fn qk61_4vec_quadrature<F>(f: F, a: f64, b: f64) -> (f64x4, f64, f64)
+where
+    F: Fn(f64) -> f64x4,
+{
+    #[cfg(feature = "profiling")]
+    crate::profiling::count_evaluations(61);
+    let hlgth: f64 = 0.5 * (b - a);
+    let dhlgth: f64 = hlgth.abs();
+    let (centr, abscissa) = abscissae(a, b);
+    let fc = f(centr);
+    let mut fv1 = [f64x4::ZERO; 30];
+    let mut fv2 = [f64x4::ZERO; 30];
+    let mut resg = f64x4::ZERO;
+    let mut resk = fc * WGK61[30];
+    let mut resabs = resk.abs();
+
+    for j in 1..16 {
+        let jtw1 = 2 * j - 1;
+        let jtw2 = 2 * j;
+
+        let (x11, x21) = abscissa(XGK61[jtw1 - 1]);
+        let (x12, x22) = abscissa(XGK61[jtw2 - 1]);
+
+        let f11 = f(x11);
+        let f12 = f(x12);
+        let f21 = f(x21);
+        let f22 = f(x22);
+
+        fv1[jtw1 - 1] = f11;
+        fv1[jtw2 - 1] = f12;
+        fv2[jtw1 - 1] = f21;
+        fv2[jtw2 - 1] = f22;
+
+        resabs +=
+            (f11.abs() + f21.abs()) * WGK61[jtw1 - 1] + (f12.abs() + f22.abs()) * WGK61[jtw2 - 1];
+
+        let fsum1 = f11 + f21;
+        let fsum2 = f12 + f22;
+
+        resg += fsum2 * WG61[j - 1];
+        resk += fsum1 * WGK61[jtw1 - 1];
+        resk += fsum2 * WGK61[jtw2 - 1];
+    }
+
+    let reskh = resk * 0.5;
+
+    let mut resasc = (fc - reskh).abs() * WGK61[30];
+
+    for j in 0..30 {
+        resasc += ((fv1[j] - reskh).abs() + (fv2[j] - reskh).abs()) * WGK61[j];
+    }
+
+    let result = resk * hlgth;
+
+    resabs = resabs * dhlgth;
+    resasc = resasc * dhlgth;
+
+    let diff = (resk - resg) * hlgth;
+    let mut abserr = (diff * diff).reduce_add().sqrt();
+    let resabs_scalar = (resabs * resabs).reduce_add().sqrt();
+    let resasc_scalar = (resasc * resasc).reduce_add().sqrt();
+
+    if resasc_scalar != 0.0 && abserr != 0.0 {
+        abserr = resasc_scalar * 1.0_f64.min((200.0 * abserr / resasc_scalar).powf(1.5));
+    }
+
+    let round_error = ROUNDOFF_FACTOR * EPMACH * resabs_scalar;
+
+    if round_error > UFLOW {
+        abserr = abserr.max(round_error);
+    }
+
+    (result, abserr, round_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qk61::qk61_quadrature;
+    use crate::qk61_simd::{f64x4, qk61_4vec_quadrature};
+    use ndarray::array;
+
+    #[test]
+    fn scalar_parity() {
+        let f = |x: f64| [x.sin(), x.cos(), (-x * x).exp(), 1.0 / (1.0 + x * x)];
+        for (a, b) in [(0.0, 1.0), (-3.0, 2.0), (0.0, 100.0)] {
+            let (res_simd, abserr_simd, rounderr_simd) =
+                qk61_4vec_quadrature(|x: f64| f64x4::from(f(x)), a, b);
+            let (res, abserr, rounderr) = qk61_quadrature(
+                |x: f64| {
+                    let y = f(x);
+                    array![y[0], y[1], y[2], y[3]]
+                },
+                a,
+                b,
+            );
+            let res_simd = res_simd.to_array();
+            for k in 0..4 {
+                assert!((res_simd[k] - res[k]).abs() <= 1.0e-14 * res[k].abs().max(1.0));
+            }
+            assert!((abserr_simd - abserr).abs() <= 1.0e-12 * abserr);
+            assert!((rounderr_simd - rounderr).abs() <= 1.0e-12 * rounderr);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(doc)]
+use crate::qag::Qag;
+
+use crate::errors::QagError;
+use crate::monotone_quad::MonotoneResult;
+use crate::patterson_quad::PattersonResult;
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::Array1;
+/// Result of any of the integrators, so that code can be generic over the integrator used.
+///
+/// Every variant carries the native result of its integrator, while the accessors give the
+/// quantities common to all of them.
+#[must_use]
+#[derive(Debug, Clone)]
+pub enum QuadIntegratorResult {
+    /// Result of [integrate](Qag::integrate).
+    Qag(QagIntegrationResult),
+    /// Result of [integrate](crate::monotone_quad::MonotoneQuad::integrate).
+    Monotone(MonotoneResult),
+    /// Result of [integrate](crate::patterson_quad::PattersonQuad::integrate).
+    Patterson(PattersonResult),
+}
+
+impl QuadIntegratorResult {
+    pub fn new_qag(result: QagIntegrationResult) -> Self {
+        Self::Qag(result)
+    }
+
+    pub fn new_monotone(result: MonotoneResult) -> Self {
+        Self::Monotone(result)
+    }
+
+    pub fn new_patterson(result: PattersonResult) -> Self {
+        Self::Patterson(result)
+    }
+
+    /// Integration result.
+    pub fn value(&self) -> &Array1<f64> {
+        match self {
+            Self::Qag(res) => &res.result,
+            Self::Monotone(res) => &res.result,
+            Self::Patterson(res) => &res.result,
+        }
+    }
+
+    /// Estimate of the absolute error.
+    pub fn abserr(&self) -> f64 {
+        match self {
+            Self::Qag(res) => res.abserr,
+            Self::Monotone(res) => res.abserr,
+            Self::Patterson(res) => res.abserr,
+        }
+    }
+
+    /// Number of evaluations of the integrand, if available (for [Qag] only with
+    /// [more_info](Qag::more_info)).
+    pub fn neval(&self) -> Option<usize> {
+        match self {
+            Self::Qag(res) => res.more_info.as_ref().map(|more_info| more_info.neval),
+            Self::Monotone(res) => Some(res.neval),
+            Self::Patterson(res) => Some(res.neval),
+        }
+    }
+
+    /// Ok if the requested tolerance has been achieved, otherwise the error condition met while
+    /// the best estimate was returned anyway.
+    pub fn state(&self) -> Result<(), QagError> {
+        match self {
+            Self::Qag(res) => match &res.failure {
+                Some(failure) => Err(failure.clone()),
+                None => Ok(()),
+            },
+            Self::Monotone(_) | Self::Patterson(_) => Ok(()),
+        }
+    }
+}
+
+impl From<QagIntegrationResult> for QuadIntegratorResult {
+    fn from(result: QagIntegrationResult) -> Self {
+        Self::new_qag(result)
+    }
+}
+
+impl From<MonotoneResult> for QuadIntegratorResult {
+    fn from(result: MonotoneResult) -> Self {
+        Self::new_monotone(result)
+    }
+}
+
+impl From<PattersonResult> for QuadIntegratorResult {
+    fn from(result: PattersonResult) -> Self {
+        Self::new_patterson(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::errors::QagError;
+    use crate::monotone_quad::MonotoneQuad;
+    use crate::patterson_quad::PattersonQuad;
+    use crate::qag::{LimitBehavior, Qag};
+    use crate::qag_integration_result::QagIntegrationResult;
+    use crate::quad_integrator_result::QuadIntegratorResult;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn accessors() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            limit_behavior: LimitBehavior::ReturnBest,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sin(), x.powf(-0.5)]),
+        };
+
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let generic = QuadIntegratorResult::from(res.clone());
+        assert_eq!(generic.value(), &res.result);
+        assert_eq!(generic.abserr(), res.abserr);
+        assert_eq!(generic.neval(), Some(res.more_info.unwrap().neval));
+        assert_eq!(generic.state(), Ok(()));
+
+        let qag = Qag { limit: 2, ..qag };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let generic = QuadIntegratorResult::new_qag(res);
+        assert_eq!(generic.state(), Err(QagError::MaxIteration));
+
+        let g = FnVec {
+            components: Arc::new(|x: f64| array![x.exp()]),
+        };
+        let res = MonotoneQuad { limit: 10000 }
+            .integrate(&g, 0.0, 1.0, 0.0, 1.0e-3)
+            .unwrap();
+        let generic = QuadIntegratorResult::from(res.clone());
+        assert_eq!(generic.value(), &res.result);
+        assert_eq!(generic.neval(), Some(res.neval));
+        assert_eq!(generic.state(), Ok(()));
+
+        let res = PattersonQuad { max_points: 63 }
+            .integrate(&g, 0.0, 1.0, 1.0e-10, 0.0)
+            .unwrap();
+        let generic = QuadIntegratorResult::from(res.clone());
+        assert_eq!(generic.value(), &res.result);
+        assert_eq!(generic.neval(), Some(res.neval));
+        assert_eq!(generic.state(), Ok(()));
+    }
+
+    #[test]
+    fn failures() {
+        let res = QagIntegrationResult::new(array![1.0, 2.0], 1.0e-12);
+        let failures = [
+            QagError::Invalid,
+            QagError::MaxIteration,
+            QagError::BadTolerance,
+            QagError::BadFunction,
+            QagError::Diverge,
+            QagError::MissingInterval { a: 0.0, b: 1.0 },
+            QagError::Panicked("panic".to_string()),
+            QagError::NotMonotone { x: 0.5 },
+            QagError::EmptyIntegrand,
+        ];
+        for failure in failures {
+            let mut res = res.clone();
+            res.failure = Some(failure.clone());
+            let generic = QuadIntegratorResult::from(res);
+            assert_eq!(generic.state(), Err(failure.clone()));
+            let error: Box<dyn std::error::Error> = generic.state().unwrap_err().into();
+            assert_eq!(error.to_string(), failure.to_string());
+        }
+    }
+}
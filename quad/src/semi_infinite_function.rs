@@ -1,32 +1,168 @@
 use crate::constants::*;
+use crate::qag::Values;
 use ndarray::Array1;
 /// Transform the function in case of semi-infinite interval.
 ///
 /// For an interval (start,+∞) integrand is transformed using the transformation x = start + (1-t)/t.
 /// For an interval (-∞,start) integrand is transformed using the transformation x = start - (1-t)/t.
+///
+/// In both cases t runs over (0,1) and the value returned at t = 'x' is f(x(t)) |dx/dt|, with
+/// the Jacobian |dx/dt| = 1/t^2, so that the integral over (0,1) is the one over the ordered
+/// interval. Below t = sqrt([UFLOW]), where x(t) overflows, zero is returned.
 pub fn semi_infinite_function<F>(f: &F, x: f64, start: f64, infty: f64) -> Array1<f64>
 where
     F: Fn(f64) -> Array1<f64> + ?Sized,
 {
+    semi_infinite_values(f, x, start, infty)
+}
+/// Same as [semi_infinite_function] for any [Values].
+fn semi_infinite_values<V, F>(f: &F, x: f64, start: f64, infty: f64) -> V
+where
+    V: Values,
+    F: Fn(f64) -> V + ?Sized,
+{
+    let sgn = if infty.is_sign_positive() { 1.0 } else { -1.0 };
     if x < UFLOW.sqrt() {
-        return Array1::<f64>::zeros(f(0.0).len());
+        return V::zeros(f(start + sgn).len());
     }
-    let sgn = if infty.is_sign_positive() { 1.0 } else { -1.0 };
     let z = start + sgn * (1.0 - x) / x;
-    let res: Array1<f64> = f(z);
-    res / (sgn * x * x)
+    f(z).divided(x * x)
 }
 /// Transform the function in case of infinite interval.
 ///
-/// For an interval (-∞,+∞) integrand is transformed using the transformation x = (1-t)/t.
+/// For an interval (-∞,+∞) integrand is transformed using the transformation x = (1-|t|)/t.
+///
+/// t runs over (-1,1), with t > 0 covering x > 0, and the value returned at t = 'x' is
+/// f(x(t)) / t^2, the Jacobian being |dx/dt| = 1/t^2. Below |t| = sqrt([UFLOW]) zero is
+/// returned.
 pub fn double_infinite_function<F>(f: &F, x: f64) -> Array1<f64>
 where
     F: Fn(f64) -> Array1<f64> + ?Sized,
+{
+    double_infinite_values(f, x)
+}
+/// Same as [double_infinite_function] for any [Values].
+fn double_infinite_values<V, F>(f: &F, x: f64) -> V
+where
+    V: Values,
+    F: Fn(f64) -> V + ?Sized,
 {
     if x.abs() < UFLOW.sqrt() {
-        return Array1::<f64>::zeros(f(0.0).len());
+        return V::zeros(f(0.0).len());
     }
     let z = (1.0 - x.abs()) / x;
-    let res: Array1<f64> = f(z);
-    res / (x * x)
+    f(z).divided(x * x)
+}
+/// Integrand on a finite interval equivalent to 'f' over ('a', 'b'), to inspect the function
+/// actually integrated by [integrate](crate::qag::Qag::integrate).
+///
+/// It returns the transformed integrand and its bounds: (0,1) with [semi_infinite_function] for
+/// a semi-infinite interval, (-1,1) with [double_infinite_function] for (-∞,+∞), while a finite
+/// interval is returned unchanged. The bounds are expected with a <= b.
+pub fn transformed_integrand<F>(f: F, a: f64, b: f64) -> (impl Fn(f64) -> Vec<f64>, f64, f64)
+where
+    F: Fn(f64) -> Vec<f64>,
+{
+    let g = move |x: f64| Array1::from(f(x));
+    let (lo, hi) = match (a.is_finite(), b.is_finite()) {
+        (true, true) => (a, b),
+        (false, false) => (-1.0, 1.0),
+        _ => (0.0, 1.0),
+    };
+    let transformed = move |t: f64| {
+        let res = if b == f64::INFINITY && a.is_finite() {
+            semi_infinite_function(&g, t, a, b)
+        } else if a == f64::NEG_INFINITY && b.is_finite() {
+            semi_infinite_function(&g, t, b, a)
+        } else if a == f64::NEG_INFINITY && b == f64::INFINITY {
+            double_infinite_function(&g, t)
+        } else {
+            g(t)
+        };
+        res.to_vec()
+    };
+    (transformed, lo, hi)
+}
+/// Transformed function and interval in case of semi-infinite or infinite interval.
+///
+/// The function is transformed with [semi_infinite_function] on (0,1) or with
+/// [double_infinite_function] on (-1,1), for an integrand returning any [Values]. Returns [None]
+/// if the interval is finite.
+pub(crate) fn infinite_integrand<'a, V: Values>(
+    f: &'a (dyn Fn(f64) -> V + Sync),
+    a: f64,
+    b: f64,
+) -> Option<(impl Fn(f64) -> V + Sync + 'a, f64, f64)> {
+    let (half_line, a2) = if b == f64::INFINITY && a.is_finite() {
+        (Some((a, b)), 0.0)
+    } else if a == f64::NEG_INFINITY && b.is_finite() {
+        (Some((b, a)), 0.0)
+    } else if a == f64::NEG_INFINITY && b == f64::INFINITY {
+        (None, -1.0)
+    } else {
+        return None;
+    };
+    let f2 = move |x: f64| match half_line {
+        Some((start, infty)) => semi_infinite_values(f, x, start, infty),
+        None => double_infinite_values(f, x),
+    };
+    Some((f2, a2, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use crate::semi_infinite_function::transformed_integrand;
+    use ndarray::Array1;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn transformed() {
+        let qag = Qag {
+            limit: 10000,
+            ..Default::default()
+        };
+        let f = |x: f64| vec![1.0 / (1.0 + x * x), (-x * x).exp()];
+        let cases = [
+            (0.0, f64::INFINITY, (0.0, 1.0), [0.5 * PI, 0.5 * PI.sqrt()]),
+            (
+                f64::NEG_INFINITY,
+                0.0,
+                (0.0, 1.0),
+                [0.5 * PI, 0.5 * PI.sqrt()],
+            ),
+            (
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                (-1.0, 1.0),
+                [PI, PI.sqrt()],
+            ),
+            (-1.0, 1.0, (-1.0, 1.0), [0.5 * PI, 1.493648265624854]),
+        ];
+        for (a, b, bounds, correct) in cases {
+            let (g, lo, hi) = transformed_integrand(f, a, b);
+            assert_eq!((lo, hi), bounds);
+            let g = FnVec {
+                components: Arc::new(|t: f64| Array1::from(g(t))),
+            };
+            let res = qag.integrate(&g, lo, hi, 1.0e-12, 0.0).unwrap();
+            let res_direct = qag
+                .integrate(
+                    &FnVec {
+                        components: Arc::new(|x: f64| Array1::from(f(x))),
+                    },
+                    a,
+                    b,
+                    1.0e-12,
+                    0.0,
+                )
+                .unwrap();
+            assert_eq!(res.result, res_direct.result);
+            for (x, y) in res.result.iter().zip(correct) {
+                assert!((x - y).abs() < 1.0e-12);
+            }
+        }
+    }
 }
@@ -0,0 +1,264 @@
+//! Battery of classic test integrands with their analytic values.
+//!
+//! It contains the one-dimensional Genz functions (oscillatory, product peak, corner peak,
+//! Gaussian, continuous and discontinuous) together with endpoint singularities and a highly
+//! oscillatory integrand, in order to share a common set of hard integrals across tests and
+//! benchmarks.
+use crate::constants::FnVec;
+#[cfg(test)]
+use crate::qag::{Qag, Reparam};
+#[cfg(test)]
+use crate::qag_integration_result::QagIntegrationResult;
+use ndarray::{array, Array1};
+use std::f64::consts::PI;
+use std::sync::Arc;
+/// Test integrand on the interval (a,b) with its analytic integral 'correct_result'.
+#[derive(Clone)]
+pub struct TestFunction {
+    pub name: &'static str,
+    pub f: FnVec<'static>,
+    pub a: f64,
+    pub b: f64,
+    pub correct_result: Array1<f64>,
+}
+
+impl TestFunction {
+    pub fn new<F>(name: &'static str, f: F, a: f64, b: f64, correct_result: Array1<f64>) -> Self
+    where
+        F: Fn(f64) -> Array1<f64> + Send + Sync + 'static,
+    {
+        Self {
+            name,
+            f: FnVec {
+                components: Arc::new(f),
+            },
+            a,
+            b,
+            correct_result,
+        }
+    }
+}
+/// Genz oscillatory function cos(2πu + cx) on (0,1).
+pub fn genz_oscillatory() -> TestFunction {
+    let (u, c) = (0.3, 5.0);
+    TestFunction::new(
+        "genz_oscillatory",
+        move |x: f64| array![(2.0 * PI * u + c * x).cos()],
+        0.0,
+        1.0,
+        array![((2.0 * PI * u + c).sin() - (2.0 * PI * u).sin()) / c],
+    )
+}
+/// Genz product peak function 1/(c^(-2) + (x-w)^2) on (0,1).
+pub fn genz_product_peak() -> TestFunction {
+    let (c, w) = (10.0_f64, 0.3);
+    TestFunction::new(
+        "genz_product_peak",
+        move |x: f64| array![1.0 / (c.powi(-2) + (x - w).powi(2))],
+        0.0,
+        1.0,
+        array![c * ((c * (1.0 - w)).atan() + (c * w).atan())],
+    )
+}
+/// Genz corner peak function (1+cx)^(-2) on (0,1).
+pub fn genz_corner_peak() -> TestFunction {
+    let c = 5.0;
+    TestFunction::new(
+        "genz_corner_peak",
+        move |x: f64| array![(1.0 + c * x).powi(-2)],
+        0.0,
+        1.0,
+        array![1.0 / (1.0 + c)],
+    )
+}
+/// Genz Gaussian function exp(-c^2 (x-w)^2) on (0,1).
+///
+/// The peak is narrow enough that the tails outside (0,1) are below machine precision.
+pub fn genz_gaussian() -> TestFunction {
+    let (c, w) = (25.0, 0.5);
+    TestFunction::new(
+        "genz_gaussian",
+        move |x: f64| array![(-(c * (x - w)).powi(2)).exp()],
+        0.0,
+        1.0,
+        array![PI.sqrt() / c],
+    )
+}
+/// Genz continuous function exp(-c|x-w|) on (0,1), with a kink at w.
+pub fn genz_continuous() -> TestFunction {
+    let (c, w) = (5.0_f64, 0.5);
+    TestFunction::new(
+        "genz_continuous",
+        move |x: f64| array![(-c * (x - w).abs()).exp()],
+        0.0,
+        1.0,
+        array![(2.0 - (-c * w).exp() - (-c * (1.0 - w)).exp()) / c],
+    )
+}
+/// Genz discontinuous function exp(cx) for x <= w, 0 otherwise, on (0,1).
+pub fn genz_discontinuous() -> TestFunction {
+    let (c, w) = (5.0_f64, 0.3);
+    TestFunction::new(
+        "genz_discontinuous",
+        move |x: f64| array![if x <= w { (c * x).exp() } else { 0.0 }],
+        0.0,
+        1.0,
+        array![((c * w).exp() - 1.0) / c],
+    )
+}
+/// Corner singularity x^(-1/2) on (0,1).
+pub fn corner_singularity() -> TestFunction {
+    TestFunction::new(
+        "corner_singularity",
+        |x: f64| array![x.powf(-0.5)],
+        0.0,
+        1.0,
+        array![2.0],
+    )
+}
+/// Logarithmic singularity log(x) on (0,1).
+pub fn log_singularity() -> TestFunction {
+    TestFunction::new(
+        "log_singularity",
+        |x: f64| array![x.ln()],
+        0.0,
+        1.0,
+        array![-1.0],
+    )
+}
+/// Highly oscillatory function sin(100x) on (0,1).
+pub fn oscillatory() -> TestFunction {
+    TestFunction::new(
+        "oscillatory",
+        |x: f64| array![(100.0 * x).sin()],
+        0.0,
+        1.0,
+        array![(1.0 - 100.0_f64.cos()) / 100.0],
+    )
+}
+/// All the test functions.
+pub fn battery() -> Vec<TestFunction> {
+    vec![
+        genz_oscillatory(),
+        genz_product_peak(),
+        genz_corner_peak(),
+        genz_gaussian(),
+        genz_continuous(),
+        genz_discontinuous(),
+        corner_singularity(),
+        log_singularity(),
+        oscillatory(),
+    ]
+}
+/// Integrands exercising the transformations shared by [integrate](Qag::integrate) and the
+/// other integrators, each with the [Qag] enabling its transformation.
+///
+/// They are meant to be integrated with 'epsabs' = 0 and 'epsrel' = 1e-8, and checked with
+/// [assert_transform_case].
+#[cfg(test)]
+pub(crate) fn transform_cases() -> Vec<(Qag, TestFunction)> {
+    vec![
+        (
+            Qag::default(),
+            TestFunction::new(
+                "reversed",
+                |x: f64| array![x * x, x.cos()],
+                1.0,
+                0.0,
+                array![-1.0 / 3.0, -1.0_f64.sin()],
+            ),
+        ),
+        (
+            Qag::builder().points(vec![0.3, 2.0]).build(),
+            TestFunction::new(
+                "points",
+                |x: f64| array![(x - 0.3).abs(), x],
+                0.0,
+                1.0,
+                array![0.29, 0.5],
+            ),
+        ),
+        (
+            Qag::builder().removable_singularity(0.5).build(),
+            TestFunction::new(
+                "removable_singularities",
+                |x: f64| array![(x - 0.5).sin() / (x - 0.5)],
+                0.0,
+                1.0,
+                array![0.9862148360861335],
+            ),
+        ),
+        (
+            Qag::builder()
+                .limit(10000)
+                .norm_weights(vec![1.0, 1.0e24])
+                .build(),
+            TestFunction::new(
+                "norm_weights",
+                |x: f64| array![x * x, 1.0e-12 / (1.0e-4 + (x - 0.3) * (x - 0.3))],
+                0.0,
+                1.0,
+                array![1.0 / 3.0, 1.0e-10 * (70.0_f64.atan() + 30.0_f64.atan())],
+            ),
+        ),
+        (
+            Qag::builder().reparam(Reparam::Log).build(),
+            TestFunction::new(
+                "reparam",
+                |x: f64| array![1.0 / (x * (1.0 + x.ln() * x.ln()))],
+                (-20.0_f64).exp(),
+                20.0_f64.exp(),
+                array![2.0 * 20.0_f64.atan()],
+            ),
+        ),
+        (
+            Qag::default(),
+            TestFunction::new(
+                "infinite",
+                |x: f64| array![(-x).exp(), 1.0 / (1.0 + x * x)],
+                0.0,
+                f64::INFINITY,
+                array![1.0, 0.5 * PI],
+            ),
+        ),
+        (
+            Qag::builder().record_point_values(true).build(),
+            TestFunction::new(
+                "record_point_values",
+                |x: f64| array![x.exp()],
+                0.0,
+                1.0,
+                array![1.0_f64.exp() - 1.0],
+            ),
+        ),
+    ]
+}
+/// Asserts that 'res' is the result of a case of [transform_cases], with every component within
+/// a relative error of 1e-6 and the output of its transformation.
+#[cfg(test)]
+pub(crate) fn assert_transform_case(qag: &Qag, test: &TestFunction, res: &QagIntegrationResult) {
+    assert_eq!(res.result.len(), test.correct_result.len(), "{}", test.name);
+    for (x, correct) in res.result.iter().zip(test.correct_result.iter()) {
+        let rel_err = (x - correct).abs() / correct.abs();
+        assert!(rel_err < 1.0e-6, "{}: {} != {}", test.name, x, correct);
+    }
+    assert_transform_output(qag, test, res);
+}
+/// Same as [assert_transform_case], without checking the result.
+#[cfg(test)]
+pub(crate) fn assert_transform_output(qag: &Qag, test: &TestFunction, res: &QagIntegrationResult) {
+    let (a, b) = (test.a.min(test.b), test.a.max(test.b));
+    let ignored_points: Vec<f64> = qag
+        .points
+        .iter()
+        .copied()
+        .filter(|p| !(a..=b).contains(p))
+        .collect();
+    assert_eq!(res.ignored_points, ignored_points, "{}", test.name);
+    assert_eq!(
+        res.point_values.is_some(),
+        qag.record_point_values,
+        "{}",
+        test.name
+    );
+}
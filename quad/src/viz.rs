@@ -0,0 +1,123 @@
+use crate::qag_integration_result::MoreInfo;
+use std::io::{self, Write};
+/// Width in pixels of the image drawn by [to_svg](MoreInfo::to_svg).
+pub const SVG_WIDTH: f64 = 800.0;
+/// Height in pixels of the image drawn by [to_svg](MoreInfo::to_svg).
+pub const SVG_HEIGHT: f64 = 100.0;
+
+impl MoreInfo {
+    /// Final sub-intervals (left, right, err), sorted by their left end.
+    fn sorted_intervals(&self) -> Vec<(f64, f64, f64)> {
+        let mut intervals: Vec<_> = self
+            .heap
+            .iter()
+            .map(|item| (item.interval.0, item.interval.1, item.err))
+            .collect();
+        intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+        intervals
+    }
+
+    /// Write the final subdivision as CSV, with a header and a row 'left,right,width,abserr' per
+    /// sub-interval, sorted by the left end.
+    ///
+    /// For infinite ranges the intervals refer to the transformed variable.
+    pub fn to_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "left,right,width,abserr")?;
+        for (a, b, err) in self.sorted_intervals() {
+            writeln!(writer, "{},{},{},{}", a, b, b - a, err)?;
+        }
+        Ok(())
+    }
+
+    /// Draw the final subdivision as an SVG image of [SVG_WIDTH] x [SVG_HEIGHT] pixels.
+    ///
+    /// Every sub-interval is a rectangle spanning its share of the width, colored from blue to
+    /// red with the logarithm of its error estimate, between the smallest and the largest
+    /// non-zero ones.
+    pub fn to_svg<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let intervals = self.sorted_intervals();
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+            SVG_WIDTH, SVG_HEIGHT
+        )?;
+        if let (Some(first), Some(last)) = (intervals.first(), intervals.last()) {
+            let (lo, hi) = (first.0, last.1);
+            let log_errors = intervals
+                .iter()
+                .filter(|interval| interval.2 > 0.0)
+                .map(|interval| interval.2.log10());
+            let min = log_errors.clone().fold(f64::INFINITY, f64::min);
+            let max = log_errors.fold(f64::NEG_INFINITY, f64::max);
+            for (a, b, err) in intervals {
+                let x = SVG_WIDTH * (a - lo) / (hi - lo);
+                let width = SVG_WIDTH * (b - a) / (hi - lo);
+                let t = if err > 0.0 && max > min {
+                    (err.log10() - min) / (max - min)
+                } else {
+                    0.0
+                };
+                let red = (255.0 * t).round() as u8;
+                writeln!(
+                    writer,
+                    r#"<rect x="{}" y="0" width="{}" height="{}" fill="rgb({},0,{})" stroke="black" stroke-width="0.2"><title>({}, {}): {}</title></rect>"#,
+                    x,
+                    width,
+                    SVG_HEIGHT,
+                    red,
+                    255 - red,
+                    a,
+                    b,
+                    err
+                )?;
+            }
+        }
+        writeln!(writer, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::FnVec;
+    use crate::qag::Qag;
+    use ndarray::array;
+    use std::sync::Arc;
+
+    #[test]
+    fn csv_svg() {
+        let qag = Qag {
+            limit: 10000,
+            more_info: true,
+            ..Default::default()
+        };
+        let f = FnVec {
+            components: Arc::new(|x: f64| array![x.sqrt()]),
+        };
+        let res = qag.integrate(&f, 0.0, 1.0, 1.0e-10, 0.0).unwrap();
+        let more_info = res.more_info.unwrap();
+
+        let mut csv = vec![];
+        more_info.to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("left,right,width,abserr"));
+        let rows: Vec<Vec<f64>> = lines
+            .map(|line| line.split(',').map(|x| x.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), more_info.heap.len());
+        assert_eq!(rows[0][0], 0.0);
+        assert_eq!(rows[rows.len() - 1][1], 1.0);
+        for w in rows.windows(2) {
+            assert_eq!(w[0][1], w[1][0]);
+        }
+        let total_err: f64 = rows.iter().map(|row| row[3]).sum();
+        assert!(total_err <= 1.0e-10);
+
+        let mut svg = vec![];
+        more_info.to_svg(&mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<rect").count(), more_info.heap.len());
+    }
+}